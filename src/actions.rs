@@ -0,0 +1,504 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::fuzzy::{FuzzyList, TieBreak};
+use crate::keys::KeyChord;
+
+/// Something cltree can do, run by a key after the command prefix or from the palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    OpenPalette,
+    ToggleTreeFocus,
+    ShowChanges,
+    FindFile,
+    TogglePreview,
+    DiffFile,
+    CycleSort,
+    ToggleDirsFirst,
+    FilterTree,
+    ToggleGitignored,
+    ToggleColumns,
+    ExpandAll,
+    CollapseAll,
+    ScrollPreviewDown,
+    ScrollPreviewUp,
+    ToggleHidden,
+    DeeperTree,
+    ShallowerTree,
+    GrowTree,
+    ShrinkTree,
+    CycleZoom,
+    ToggleStatusBar,
+    SearchScrollback,
+    CopyLastOutput,
+    RestartSession,
+    Interrupt,
+    Terminate,
+    Kill,
+    ToggleSuspend,
+}
+
+/// A registered action: its palette name, its label in the key hints, the
+/// keys that run it after the prefix and the keys that run it in tree focus.
+#[derive(Debug, Clone)]
+pub struct ActionEntry {
+    pub action: Action,
+    pub name: &'static str,
+    pub hint: &'static str,
+    pub keys: Vec<char>,
+    pub tree_keys: Vec<char>,
+}
+
+/// Every action cltree offers, in the order the palette and hint list them.
+#[derive(Debug, Clone, Default)]
+pub struct ActionRegistry {
+    entries: Vec<ActionEntry>,
+}
+
+/// An action with its palette name, hint label, prefix keys and tree-focus keys.
+type Builtin = (
+    Action,
+    &'static str,
+    &'static str,
+    &'static [char],
+    &'static [char],
+);
+
+/// Built-in actions, grouped by feature.
+const BUILTIN: &[Builtin] = &[
+    (
+        Action::OpenPalette,
+        "Command palette",
+        "palette",
+        &[':', ' '],
+        &[],
+    ),
+    // File tree
+    (
+        Action::ToggleTreeFocus,
+        "Toggle tree focus",
+        "tree",
+        &['f'],
+        &[],
+    ),
+    (
+        Action::ShowChanges,
+        "Files changed this session",
+        "changes",
+        &['s'],
+        &['s'],
+    ),
+    (
+        Action::FindFile,
+        "Find file",
+        "find file",
+        &['p', '/'],
+        &['/'],
+    ),
+    (
+        Action::TogglePreview,
+        "Toggle file preview",
+        "preview",
+        &['v'],
+        &['v'],
+    ),
+    (
+        Action::DiffFile,
+        "Diff file against baseline",
+        "diff",
+        &['d'],
+        &['d'],
+    ),
+    (Action::FilterTree, "Filter tree", "filter", &[], &['f']),
+    (
+        Action::ToggleGitignored,
+        "Toggle gitignored files",
+        "gitignored",
+        &[],
+        &['i'],
+    ),
+    (
+        Action::ToggleColumns,
+        "Toggle metadata columns",
+        "columns",
+        &[],
+        &['m'],
+    ),
+    (
+        Action::CycleSort,
+        "Change sort order",
+        "sort",
+        &['o'],
+        &['o'],
+    ),
+    (
+        Action::ToggleDirsFirst,
+        "Toggle directories first",
+        "mixed",
+        &[],
+        &['O'],
+    ),
+    (
+        Action::ExpandAll,
+        "Expand all directories",
+        "expand all",
+        &[],
+        &['E'],
+    ),
+    (
+        Action::CollapseAll,
+        "Collapse all directories",
+        "collapse all",
+        &[],
+        &['C'],
+    ),
+    (
+        Action::ScrollPreviewDown,
+        "Scroll preview down",
+        "preview down",
+        &[],
+        &['J'],
+    ),
+    (
+        Action::ScrollPreviewUp,
+        "Scroll preview up",
+        "preview up",
+        &[],
+        &['K'],
+    ),
+    (
+        Action::ToggleHidden,
+        "Toggle dotfiles",
+        "dotfiles",
+        &['.'],
+        &[],
+    ),
+    (
+        Action::DeeperTree,
+        "Tree depth +1",
+        "deeper",
+        &['+', '='],
+        &[],
+    ),
+    (
+        Action::ShallowerTree,
+        "Tree depth -1",
+        "shallower",
+        &['-'],
+        &[],
+    ),
+    // Layout
+    (Action::GrowTree, "Grow tree panel", "grow", &['<'], &[]),
+    (
+        Action::ShrinkTree,
+        "Shrink tree panel",
+        "shrink",
+        &['>'],
+        &[],
+    ),
+    (Action::CycleZoom, "Zoom pane", "zoom", &['x'], &[]),
+    (
+        Action::ToggleStatusBar,
+        "Toggle status bar",
+        "status bar",
+        &['b'],
+        &['b'],
+    ),
+    // Terminal and session
+    (
+        Action::SearchScrollback,
+        "Search scrollback",
+        "search",
+        &['['],
+        &[],
+    ),
+    (
+        Action::CopyLastOutput,
+        "Copy last output",
+        "copy",
+        &['y'],
+        &[],
+    ),
+    (
+        Action::RestartSession,
+        "Restart Claude",
+        "restart",
+        &['R'],
+        &[],
+    ),
+    // Process control
+    (
+        Action::Interrupt,
+        "Interrupt (SIGINT)",
+        "interrupt",
+        &['i', 'c'],
+        &[],
+    ),
+    (
+        Action::Terminate,
+        "Terminate (SIGTERM)",
+        "terminate",
+        &['t'],
+        &[],
+    ),
+    (Action::Kill, "Kill (SIGKILL)", "kill", &['k'], &[]),
+    (
+        Action::ToggleSuspend,
+        "Stop or resume (SIGSTOP/SIGCONT)",
+        "stop/resume",
+        &['z'],
+        &[],
+    ),
+];
+
+impl ActionRegistry {
+    /// A registry holding the built-in actions.
+    pub fn builtin() -> Self {
+        let mut registry = Self::default();
+        for &(action, name, hint, keys, tree_keys) in BUILTIN {
+            registry.register(action, name, hint, keys, tree_keys);
+        }
+        registry
+    }
+
+    /// Add `action`, run by any of `keys` after the prefix and by any of
+    /// `tree_keys` while the tree has focus. A key already bound to another
+    /// action moves to this one.
+    pub fn register(
+        &mut self,
+        action: Action,
+        name: &'static str,
+        hint: &'static str,
+        keys: &[char],
+        tree_keys: &[char],
+    ) {
+        for entry in &mut self.entries {
+            entry.keys.retain(|k| !keys.contains(k));
+            entry.tree_keys.retain(|k| !tree_keys.contains(k));
+        }
+        self.entries.retain(|e| e.action != action);
+        self.entries.push(ActionEntry {
+            action,
+            name,
+            hint,
+            keys: keys.to_vec(),
+            tree_keys: tree_keys.to_vec(),
+        });
+    }
+
+    pub fn entries(&self) -> &[ActionEntry] {
+        &self.entries
+    }
+
+    /// The action bound to a key pressed after the prefix.
+    pub fn lookup(&self, key: &KeyEvent) -> Option<Action> {
+        let KeyCode::Char(c) = key.code else {
+            return None;
+        };
+        self.entries
+            .iter()
+            .find(|e| e.keys.contains(&c))
+            .map(|e| e.action)
+    }
+
+    /// The action bound to a key pressed while the tree has focus. Control
+    /// and Alt chords are left to the tree's own keys, like Ctrl+D.
+    pub fn lookup_tree(&self, key: &KeyEvent) -> Option<Action> {
+        if key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            return None;
+        }
+        let KeyCode::Char(c) = key.code else {
+            return None;
+        };
+        self.entries
+            .iter()
+            .find(|e| e.tree_keys.contains(&c))
+            .map(|e| e.action)
+    }
+
+    /// `[f] tree  [s] changes  …`, one entry per action, for the command-mode status.
+    pub fn hints(&self) -> String {
+        self.entries
+            .iter()
+            .filter_map(|e| Some(format!("[{}] {}", key_label(*e.keys.first()?), e.hint)))
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
+
+    /// The same for the actions bound in tree focus.
+    pub fn tree_hints(&self) -> String {
+        self.entries
+            .iter()
+            .filter_map(|e| Some(format!("[{}] {}", key_label(*e.tree_keys.first()?), e.hint)))
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
+}
+
+fn key_label(key: char) -> String {
+    match key {
+        ' ' => "Space".to_string(),
+        key => key.to_string(),
+    }
+}
+
+/// A row of the palette.
+#[derive(Debug, Clone)]
+pub struct PaletteItem {
+    pub action: Action,
+    pub name: &'static str,
+    /// How to run the action without the palette, like `Ctrl+] f`.
+    pub binding: String,
+}
+
+/// State of the command palette popup: a fuzzy search over the registered actions.
+pub struct Palette {
+    items: Vec<PaletteItem>,
+    list: FuzzyList,
+}
+
+impl Palette {
+    /// List the actions in `registry`, showing their keys after `prefix`,
+    /// and `palette_key` for the palette itself when one is set.
+    pub fn new(registry: &ActionRegistry, prefix: KeyChord, palette_key: Option<KeyChord>) -> Self {
+        let items: Vec<PaletteItem> = registry
+            .entries()
+            .iter()
+            .map(|entry| {
+                let mut bindings: Vec<String> = entry
+                    .keys
+                    .iter()
+                    .map(|&k| format!("{prefix} {}", key_label(k)))
+                    .collect();
+                if entry.action == Action::OpenPalette {
+                    if let Some(key) = palette_key {
+                        bindings.insert(0, key.to_string());
+                    }
+                }
+                bindings.extend(
+                    entry
+                        .tree_keys
+                        .iter()
+                        .map(|&k| format!("{} in tree", key_label(k))),
+                );
+                PaletteItem {
+                    action: entry.action,
+                    name: entry.name,
+                    binding: bindings.first().cloned().unwrap_or_default(),
+                }
+            })
+            .collect();
+        // Equal scores keep registry order, which groups actions by feature
+        let mut list = FuzzyList::new(TieBreak::ListOrder, usize::MAX);
+        list.set_candidates(items.iter().map(|item| item.name.to_string()).collect());
+        Self { items, list }
+    }
+
+    pub fn list(&self) -> &FuzzyList {
+        &self.list
+    }
+
+    pub fn list_mut(&mut self) -> &mut FuzzyList {
+        &mut self.list
+    }
+
+    pub fn item(&self, index: usize) -> &PaletteItem {
+        &self.items[index]
+    }
+
+    pub fn item_count(&self) -> usize {
+        self.items.len()
+    }
+
+    /// The action under the cursor.
+    pub fn chosen(&self) -> Option<Action> {
+        Some(self.items[self.list.current()?].action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn keys_run_registered_actions() {
+        let mut registry = ActionRegistry::builtin();
+        assert_eq!(registry.lookup(&key('f')), Some(Action::ToggleTreeFocus));
+        assert_eq!(registry.lookup(&key('/')), Some(Action::FindFile));
+        assert_eq!(registry.lookup(&key('q')), None);
+        assert!(registry
+            .hints()
+            .starts_with("[:] palette  [f] tree  [s] changes"));
+
+        // Binding a taken key moves it
+        registry.register(Action::CycleZoom, "Zoom pane", "zoom", &['f'], &[]);
+        assert_eq!(registry.lookup(&key('f')), Some(Action::CycleZoom));
+        assert_eq!(registry.lookup(&key('x')), None);
+        let focus = registry
+            .entries()
+            .iter()
+            .find(|e| e.action == Action::ToggleTreeFocus)
+            .unwrap();
+        assert!(focus.keys.is_empty());
+        assert!(!registry.hints().contains("tree"));
+    }
+
+    #[test]
+    fn tree_keys_are_a_separate_keymap() {
+        let registry = ActionRegistry::builtin();
+        assert_eq!(registry.lookup_tree(&key('f')), Some(Action::FilterTree));
+        assert_eq!(registry.lookup(&key('f')), Some(Action::ToggleTreeFocus));
+        assert_eq!(
+            registry.lookup_tree(&key('b')),
+            Some(Action::ToggleStatusBar)
+        );
+        assert_eq!(registry.lookup_tree(&key('E')), Some(Action::ExpandAll));
+        // Navigation keys stay with the tree
+        assert_eq!(registry.lookup_tree(&key('j')), None);
+        let ctrl_d = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(registry.lookup_tree(&ctrl_d), None);
+        assert!(registry.tree_hints().contains("[f] filter"));
+        assert!(!registry.tree_hints().contains("restart"));
+    }
+
+    #[test]
+    fn palette_searches_action_names() {
+        let registry = ActionRegistry::builtin();
+        let prefix = KeyChord::default();
+        let mut palette = Palette::new(&registry, prefix, KeyChord::parse("ctrl-space").ok());
+        assert_eq!(palette.list().matches().len(), registry.entries().len());
+        assert_eq!(palette.chosen(), Some(Action::OpenPalette));
+        assert_eq!(palette.item(0).binding, "Ctrl+Space");
+
+        for c in "scrollback".chars() {
+            palette.list_mut().push_char(c);
+        }
+        assert_eq!(palette.chosen(), Some(Action::SearchScrollback));
+        let index = palette.list().matches()[0].index;
+        assert_eq!(palette.item(index).binding, "Ctrl+] [");
+
+        palette.list_mut().clear_query();
+        for c in "rest".chars() {
+            palette.list_mut().push_char(c);
+        }
+        assert_eq!(palette.chosen(), Some(Action::RestartSession));
+
+        // Actions bound only in tree focus are listed with their tree key
+        palette.list_mut().clear_query();
+        for c in "gitignored".chars() {
+            palette.list_mut().push_char(c);
+        }
+        assert_eq!(palette.chosen(), Some(Action::ToggleGitignored));
+        let index = palette.list().matches()[0].index;
+        assert_eq!(palette.item(index).binding, "i in tree");
+        palette.list_mut().push_char('!');
+        assert_eq!(palette.chosen(), None);
+    }
+}
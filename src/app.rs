@@ -1,11 +1,82 @@
-use anyhow::Result;
-use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use anyhow::{bail, Context, Result};
+use crossterm::event::{
+    KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 use ratatui::prelude::Rect;
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+use crate::actions::{Action, ActionRegistry, Palette};
+use crate::asciicast::{Header, Recorder};
+use crate::changes::SessionChanges;
+use crate::config::EnvOverrides;
+use crate::diff::{Baselines, DiffView};
+use crate::event::Event;
+use crate::finder::{relative_path, Finder};
+use crate::keys::KeyChord;
+use crate::preview::Preview;
+use crate::project_state::ProjectState;
+use crate::scanner::{DirSizeReader, FileLister, GitStatusReader, TreeScanner};
+use crate::signals::ChildSignal;
 use crate::terminal::TerminalPane;
-use crate::tree::FileTree;
+use crate::tree::{
+    ChangeOutcome, DirSizes, ExcludeRules, FileTree, GitStatus, GitStatusMap, ScanResult,
+    SortOrder, TreeFilter,
+};
+use crate::ui::{
+    icon_column, IconTheme, Panes, Segment, TreeColumns, TreeSide, Zoom, DEFAULT_STACK_BELOW,
+};
+
+/// Startup settings assembled from CLI flags and config files.
+pub struct AppOptions {
+    pub path: PathBuf,
+    pub tree_width: u16,
+    pub show_hidden: bool,
+    pub show_ignored: bool,
+    pub follow_symlinks: bool,
+    pub max_depth: usize,
+    pub sort: SortOrder,
+    pub filter: Option<TreeFilter>,
+    pub excludes: ExcludeRules,
+    pub icons: IconTheme,
+    /// Metadata columns shown from the start; none leaves them hidden.
+    pub columns: TreeColumns,
+    pub layout: TreeSide,
+    pub stack_below: u16,
+    /// Status bar segments, left to right; empty hides the bar.
+    pub status_bar: Vec<Segment>,
+    /// Children shown per directory before the rest are summarized; None shows all.
+    pub max_children: Option<usize>,
+    pub compact_dirs: bool,
+    pub claude_args: Vec<String>,
+    pub env_layers: Vec<EnvOverrides>,
+    pub record: Option<RecordOptions>,
+    pub paste_confirm_bytes: usize,
+    pub prefix: KeyChord,
+    /// Opens the command palette directly, without the prefix.
+    pub palette_key: Option<KeyChord>,
+    /// Where runtime changes to hidden files, depth and tree width are saved.
+    pub state_file: Option<PathBuf>,
+}
+
+pub struct RecordOptions {
+    pub path: PathBuf,
+    pub input: bool,
+}
+
+/// How long a status message stays on screen.
+const STATUS_MESSAGE_TTL: Duration = Duration::from_secs(4);
+
+/// Multi-line pastes above this many bytes need confirmation by default.
+pub const DEFAULT_PASTE_CONFIRM_BYTES: usize = 4096;
+
+/// Bounds of the tree panel width, in percent of the screen.
+const MIN_TREE_WIDTH: u16 = 10;
+const MAX_TREE_WIDTH: u16 = 50;
+/// Percent the tree panel grows or shrinks per key press.
+const TREE_WIDTH_STEP: u16 = 5;
 
 pub struct Selection {
     pub start: (u16, u16), // (col, row) terminal-local coordinates
@@ -15,35 +86,247 @@ pub struct Selection {
 pub struct App {
     pub tree: FileTree,
     pub terminal: TerminalPane,
+    /// Share of the screen the tree pane takes, across or down depending on the layout.
     pub tree_width_percent: u16,
+    pub tree_side: TreeSide,
+    /// Screens narrower than this stack the tree above or below the terminal; 0 never does.
+    pub stack_below: u16,
+    pub zoom: Zoom,
+    /// Where the panes were last drawn, set by the UI.
+    pub panes: Option<Panes>,
+    /// What the status bar shows, left to right.
+    pub status_segments: Vec<Segment>,
+    pub status_bar_shown: bool,
+    // The border between the panes is being dragged
+    resizing_tree: bool,
+    // Where hidden files, depth and tree width are saved when changed; None in replay
+    state_file: Option<PathBuf>,
+    /// The tree has no scanned contents yet (startup or a new root).
     pub tree_loading: bool,
+    // Background tree builds; None in replay, where the tree never changes
+    scanner: Option<TreeScanner>,
+    // Watcher paths not yet applied to the tree, batched per tick
+    pending_changes: HashSet<PathBuf>,
+    // The whole tree needs a rescan (e.g. an ignore file changed)
+    tree_dirty: bool,
+    git_reader: Option<GitStatusReader>,
+    // Set by file changes and root switches; re-read once the running read finishes
+    git_dirty: bool,
+    size_reader: Option<DirSizeReader>,
+    // Directory sizes are out of date; re-totalled while the size column is shown
+    sizes_dirty: bool,
+    /// Files created, modified or deleted since startup.
+    pub changes: SessionChanges,
+    /// The tree pane lists only the files changed this session.
+    pub changes_view: bool,
+    pub changes_offset: usize,
+    /// The fuzzy file finder popup, while open.
+    pub finder: Option<Finder>,
+    file_lister: Option<FileLister>,
+    /// Rows of the finder's result list, set by the UI for paging.
+    pub finder_area: Option<Rect>,
+    /// The preview pane is shown below the tree.
+    pub preview_open: bool,
+    /// The file in the preview pane: the tree cursor in tree focus, otherwise the hovered file.
+    pub preview: Option<Preview>,
+    pub preview_area: Option<Rect>,
+    // Last file under the mouse in the tree
+    hovered: Option<PathBuf>,
+    // The previewed file changed on disk; reloaded on the next tick
+    preview_dirty: bool,
+    /// What changed files are diffed against.
+    pub baselines: Baselines,
+    /// The diff popup, while open.
+    pub diff_view: Option<DiffView>,
+    pub diff_area: Option<Rect>,
+    // The diffed file changed on disk; recomputed on the next tick
+    diff_dirty: bool,
+    /// Glyph set for tree nodes.
+    pub icons: IconTheme,
+    /// Metadata columns shown when they are toggled on.
+    pub columns: TreeColumns,
+    pub columns_shown: bool,
     pub tree_area: Option<Rect>,
     pub terminal_area: Option<Rect>,
     pub selection: Option<Selection>,
     pub last_auto_scroll_cwd: Option<PathBuf>,
+    /// A large paste waiting for the user to confirm or cancel it.
+    pub pending_paste: Option<String>,
+    pub paste_confirm_bytes: usize,
+    /// The command prefix was pressed; the next key is a cltree action.
+    pub command_mode: bool,
+    pub prefix: KeyChord,
+    pub palette_key: Option<KeyChord>,
+    /// What the prefix keys and the palette can run.
+    pub actions: ActionRegistry,
+    /// The command palette popup, while open.
+    pub palette: Option<Palette>,
+    /// Rows of the palette's action list, set by the UI for paging.
+    pub palette_area: Option<Rect>,
+    /// The scrollback search being typed.
+    pub scroll_search: Option<ScrollSearch>,
+    /// Keys drive the file tree instead of going to Claude.
+    pub tree_focus: bool,
+    /// The tree filter being typed.
+    pub filter_input: Option<FilterInput>,
+    status_message: Option<(String, Instant)>,
+}
+
+/// A search through the terminal's scrollback; the view follows it as it is typed.
+pub struct ScrollSearch {
+    pub text: String,
+    /// Session lines of the scrollback rows containing the text, oldest first.
+    pub matches: Vec<usize>,
+    /// Index into `matches` of the row scrolled into view.
+    pub current: Option<usize>,
+}
+
+/// A tree filter expression being edited; the tree follows it as it is typed.
+pub struct FilterInput {
+    pub text: String,
+    /// The text does not parse; the tree keeps the last valid filter.
+    pub invalid: bool,
+    // Restored when editing is cancelled
+    previous: Option<TreeFilter>,
 }
 
 impl App {
     pub fn new(
-        path: PathBuf,
-        tree_width: u16,
-        show_hidden: bool,
-        max_depth: usize,
-        claude_args: Vec<String>,
+        options: AppOptions,
         pty_tx: mpsc::UnboundedSender<()>,
+        event_tx: mpsc::UnboundedSender<Event>,
     ) -> Result<Self> {
-        let canonical_path = path.canonicalize().unwrap_or(path);
+        let canonical_path = options.path.canonicalize().unwrap_or(options.path);
+        // Recordings store the starting tree in their header, so that one build is
+        // done up front; otherwise the tree fills in from a background scan
+        let mut tree = FileTree::unscanned(&canonical_path, options.show_hidden, options.max_depth);
+        tree.show_gitignored = options.show_ignored;
+        tree.follow_symlinks = options.follow_symlinks;
+        tree.compact_dirs = options.compact_dirs;
+        tree.child_limit = options.max_children;
+        if options.max_children.is_none() {
+            tree.node_budget = None;
+        }
+        tree.set_excludes(options.excludes)?;
+        tree.set_sort(options.sort);
+        tree.set_filter(options.filter);
+        tree.set_collect_meta(!options.columns.is_empty());
+        if options.record.is_some() {
+            tree.refresh();
+        }
+        let scanned = options.record.is_some();
 
-        Ok(Self {
-            tree: FileTree::new(&canonical_path, show_hidden, max_depth)?,
-            terminal: TerminalPane::new(&canonical_path, &claude_args, pty_tx)?,
-            tree_width_percent: tree_width.clamp(10, 50),
+        let recorder = match options.record {
+            Some(record) => {
+                let header = Header {
+                    width: 80,
+                    height: 24,
+                    timestamp: None,
+                    env: ["SHELL", "TERM"]
+                        .iter()
+                        .filter_map(|k| std::env::var(k).ok().map(|v| (k.to_string(), v)))
+                        .collect(),
+                    root: Some(canonical_path.clone()),
+                    tree: tree.snapshot(),
+                };
+                Some(Recorder::create(&record.path, &header, record.input)?)
+            }
+            None => None,
+        };
+
+        let terminal = TerminalPane::new(
+            &canonical_path,
+            &options.claude_args,
+            &options.env_layers,
+            recorder,
+            pty_tx,
+        )?;
+
+        let mut app = Self::from_parts(tree, terminal, options.tree_width);
+        app.paste_confirm_bytes = options.paste_confirm_bytes;
+        app.prefix = options.prefix;
+        app.palette_key = options.palette_key;
+        app.state_file = options.state_file;
+        app.tree_side = options.layout;
+        app.stack_below = options.stack_below;
+        app.status_bar_shown = !options.status_bar.is_empty();
+        if app.status_bar_shown {
+            app.status_segments = options.status_bar;
+        }
+        app.icons = options.icons;
+        if !options.columns.is_empty() {
+            app.columns = options.columns;
+            app.columns_shown = true;
+        }
+        app.scanner = Some(TreeScanner::new(event_tx.clone()));
+        app.git_reader = Some(GitStatusReader::new(event_tx.clone()));
+        app.size_reader = Some(DirSizeReader::new(event_tx.clone()));
+        app.file_lister = Some(FileLister::new(event_tx));
+        if scanned {
+            app.tree_loading = false;
+        } else {
+            app.start_scan();
+        }
+        Ok(app)
+    }
+
+    /// Assemble an app around an existing tree and terminal pane (used by replay).
+    pub fn from_parts(tree: FileTree, terminal: TerminalPane, tree_width: u16) -> Self {
+        Self {
+            tree,
+            terminal,
+            tree_width_percent: tree_width.clamp(MIN_TREE_WIDTH, MAX_TREE_WIDTH),
+            tree_side: TreeSide::default(),
+            stack_below: DEFAULT_STACK_BELOW,
+            zoom: Zoom::default(),
+            panes: None,
+            status_segments: Segment::ALL.to_vec(),
+            status_bar_shown: true,
+            resizing_tree: false,
+            state_file: None,
             tree_loading: true,
+            scanner: None,
+            pending_changes: HashSet::new(),
+            tree_dirty: false,
+            git_reader: None,
+            git_dirty: true,
+            size_reader: None,
+            sizes_dirty: true,
+            changes: SessionChanges::default(),
+            changes_view: false,
+            changes_offset: 0,
+            finder: None,
+            file_lister: None,
+            finder_area: None,
+            preview_open: false,
+            preview: None,
+            preview_area: None,
+            hovered: None,
+            preview_dirty: false,
+            baselines: Baselines::default(),
+            diff_view: None,
+            diff_area: None,
+            diff_dirty: false,
+            icons: IconTheme::default(),
+            columns: TreeColumns::ALL,
+            columns_shown: false,
             tree_area: None,
             terminal_area: None,
             selection: None,
             last_auto_scroll_cwd: None,
-        })
+            pending_paste: None,
+            paste_confirm_bytes: DEFAULT_PASTE_CONFIRM_BYTES,
+            command_mode: false,
+            prefix: KeyChord::default(),
+            palette_key: None,
+            actions: ActionRegistry::builtin(),
+            palette: None,
+            palette_area: None,
+            scroll_search: None,
+            tree_focus: false,
+            filter_input: None,
+            status_message: None,
+        }
     }
 
     pub fn tick(&mut self) -> bool {
@@ -52,8 +335,59 @@ impl App {
         // CWD가 트리 루트 밖이면 트리 루트 갱신
         let cwd = self.terminal.cwd().to_path_buf();
         if !cwd.starts_with(self.tree.root_path()) {
-            self.tree.set_root(cwd);
             self.last_auto_scroll_cwd = None;
+            self.git_dirty = true;
+            self.sizes_dirty = true;
+            if self.scanner.is_some() {
+                // Any scan of the old root is stale now; start_scan cancels it
+                self.tree.set_root_unscanned(cwd);
+                self.tree_loading = true;
+                self.start_scan();
+            } else {
+                self.tree.set_root(cwd);
+            }
+        }
+
+        // Apply this tick's file changes once any running scan has delivered its listings
+        if !self.pending_changes.is_empty() && self.scan_progress().is_none() {
+            let paths = std::mem::take(&mut self.pending_changes);
+            if self.tree.apply_changes(paths) == ChangeOutcome::NeedsRescan {
+                self.tree_dirty = true;
+            }
+        }
+        if self.tree_dirty && self.scan_progress().is_none() {
+            self.tree_dirty = false;
+            self.sizes_dirty = true;
+            if self.scanner.is_some() {
+                self.start_scan();
+            } else {
+                self.tree.refresh();
+            }
+        }
+        if std::mem::take(&mut self.preview_dirty) {
+            if let Some(preview) = self.preview.as_mut() {
+                preview.reload();
+            }
+        }
+        self.update_preview();
+        if std::mem::take(&mut self.diff_dirty) {
+            if let Some(view) = self.diff_view.as_mut() {
+                let diff = self.baselines.diff(&view.diff.path);
+                view.update(diff);
+            }
+        }
+
+        if self.git_dirty {
+            if let Some(reader) = self.git_reader.as_mut().filter(|r| !r.is_running()) {
+                self.git_dirty = false;
+                reader.start(self.tree.root_path().to_path_buf());
+            }
+        }
+        if self.sizes_dirty && self.shown_columns().size {
+            if let Some(reader) = self.size_reader.as_mut().filter(|r| r.is_ready()) {
+                self.sizes_dirty = false;
+                reader.start(self.tree.begin_size_scan());
+            }
         }
 
         // Process clipboard requests from vterm (OSC 52)
@@ -63,23 +397,849 @@ impl App {
                 copy_to_clipboard(&text);
             }
         }
-        if self.tree_loading {
-            self.tree_loading = false;
-        }
         self.terminal.is_process_exited()
     }
 
     pub fn handle_key(&mut self, key: KeyEvent) -> bool {
         self.selection = None;
-        self.terminal.handle_key(key);
+        if self.pending_paste.is_some() {
+            self.handle_paste_prompt_key(key);
+            return false;
+        }
+        if self.palette.is_some() {
+            if key.kind != KeyEventKind::Release {
+                self.handle_palette_key(key);
+            }
+            return false;
+        }
+        if self.finder.is_some() {
+            if key.kind != KeyEventKind::Release {
+                self.handle_finder_key(key);
+            }
+            return false;
+        }
+        if self.diff_view.is_some() {
+            if key.kind != KeyEventKind::Release {
+                self.handle_diff_key(key);
+            }
+            return false;
+        }
+        if self.filter_input.is_some() {
+            if key.kind != KeyEventKind::Release {
+                self.handle_filter_key(key);
+            }
+            return false;
+        }
+        if self.scroll_search.is_some() {
+            if key.kind != KeyEventKind::Release {
+                self.handle_scroll_search_key(key);
+            }
+            return false;
+        }
+        if key.kind != KeyEventKind::Release {
+            if self.command_mode {
+                self.command_mode = false;
+                self.handle_command_key(key);
+                return false;
+            }
+            if self.prefix.matches(&key) {
+                self.command_mode = true;
+                return false;
+            }
+            if self.palette_key.is_some_and(|k| k.matches(&key)) {
+                self.open_palette();
+                return false;
+            }
+            if self.tree_focus && self.changes_view {
+                self.handle_changes_key(key);
+                return false;
+            }
+            if self.tree_focus {
+                self.handle_tree_key(key);
+                return false;
+            }
+        }
+        if !self.tree_focus {
+            if key.code == KeyCode::Enter && key.kind != KeyEventKind::Release {
+                // Where the next reply starts, for copying it later
+                self.terminal.vterm_lock().mark_prompt();
+            }
+            self.terminal.handle_key(key);
+        }
         false
     }
 
+    /// Show the preview target, loading it if it changed.
+    fn update_preview(&mut self) {
+        if !self.preview_open {
+            return;
+        }
+        let target = if self.tree_focus {
+            self.tree
+                .selected_node()
+                .filter(|n| !n.is_dir && n.more.is_none())
+                .map(|n| n.path.clone())
+        } else {
+            self.hovered.clone()
+        };
+        let Some(target) = target else {
+            return;
+        };
+        if self.preview.as_ref().is_none_or(|p| p.path() != target) {
+            // Opening a file is the earliest chance to snapshot it before Claude edits it
+            self.baselines.remember(&target);
+            self.preview = Some(Preview::load(target));
+        }
+    }
+
+    fn toggle_preview(&mut self) {
+        self.preview_open = !self.preview_open;
+        if !self.preview_open {
+            self.preview = None;
+        }
+        self.update_preview();
+    }
+
+    fn open_diff(&mut self, path: &Path) {
+        let diff = self.baselines.diff(path);
+        self.diff_view = Some(DiffView::new(diff));
+    }
+
+    fn handle_diff_key(&mut self, key: KeyEvent) {
+        let Some(view) = self.diff_view.as_mut() else {
+            return;
+        };
+        let page = self.diff_area.map_or(1, |a| a.height as isize);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.diff_view = None,
+            KeyCode::Char('j') | KeyCode::Down => view.scroll_by(1),
+            KeyCode::Char('k') | KeyCode::Up => view.scroll_by(-1),
+            KeyCode::PageDown | KeyCode::Char(' ') => view.scroll_by(page),
+            KeyCode::PageUp => view.scroll_by(-page),
+            KeyCode::Char('g') | KeyCode::Home => view.scroll = 0,
+            KeyCode::Char('G') | KeyCode::End => view.scroll_by(isize::MAX),
+            KeyCode::Char('n') | KeyCode::Char(']') => view.next_hunk(),
+            KeyCode::Char('p') | KeyCode::Char('N') | KeyCode::Char('[') => view.prev_hunk(),
+            KeyCode::Char('s') | KeyCode::Tab => view.toggle_layout(),
+            KeyCode::Char('a') => {
+                let path = view.diff.path.clone();
+                self.baselines.accept(&path);
+                let diff = self.baselines.diff(&path);
+                view.update(diff);
+            }
+            _ => {}
+        }
+    }
+
+    /// Half the preview pane's height, for Ctrl+D / Ctrl+U.
+    fn preview_page(&self) -> usize {
+        self.preview_area
+            .map_or(1, |a| (a.height as usize / 2).max(1))
+    }
+
+    fn scroll_preview(&mut self, delta: isize) {
+        if let Some(preview) = self.preview.as_mut() {
+            preview.scroll_by(delta);
+        }
+    }
+
+    fn handle_tree_key(&mut self, key: KeyEvent) {
+        let page = self.tree_area.map(|a| a.height as usize).unwrap_or(1);
+        let selected = self.tree.selected();
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.tree_focus = false,
+            KeyCode::Char('j') | KeyCode::Down => self.tree.select(selected + 1),
+            KeyCode::Char('k') | KeyCode::Up => self.tree.select(selected.saturating_sub(1)),
+            KeyCode::PageDown => self.tree.select(selected + page),
+            KeyCode::PageUp => self.tree.select(selected.saturating_sub(page)),
+            KeyCode::Char('g') | KeyCode::Home => self.tree.select(0),
+            KeyCode::Char('G') | KeyCode::End => self.tree.select(usize::MAX),
+            KeyCode::Char('h') | KeyCode::Left => {
+                let expanded_dir = self
+                    .tree
+                    .selected_node()
+                    .is_some_and(|n| n.depth > 0 && self.tree.is_expanded(n));
+                if expanded_dir {
+                    self.tree.toggle(selected);
+                } else {
+                    self.tree.select_parent();
+                }
+            }
+            KeyCode::Char('l') | KeyCode::Right => {
+                if let Some(node) = self.tree.selected_node() {
+                    if node.is_dir && !self.tree.is_expanded(node) {
+                        self.tree.toggle(selected);
+                    } else if node.is_dir {
+                        // Step onto the first child, if any
+                        let depth = node.depth;
+                        if self
+                            .tree
+                            .nodes()
+                            .get(selected + 1)
+                            .is_some_and(|n| n.depth > depth)
+                        {
+                            self.tree.select(selected + 1);
+                        }
+                    }
+                }
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                self.tree.toggle(selected);
+            }
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.scroll_preview(self.preview_page() as isize)
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.scroll_preview(-(self.preview_page() as isize))
+            }
+            _ => {
+                if let Some(action) = self.actions.lookup_tree(&key) {
+                    self.run_action(action);
+                }
+            }
+        }
+        self.tree.scroll_to_selected(page);
+        self.update_preview();
+    }
+
+    fn handle_filter_key(&mut self, key: KeyEvent) {
+        let Some(input) = self.filter_input.as_mut() else {
+            return;
+        };
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => {
+                let previous = input.previous.take();
+                self.filter_input = None;
+                self.tree.set_filter(previous);
+            }
+            KeyCode::Enter => self.filter_input = None,
+            KeyCode::Backspace => {
+                input.text.pop();
+                self.apply_filter_input();
+            }
+            KeyCode::Char('u') if ctrl => {
+                input.text.clear();
+                self.apply_filter_input();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                input.text.push(c);
+                self.apply_filter_input();
+            }
+            _ => {}
+        }
+        let page = self.tree_area.map_or(1, |a| a.height as usize);
+        self.tree.scroll_to_selected(page);
+    }
+
+    /// Filter the tree by the text typed so far, if it parses.
+    fn apply_filter_input(&mut self) {
+        let Some(input) = self.filter_input.as_mut() else {
+            return;
+        };
+        if input.text.is_empty() {
+            input.invalid = false;
+            self.tree.set_filter(None);
+            return;
+        }
+        match TreeFilter::parse(&input.text) {
+            Ok(filter) => {
+                input.invalid = false;
+                self.tree.set_filter(Some(filter));
+            }
+            Err(_) => input.invalid = true,
+        }
+    }
+
+    /// The metadata columns to draw, none while they are toggled off.
+    pub fn shown_columns(&self) -> TreeColumns {
+        if self.columns_shown {
+            self.columns
+        } else {
+            TreeColumns::default()
+        }
+    }
+
+    fn toggle_columns(&mut self) {
+        self.columns_shown = !self.columns_shown;
+        self.tree.set_collect_meta(self.columns_shown);
+        let message = if self.columns_shown {
+            "Showing metadata columns"
+        } else {
+            "Hiding metadata columns"
+        };
+        self.status_message = Some((message.to_string(), Instant::now()));
+    }
+
+    fn cycle_sort(&mut self) {
+        let mut order = self.tree.sort();
+        order.mode = order.mode.next();
+        self.set_sort(order);
+    }
+
+    fn set_sort(&mut self, order: SortOrder) {
+        self.tree.set_sort(order);
+        let page = self.tree_area.map_or(1, |a| a.height as usize);
+        self.tree.scroll_to_selected(page);
+        let message = format!("Sorted by {}", order.label());
+        self.status_message = Some((message, Instant::now()));
+    }
+
+    fn open_finder(&mut self) {
+        let Some(lister) = &self.file_lister else {
+            return;
+        };
+        let root = self.tree.root_path().to_path_buf();
+        lister.start(root.clone(), self.tree.show_hidden);
+        self.finder = Some(Finder::new(root));
+    }
+
+    pub fn handle_finder_files(&mut self, root: PathBuf, files: Vec<String>) {
+        if let Some(finder) = self
+            .finder
+            .as_mut()
+            .filter(|f| f.is_loading() && f.root() == root)
+        {
+            finder.set_files(files);
+        }
+    }
+
+    fn handle_finder_key(&mut self, key: KeyEvent) {
+        let Some(finder) = self.finder.as_mut() else {
+            return;
+        };
+        let page = self.finder_area.map_or(1, |a| a.height as usize);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => self.finder = None,
+            KeyCode::Char('c') if ctrl => self.finder = None,
+            KeyCode::Enter => self.insert_finder_selection(),
+            KeyCode::Tab => {
+                finder.toggle_mark();
+                finder.list_mut().move_selection(1, page);
+            }
+            KeyCode::BackTab => {
+                finder.toggle_mark();
+                finder.list_mut().move_selection(-1, page);
+            }
+            _ => {
+                finder.list_mut().handle_key(&key, page);
+            }
+        }
+    }
+
+    /// Type `@path` references for the chosen files into Claude's prompt,
+    /// relative to the child's working directory.
+    fn insert_finder_selection(&mut self) {
+        let Some(finder) = self.finder.take() else {
+            return;
+        };
+        let cwd = self.terminal.cwd();
+        let refs: Vec<String> = finder
+            .chosen()
+            .iter()
+            .map(|path| format!("@{}", relative_path(path, cwd).display()))
+            .collect();
+        if !refs.is_empty() {
+            self.terminal.handle_paste(format!("{} ", refs.join(" ")));
+        }
+    }
+
+    /// Keys while the tree pane shows the files changed this session.
+    fn handle_changes_key(&mut self, key: KeyEvent) {
+        let page = self.tree_area.map(|a| a.height as usize).unwrap_or(1);
+        let max_offset = self.changes.len().saturating_sub(page);
+        let offset = self.changes_offset;
+        self.changes_offset = match key.code {
+            KeyCode::Esc | KeyCode::Char('s') => {
+                self.changes_view = false;
+                return;
+            }
+            KeyCode::Char('q') => {
+                self.changes_view = false;
+                self.tree_focus = false;
+                return;
+            }
+            KeyCode::Char('j') | KeyCode::Down => offset + 1,
+            KeyCode::Char('k') | KeyCode::Up => offset.saturating_sub(1),
+            KeyCode::PageDown => offset + page,
+            KeyCode::PageUp => offset.saturating_sub(page),
+            KeyCode::Char('g') | KeyCode::Home => 0,
+            KeyCode::Char('G') | KeyCode::End => max_offset,
+            _ => offset,
+        }
+        .min(max_offset);
+    }
+
+    fn handle_command_key(&mut self, key: KeyEvent) {
+        // Pressing the prefix twice sends it through to the child
+        if self.prefix.matches(&key) {
+            self.terminal.handle_key(key);
+            return;
+        }
+        if let Some(action) = self.actions.lookup(&key) {
+            self.run_action(action);
+        }
+    }
+
+    /// Do what a command key or palette entry stands for.
+    pub fn run_action(&mut self, action: Action) {
+        let result = match action {
+            Action::Interrupt => self.send_signal(ChildSignal::Interrupt),
+            Action::Terminate => self.send_signal(ChildSignal::Terminate),
+            Action::Kill => self.send_signal(ChildSignal::Kill),
+            Action::ToggleSuspend => self
+                .terminal
+                .toggle_suspend()
+                .map(|(signal, pgid)| signal_message(signal, pgid)),
+            Action::RestartSession => self
+                .terminal
+                .restart()
+                .map(|()| "Restarted Claude".to_string()),
+            Action::OpenPalette => {
+                self.open_palette();
+                return;
+            }
+            Action::ToggleTreeFocus => {
+                self.tree_focus = !self.tree_focus;
+                self.changes_view = false;
+                return;
+            }
+            Action::CycleZoom => {
+                self.zoom = self.zoom.next();
+                // Keys follow the pane left on screen
+                self.tree_focus = self.zoom == Zoom::Tree;
+                let message = match self.zoom {
+                    Zoom::Off => "Showing both panes",
+                    Zoom::Terminal => "Zoomed to Claude",
+                    Zoom::Tree => "Zoomed to the tree",
+                };
+                self.status_message = Some((message.to_string(), Instant::now()));
+                return;
+            }
+            Action::ToggleStatusBar => {
+                self.status_bar_shown = !self.status_bar_shown;
+                return;
+            }
+            Action::ShowChanges => {
+                self.changes_view = !self.changes_view;
+                self.tree_focus = self.changes_view;
+                return;
+            }
+            Action::FindFile => {
+                self.open_finder();
+                return;
+            }
+            Action::TogglePreview => {
+                self.toggle_preview();
+                return;
+            }
+            Action::CycleSort => {
+                self.cycle_sort();
+                return;
+            }
+            Action::ToggleDirsFirst => {
+                let mut order = self.tree.sort();
+                order.directories_first = !order.directories_first;
+                self.set_sort(order);
+                return;
+            }
+            Action::FilterTree => {
+                self.filter_input = Some(FilterInput {
+                    text: self
+                        .tree
+                        .filter()
+                        .map(|f| f.expr().to_string())
+                        .unwrap_or_default(),
+                    invalid: false,
+                    previous: self.tree.filter().cloned(),
+                });
+                return;
+            }
+            Action::ToggleGitignored => {
+                self.tree.show_gitignored = !self.tree.show_gitignored;
+                self.tree_dirty = true;
+                Ok(if self.tree.show_gitignored {
+                    "Showing gitignored files".to_string()
+                } else {
+                    "Hiding gitignored files".to_string()
+                })
+            }
+            Action::ToggleColumns => {
+                self.toggle_columns();
+                return;
+            }
+            Action::ExpandAll => {
+                self.tree.expand_all();
+                return;
+            }
+            Action::CollapseAll => {
+                self.tree.collapse_all();
+                return;
+            }
+            Action::ScrollPreviewDown => {
+                self.scroll_preview(1);
+                return;
+            }
+            Action::ScrollPreviewUp => {
+                self.scroll_preview(-1);
+                return;
+            }
+            Action::ToggleHidden => {
+                self.toggle_hidden();
+                return;
+            }
+            Action::DeeperTree => {
+                self.change_depth(1);
+                return;
+            }
+            Action::ShallowerTree => {
+                self.change_depth(-1);
+                return;
+            }
+            Action::GrowTree => {
+                self.set_tree_width(self.tree_width_percent + TREE_WIDTH_STEP);
+                return;
+            }
+            Action::ShrinkTree => {
+                self.set_tree_width(self.tree_width_percent.saturating_sub(TREE_WIDTH_STEP));
+                return;
+            }
+            Action::DiffFile => {
+                // In tree focus the selected file; otherwise the previewed
+                // file, else the one under the mouse
+                let target = if self.tree_focus {
+                    self.tree
+                        .selected_node()
+                        .filter(|n| !n.is_dir && n.more.is_none())
+                        .map(|n| n.path.clone())
+                } else {
+                    self.preview
+                        .as_ref()
+                        .map(|p| p.path().to_path_buf())
+                        .or_else(|| self.hovered.clone())
+                };
+                if let Some(path) = target {
+                    self.open_diff(&path);
+                }
+                return;
+            }
+            Action::SearchScrollback => {
+                self.scroll_search = Some(ScrollSearch {
+                    text: String::new(),
+                    matches: Vec::new(),
+                    current: None,
+                });
+                return;
+            }
+            Action::CopyLastOutput => self.copy_last_output(),
+        };
+        let message = result.unwrap_or_else(|e| format!("{e:#}"));
+        self.status_message = Some((message, Instant::now()));
+    }
+
+    fn open_palette(&mut self) {
+        self.palette = Some(Palette::new(&self.actions, self.prefix, self.palette_key));
+    }
+
+    fn handle_palette_key(&mut self, key: KeyEvent) {
+        let Some(palette) = self.palette.as_mut() else {
+            return;
+        };
+        let page = self.palette_area.map_or(1, |a| a.height as usize);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => self.palette = None,
+            KeyCode::Char('c') if ctrl => self.palette = None,
+            KeyCode::Enter => {
+                let chosen = palette.chosen();
+                self.palette = None;
+                if let Some(action) = chosen {
+                    self.run_action(action);
+                }
+            }
+            _ => {
+                palette.list_mut().handle_key(&key, page);
+            }
+        }
+    }
+
+    /// Keys while typing a scrollback search: Up and Down step through older and
+    /// newer matches, Enter keeps the view and Esc returns to the bottom.
+    fn handle_scroll_search_key(&mut self, key: KeyEvent) {
+        let Some(search) = self.scroll_search.as_mut() else {
+            return;
+        };
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => {
+                self.scroll_search = None;
+                self.terminal.vterm_lock().set_scroll_offset(0);
+                return;
+            }
+            KeyCode::Char('c') if ctrl => {
+                self.scroll_search = None;
+                self.terminal.vterm_lock().set_scroll_offset(0);
+                return;
+            }
+            KeyCode::Enter => {
+                self.scroll_search = None;
+                return;
+            }
+            KeyCode::Up => self.step_scroll_search(-1),
+            KeyCode::Char('p') if ctrl => self.step_scroll_search(-1),
+            KeyCode::Down => self.step_scroll_search(1),
+            KeyCode::Char('n') if ctrl => self.step_scroll_search(1),
+            KeyCode::Backspace => {
+                search.text.pop();
+                self.update_scroll_search();
+            }
+            KeyCode::Char('u') if ctrl => {
+                search.text.clear();
+                self.update_scroll_search();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                search.text.push(c);
+                self.update_scroll_search();
+            }
+            _ => {}
+        }
+        self.scroll_to_search_match();
+    }
+
+    /// Search the scrollback again after the text changed, starting from the newest match.
+    fn update_scroll_search(&mut self) {
+        let Some(search) = self.scroll_search.as_mut() else {
+            return;
+        };
+        search.matches = self.terminal.vterm_lock().scrollback_matches(&search.text);
+        search.current = search.matches.len().checked_sub(1);
+    }
+
+    /// Move to an older (-1) or newer (+1) match. The scrollback is searched
+    /// again first, since output that arrived meanwhile may add or drop matches.
+    fn step_scroll_search(&mut self, step: isize) {
+        let Some(search) = self.scroll_search.as_mut() else {
+            return;
+        };
+        let line = search.current.and_then(|i| search.matches.get(i)).copied();
+        search.matches = self.terminal.vterm_lock().scrollback_matches(&search.text);
+        let Some(last) = search.matches.len().checked_sub(1) else {
+            search.current = None;
+            return;
+        };
+        // Where the current match is now; if it scrolled away, the oldest one left
+        let at = line.map_or(last, |line| search.matches.partition_point(|&l| l < line));
+        let kept = line.is_some_and(|line| search.matches.get(at) == Some(&line));
+        search.current = Some(match step {
+            -1 if kept => at.saturating_sub(1),
+            -1 => at.min(last),
+            _ if kept => (at + 1).min(last),
+            _ => at.min(last),
+        });
+    }
+
+    /// Scroll the terminal so the current match sits mid-screen.
+    fn scroll_to_search_match(&mut self) {
+        let Some(search) = self.scroll_search.as_ref() else {
+            return;
+        };
+        let Some(&line) = search.current.and_then(|i| search.matches.get(i)) else {
+            return;
+        };
+        let height = self.terminal_area.map_or(1, |a| a.height as usize);
+        let mut vterm = self.terminal.vterm_lock();
+        let Some(row) = vterm.scrollback_row(line) else {
+            return;
+        };
+        let total = vterm.scrollback().len() + vterm.grid().len();
+        let bottom = (row + height / 2 + 1).min(total);
+        vterm.set_scroll_offset((total - bottom).max(1));
+    }
+
+    /// The scrollback row of the current search match, for highlighting.
+    pub fn search_match_row(&self) -> Option<usize> {
+        let search = self.scroll_search.as_ref()?;
+        let line = *search.matches.get(search.current?)?;
+        self.terminal.vterm_lock().scrollback_row(line)
+    }
+
+    /// Copy what the child printed after the last prompt was submitted.
+    fn copy_last_output(&mut self) -> Result<String> {
+        let text = self
+            .terminal
+            .vterm_lock()
+            .output_since_prompt()
+            .context("no output since the last prompt")?;
+        if !copy_to_clipboard(&text) {
+            bail!("no clipboard tool found");
+        }
+        let lines = text.lines().count();
+        let noun = if lines == 1 { "line" } else { "lines" };
+        Ok(format!("Copied {lines} {noun} of output"))
+    }
+
+    /// Show or hide dotfiles, rescanning the tree.
+    fn toggle_hidden(&mut self) {
+        self.tree.show_hidden = !self.tree.show_hidden;
+        self.tree_dirty = true;
+        let message = if self.tree.show_hidden {
+            "Showing hidden files"
+        } else {
+            "Hiding hidden files"
+        };
+        self.status_message = Some((message.to_string(), Instant::now()));
+        self.save_project_state();
+    }
+
+    /// Walk `delta` levels deeper or shallower, rescanning the tree.
+    fn change_depth(&mut self, delta: isize) {
+        let depth = self.tree.max_depth().saturating_add_signed(delta).max(1);
+        if depth != self.tree.max_depth() {
+            self.tree.set_max_depth(depth);
+            self.tree_dirty = true;
+        }
+        self.status_message = Some((format!("Tree depth {depth}"), Instant::now()));
+        self.save_project_state();
+    }
+
+    /// Resize the tree panel to `percent` of the screen, within bounds.
+    fn set_tree_width(&mut self, percent: u16) {
+        self.tree_width_percent = percent.clamp(MIN_TREE_WIDTH, MAX_TREE_WIDTH);
+        let message = format!("Tree width {}%", self.tree_width_percent);
+        self.status_message = Some((message, Instant::now()));
+        self.save_project_state();
+    }
+
+    /// Show a message on the terminal pane's border for a few seconds.
+    pub fn show_message(&mut self, message: String) {
+        self.status_message = Some((message, Instant::now()));
+    }
+
+    /// Remember the current hidden files, depth and tree width for this project.
+    fn save_project_state(&mut self) {
+        let Some(file) = &self.state_file else {
+            return;
+        };
+        let state = ProjectState {
+            show_hidden: Some(self.tree.show_hidden),
+            depth: Some(self.tree.max_depth()),
+            tree_width: Some(self.tree_width_percent),
+        };
+        if let Err(e) = state.save(file) {
+            self.status_message = Some((format!("{e:#}"), Instant::now()));
+        }
+    }
+
+    /// Leave a zoom whose pane no longer has the keyboard: focusing the tree
+    /// shows it again, and leaving tree focus brings back the terminal.
+    pub fn sync_zoom(&mut self) {
+        let hidden = match self.zoom {
+            Zoom::Off => return,
+            Zoom::Terminal => self.tree_focus,
+            Zoom::Tree => !self.tree_focus,
+        };
+        if hidden {
+            self.zoom = Zoom::Off;
+        }
+    }
+
+    fn send_signal(&mut self, signal: ChildSignal) -> Result<String> {
+        self.terminal
+            .signal(signal)
+            .map(|pgid| signal_message(signal, pgid))
+    }
+
+    /// What keys do right now, for the status bar.
+    pub fn mode_label(&self) -> &'static str {
+        if self.finder.is_some() || self.filter_input.is_some() || self.scroll_search.is_some() {
+            "search"
+        } else if self.palette.is_some() {
+            "palette"
+        } else if self.command_mode {
+            "command"
+        } else if self.diff_view.is_some() {
+            "diff"
+        } else if self.tree_focus && self.changes_view {
+            "changes"
+        } else if self.tree_focus {
+            "tree"
+        } else {
+            "normal"
+        }
+    }
+
+    /// Text for the line on the terminal pane's bottom border, if anything needs saying.
+    pub fn status_line(&self) -> Option<String> {
+        let pid = self
+            .terminal
+            .child_pid()
+            .map(|pid| pid.to_string())
+            .unwrap_or_else(|| "-".into());
+        let pgid = self
+            .terminal
+            .foreground_pgid()
+            .map(|pgid| pgid.to_string())
+            .unwrap_or_else(|| "-".into());
+        if self.command_mode {
+            return Some(format!(
+                " {}  [Esc] cancel · pid {pid} · fg pgid {pgid} ",
+                self.actions.hints()
+            ));
+        }
+        if let Some((message, at)) = &self.status_message {
+            if at.elapsed() < STATUS_MESSAGE_TTL {
+                return Some(format!(" {message} "));
+            }
+        }
+        if self.tree_focus && self.changes_view {
+            return Some(" changes · [j/k] scroll  [s/Esc] tree  [q] back ".to_string());
+        }
+        if self.tree_focus {
+            return Some(format!(
+                " tree · [j/k] move  [h/l] collapse/expand  [Enter] toggle  {}  [Esc] back ",
+                self.actions.tree_hints()
+            ));
+        }
+        if self.terminal.is_suspended() {
+            return Some(format!(
+                " stopped · pid {pid} · fg pgid {pgid} · {} z to resume ",
+                self.prefix
+            ));
+        }
+        None
+    }
+
     pub fn handle_paste(&mut self, text: String) {
         self.selection = None;
+        let multi_line = text.contains('\n') || text.contains('\r');
+        if self.paste_confirm_bytes > 0 && multi_line && text.len() > self.paste_confirm_bytes {
+            self.pending_paste = Some(text);
+            return;
+        }
         self.terminal.handle_paste(text);
     }
 
+    fn handle_paste_prompt_key(&mut self, key: KeyEvent) {
+        if key.kind == KeyEventKind::Release {
+            return;
+        }
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                if let Some(text) = self.pending_paste.take() {
+                    self.terminal.handle_paste(text);
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.pending_paste = None;
+            }
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.pending_paste = None;
+            }
+            _ => {}
+        }
+    }
+
     pub fn handle_mouse(&mut self, event: MouseEvent) {
         let in_tree = self.tree_area.is_some_and(|area| {
             event.column >= area.x
@@ -95,7 +1255,21 @@ impl App {
                 && event.row < area.y + area.height
         });
 
+        let in_preview = self.preview_open
+            && self.preview_area.is_some_and(|area| {
+                event.column >= area.x
+                    && event.column < area.x + area.width
+                    && event.row >= area.y
+                    && event.row < area.y + area.height
+            });
+
         match event.kind {
+            MouseEventKind::Moved if in_tree => {
+                self.hovered = self.tree_file_at(event.row);
+                self.update_preview();
+            }
+            MouseEventKind::ScrollUp if in_preview => self.scroll_preview(-3),
+            MouseEventKind::ScrollDown if in_preview => self.scroll_preview(3),
             MouseEventKind::ScrollUp => {
                 if in_tree {
                     let offset = self.tree.offset();
@@ -114,8 +1288,29 @@ impl App {
                     self.terminal.scroll_down();
                 }
             }
+            MouseEventKind::Down(MouseButton::Left)
+                if self
+                    .panes
+                    .is_some_and(|p| p.on_border(event.column, event.row)) =>
+            {
+                self.selection = None;
+                self.resizing_tree = true;
+            }
+            MouseEventKind::Drag(MouseButton::Left) if self.resizing_tree => {
+                if let Some(panes) = self.panes {
+                    let percent = panes.tree_percent_at(event.column, event.row);
+                    self.tree_width_percent = percent.clamp(MIN_TREE_WIDTH, MAX_TREE_WIDTH);
+                }
+            }
+            MouseEventKind::Up(MouseButton::Left) if self.resizing_tree => {
+                self.resizing_tree = false;
+                self.save_project_state();
+            }
             MouseEventKind::Down(MouseButton::Left) => {
-                if in_terminal {
+                if in_tree {
+                    self.selection = None;
+                    self.handle_tree_click(event.column, event.row);
+                } else if in_terminal {
                     let area = self.terminal_area.unwrap();
                     let col = event.column.saturating_sub(area.x);
                     let row = event.row.saturating_sub(area.y);
@@ -157,12 +1352,129 @@ impl App {
         }
     }
 
+    /// The file on a screen row of the tree pane, if the row shows a file.
+    fn tree_file_at(&self, row: u16) -> Option<PathBuf> {
+        let area = self.tree_area?;
+        let index = self.tree.offset() + row.checked_sub(area.y)? as usize;
+        let node = self.tree.nodes().get(index)?;
+        (!node.is_dir && node.more.is_none()).then(|| node.path.clone())
+    }
+
+    fn handle_tree_click(&mut self, column: u16, row: u16) {
+        let Some(area) = self.tree_area else {
+            return;
+        };
+        let index = self.tree.offset() + row.saturating_sub(area.y) as usize;
+        let Some(node) = self.tree.nodes().get(index) else {
+            return;
+        };
+        let icon = area.x + icon_column(node);
+        // A summarized directory's `… N more files` row shows more wherever it is clicked
+        let on_icon = (icon..icon + self.icons.column_width()).contains(&column);
+        if node.more.is_some() || (node.is_dir && on_icon) {
+            self.tree.toggle(index);
+        } else if self.tree_focus {
+            self.tree.select(index);
+        }
+    }
+
     pub fn handle_file_change(&mut self, path: PathBuf) {
-        // Refresh tree if file changed
+        self.record_change(&path);
+        if self.preview.as_ref().is_some_and(|p| p.path() == path) {
+            self.preview_dirty = true;
+        }
+        if self.diff_view.as_ref().is_some_and(|v| v.diff.path == path) {
+            self.diff_dirty = true;
+        }
+        // Queue for the next tick, which patches the tree in one batch
         if path.starts_with(self.tree.root_path()) {
-            self.tree.refresh();
+            self.pending_changes.insert(path);
+        }
+        // Content edits and index updates change git status without touching listings
+        self.git_dirty = true;
+        self.sizes_dirty = true;
+    }
+
+    /// Count a watcher event towards the files changed this session.
+    /// Must run before the change is applied to the tree, whose listings tell
+    /// whether the file existed before.
+    fn record_change(&mut self, path: &Path) {
+        let root = self.tree.root_path();
+        let Ok(rel) = path.strip_prefix(root) else {
+            return;
+        };
+        let hidden = rel
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'));
+        if (hidden && !self.tree.show_hidden)
+            || self.tree.git_status_of(path, false) == Some(GitStatus::Ignored)
+        {
+            return;
+        }
+        // Directories change whenever their entries do; only files are counted
+        let listed = self.tree.listed_kind(path);
+        if listed == Some(true) || path.is_dir() {
+            return;
+        }
+        let exists = path.exists();
+        let parent_read = path.parent().is_some_and(|p| self.tree.has_listing(p));
+        // A vanished file is only a deletion if it was seen to exist
+        if !exists && listed.is_none() && self.changes.get(path).is_none() {
+            return;
+        }
+        // Entries in directories never read are assumed to have been there
+        let existed_before = listed.is_some() || !parent_read;
+        if exists {
+            self.baselines.remember(path);
+        }
+        self.changes
+            .record(path.to_path_buf(), existed_before, exists, Instant::now());
+    }
+
+    /// Re-read every visible directory in the background.
+    fn start_scan(&mut self) {
+        if let Some(scanner) = self.scanner.as_mut() {
+            scanner.start(self.tree.begin_scan());
+        }
+    }
+
+    pub fn handle_tree_scanned(&mut self, generation: u64, result: ScanResult) {
+        let current = self
+            .scanner
+            .as_mut()
+            .is_some_and(|scanner| scanner.finish(generation));
+        if current && self.tree.finish_scan(result) {
+            self.tree_loading = false;
+        }
+    }
+
+    pub fn handle_git_status(&mut self, root: PathBuf, status: Option<GitStatusMap>) {
+        if let Some(reader) = self.git_reader.as_mut() {
+            reader.finish();
+        }
+        // A read for a previous root; the switch already queued a new one
+        if root == self.tree.root_path() {
+            self.tree.set_git_status(status);
         }
     }
+
+    pub fn handle_dir_sizes(&mut self, sizes: DirSizes) {
+        if let Some(reader) = self.size_reader.as_mut() {
+            reader.finish();
+        }
+        if sizes.root() == self.tree.root_path() {
+            self.tree.set_dir_sizes(Some(sizes));
+        }
+    }
+
+    /// Entries read so far by the running tree scan, if any.
+    pub fn scan_progress(&self) -> Option<usize> {
+        self.scanner.as_ref().and_then(TreeScanner::progress)
+    }
+}
+
+fn signal_message(signal: ChildSignal, pgid: i32) -> String {
+    format!("sent {} to process group {pgid}", signal.name())
 }
 
 pub(crate) fn copy_to_clipboard(text: &str) -> bool {
@@ -0,0 +1,314 @@
+//! asciicast v2 reader/writer.
+//!
+//! Format reference: <https://docs.asciinema.org/manual/asciicast/v2/>.
+//! The header may carry an extra `cltree` object with the tree root and a
+//! snapshot of the tree entries at the time recording started.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Header {
+    pub width: u16,
+    pub height: u16,
+    pub timestamp: Option<u64>,
+    pub env: Vec<(String, String)>,
+    /// Tree root at the time of recording
+    pub root: Option<PathBuf>,
+    /// Tree entries relative to `root`, in display order; directories end with `/`
+    pub tree: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Output,
+    Input,
+    Resize,
+    Marker,
+}
+
+impl EventKind {
+    fn code(self) -> &'static str {
+        match self {
+            EventKind::Output => "o",
+            EventKind::Input => "i",
+            EventKind::Resize => "r",
+            EventKind::Marker => "m",
+        }
+    }
+
+    fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "o" => Some(EventKind::Output),
+            "i" => Some(EventKind::Input),
+            "r" => Some(EventKind::Resize),
+            "m" => Some(EventKind::Marker),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CastEvent {
+    /// Seconds since the start of the recording
+    pub time: f64,
+    pub kind: EventKind,
+    pub data: String,
+}
+
+impl CastEvent {
+    /// `(cols, rows)` for a resize event
+    pub fn resize(&self) -> Option<(u16, u16)> {
+        if self.kind != EventKind::Resize {
+            return None;
+        }
+        let (w, h) = self.data.split_once('x')?;
+        Some((w.parse().ok()?, h.parse().ok()?))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cast {
+    pub header: Header,
+    pub events: Vec<CastEvent>,
+}
+
+impl Cast {
+    pub fn read(path: &Path) -> Result<Self> {
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty());
+        let Some((_, first)) = lines.next() else {
+            bail!("empty recording");
+        };
+
+        let header = serde_json::from_str::<HeaderJson>(first)
+            .context("header")?
+            .into_header()?;
+
+        let mut events = Vec::new();
+        for (idx, line) in lines {
+            let (time, code, data): (f64, String, String) = serde_json::from_str(line)
+                .with_context(|| format!("line {}: malformed event", idx + 1))?;
+            // Unknown event types are skipped, as the spec asks players to do
+            if let Some(kind) = EventKind::from_code(&code) {
+                events.push(CastEvent { time, kind, data });
+            }
+        }
+
+        Ok(Self { header, events })
+    }
+
+    /// Time of the last event in seconds
+    pub fn duration(&self) -> f64 {
+        self.events.last().map(|e| e.time).unwrap_or(0.0)
+    }
+}
+
+/// The header line as stored in the file.
+#[derive(Debug, Serialize, Deserialize)]
+struct HeaderJson {
+    version: u32,
+    width: u16,
+    height: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp: Option<u64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cltree: Option<CltreeJson>,
+}
+
+/// cltree's own header extension; other players ignore it.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CltreeJson {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    root: Option<PathBuf>,
+    #[serde(default)]
+    tree: Vec<String>,
+}
+
+impl HeaderJson {
+    fn from_header(header: &Header) -> Self {
+        let timestamp = header.timestamp.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+        let cltree = (header.root.is_some() || !header.tree.is_empty()).then(|| CltreeJson {
+            root: header.root.clone(),
+            tree: header.tree.clone(),
+        });
+        Self {
+            version: 2,
+            width: header.width,
+            height: header.height,
+            timestamp: Some(timestamp),
+            env: header.env.iter().cloned().collect(),
+            cltree,
+        }
+    }
+
+    fn into_header(self) -> Result<Header> {
+        if self.version != 2 {
+            bail!("only asciicast version 2 is supported");
+        }
+        let cltree = self.cltree.unwrap_or_default();
+        Ok(Header {
+            width: self.width,
+            height: self.height,
+            timestamp: self.timestamp,
+            env: self.env.into_iter().collect(),
+            root: cltree.root,
+            tree: cltree.tree,
+        })
+    }
+}
+
+/// Streams a session to an asciicast v2 file.
+pub struct Recorder {
+    out: BufWriter<File>,
+    start: Instant,
+    record_input: bool,
+    // Trailing bytes of an incomplete UTF-8 sequence split across PTY reads
+    pending: Vec<u8>,
+    failed: bool,
+}
+
+impl Recorder {
+    pub fn create(path: &Path, header: &Header, record_input: bool) -> Result<Self> {
+        let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+        let mut recorder = Self {
+            out: BufWriter::new(file),
+            start: Instant::now(),
+            record_input,
+            pending: Vec::new(),
+            failed: false,
+        };
+        let line = serde_json::to_string(&HeaderJson::from_header(header))?;
+        recorder.write_line(&line);
+        if recorder.failed {
+            bail!("failed to write {}", path.display());
+        }
+        Ok(recorder)
+    }
+
+    /// Record bytes the child wrote to the terminal.
+    pub fn output(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+        let valid_up_to = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            // Invalid (not just truncated) data: emit everything lossily
+            Err(_) => self.pending.len(),
+        };
+        if valid_up_to == 0 {
+            return;
+        }
+        let chunk: Vec<u8> = self.pending.drain(..valid_up_to).collect();
+        self.event(EventKind::Output, &String::from_utf8_lossy(&chunk));
+    }
+
+    /// Record bytes sent to the child, if input recording is enabled.
+    pub fn input(&mut self, bytes: &[u8]) {
+        if self.record_input {
+            self.event(EventKind::Input, &String::from_utf8_lossy(bytes));
+        }
+    }
+
+    pub fn resize(&mut self, cols: u16, rows: u16) {
+        self.event(EventKind::Resize, &format!("{cols}x{rows}"));
+    }
+
+    fn event(&mut self, kind: EventKind, data: &str) {
+        // Microsecond precision, like asciinema's own recorder
+        let time = (self.start.elapsed().as_secs_f64() * 1e6).round() / 1e6;
+        match serde_json::to_string(&(time, kind.code(), data)) {
+            Ok(line) => self.write_line(&line),
+            Err(_) => self.failed = true,
+        }
+    }
+
+    fn write_line(&mut self, line: &str) {
+        // A failing disk should not take the session down; stop recording instead
+        if self.failed {
+            return;
+        }
+        let ok = self.out.write_all(line.as_bytes()).is_ok()
+            && self.out.write_all(b"\n").is_ok()
+            && self.out.flush().is_ok();
+        self.failed = !ok;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_round_trip_control_characters() {
+        let original = "a\"b\\c\r\n\t\x1b[31m\u{7f}é🙂";
+        let line = serde_json::to_string(&(0.5, "o", original)).unwrap();
+        assert!(!line.contains('\x1b'));
+
+        let cast = Cast::parse(&format!(
+            "{{\"version\": 2, \"width\": 80, \"height\": 24}}\n{line}"
+        ))
+        .unwrap();
+        assert_eq!(cast.events[0].data, original);
+    }
+
+    #[test]
+    fn parses_surrogate_pairs() {
+        let header = "{\"version\": 2, \"width\": 80, \"height\": 24}\n";
+        let cast = Cast::parse(&format!(r#"{header}[0.1, "o", "\uD83D\uDE42"]"#)).unwrap();
+        assert_eq!(cast.events[0].data, "🙂");
+
+        // A lone low surrogate, and a high surrogate followed by a non-surrogate
+        assert!(Cast::parse(&format!(r#"{header}[0.1, "o", "\uDE42"]"#)).is_err());
+        assert!(Cast::parse(&format!(r#"{header}[0.1, "o", "\uD83D\u0041"]"#)).is_err());
+    }
+
+    #[test]
+    fn header_round_trips_cltree_extension() {
+        let header = Header {
+            width: 120,
+            height: 40,
+            timestamp: Some(1_700_000_000),
+            env: vec![("TERM".into(), "xterm-256color".into())],
+            root: Some(PathBuf::from("/work/project")),
+            tree: vec!["src/".into(), "src/main.rs".into()],
+        };
+        let line = serde_json::to_string(&HeaderJson::from_header(&header)).unwrap();
+        let parsed = Cast::parse(&line).unwrap().header;
+        assert_eq!(parsed, header);
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        assert!(Cast::parse("{\"version\": 1, \"width\": 80, \"height\": 24}").is_err());
+    }
+
+    #[test]
+    fn resize_event_dimensions() {
+        let event = CastEvent {
+            time: 0.0,
+            kind: EventKind::Resize,
+            data: "100x30".into(),
+        };
+        assert_eq!(event.resize(), Some((100, 30)));
+    }
+}
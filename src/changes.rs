@@ -0,0 +1,151 @@
+use ratatui::style::Color;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How long a change stays highlighted in the tree, fading out towards the end.
+pub const RECENT_FADE: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+impl ChangeKind {
+    pub fn badge(self) -> char {
+        match self {
+            ChangeKind::Created => '+',
+            ChangeKind::Modified => '~',
+            ChangeKind::Deleted => '-',
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            ChangeKind::Created => Color::Rgb(120, 220, 120),
+            ChangeKind::Modified => Color::Rgb(230, 190, 80),
+            ChangeKind::Deleted => Color::Rgb(230, 100, 100),
+        }
+    }
+}
+
+/// One file touched during the session.
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub count: usize,
+    pub last: Instant,
+    // Whether the file was there before its first change this session
+    existed_before: bool,
+    exists: bool,
+}
+
+impl FileChange {
+    pub fn kind(&self) -> ChangeKind {
+        match (self.existed_before, self.exists) {
+            (_, false) => ChangeKind::Deleted,
+            (false, true) => ChangeKind::Created,
+            (true, true) => ChangeKind::Modified,
+        }
+    }
+
+    /// Highlight strength from 1.0 (just changed) down to 0.0 after [`RECENT_FADE`].
+    pub fn recency(&self, now: Instant) -> f32 {
+        let age = now.saturating_duration_since(self.last);
+        1.0 - (age.as_secs_f32() / RECENT_FADE.as_secs_f32()).min(1.0)
+    }
+}
+
+/// Files changed since cltree started, from watcher events.
+#[derive(Debug, Default)]
+pub struct SessionChanges {
+    files: HashMap<PathBuf, FileChange>,
+}
+
+impl SessionChanges {
+    /// Count a change to `path`. `existed_before` only matters the first time a path is seen.
+    pub fn record(&mut self, path: PathBuf, existed_before: bool, exists: bool, now: Instant) {
+        // Files created and removed again (editor swap files, atomic saves) leave no trace
+        let transient = self
+            .files
+            .get(&path)
+            .map_or(!existed_before, |change| !change.existed_before);
+        if transient && !exists {
+            self.files.remove(&path);
+            return;
+        }
+        let change = self.files.entry(path).or_insert(FileChange {
+            count: 0,
+            last: now,
+            existed_before,
+            exists,
+        });
+        change.count += 1;
+        change.last = now;
+        change.exists = exists;
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&FileChange> {
+        self.files.get(path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Changed files, most recent first.
+    pub fn by_recency(&self) -> Vec<(&Path, &FileChange)> {
+        let mut files: Vec<_> = self
+            .files
+            .iter()
+            .map(|(path, change)| (path.as_path(), change))
+            .collect();
+        files.sort_by(|a, b| b.1.last.cmp(&a.1.last).then_with(|| a.0.cmp(b.0)));
+        files
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds_follow_the_first_and_latest_state() {
+        let start = Instant::now();
+        let mut changes = SessionChanges::default();
+        changes.record("/p/new.rs".into(), false, true, start);
+        changes.record("/p/old.rs".into(), true, true, start);
+        changes.record("/p/old.rs".into(), false, true, start);
+        changes.record("/p/gone.rs".into(), true, false, start);
+        // Created then deleted again is forgotten
+        changes.record("/p/tmp.rs".into(), false, true, start);
+        changes.record("/p/tmp.rs".into(), false, false, start);
+        assert!(changes.get(Path::new("/p/tmp.rs")).is_none());
+
+        let kind = |p: &str| changes.get(Path::new(p)).unwrap().kind();
+        assert_eq!(kind("/p/new.rs"), ChangeKind::Created);
+        assert_eq!(kind("/p/old.rs"), ChangeKind::Modified);
+        assert_eq!(kind("/p/gone.rs"), ChangeKind::Deleted);
+        assert_eq!(changes.get(Path::new("/p/old.rs")).unwrap().count, 2);
+    }
+
+    #[test]
+    fn most_recent_first_and_fading() {
+        let start = Instant::now();
+        let mut changes = SessionChanges::default();
+        changes.record("/p/a".into(), true, true, start);
+        changes.record("/p/b".into(), true, true, start + Duration::from_secs(1));
+        let order: Vec<_> = changes.by_recency().iter().map(|(p, _)| *p).collect();
+        assert_eq!(order, [Path::new("/p/b"), Path::new("/p/a")]);
+
+        let a = changes.get(Path::new("/p/a")).unwrap();
+        assert_eq!(a.recency(start), 1.0);
+        assert!(a.recency(start + RECENT_FADE / 2) > 0.4);
+        assert_eq!(a.recency(start + RECENT_FADE * 2), 0.0);
+    }
+}
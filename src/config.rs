@@ -0,0 +1,409 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::keys::KeyChord;
+use crate::ui::{IconTheme, Segment, TreeColumns, TreeSide};
+
+/// Project-local config file name, looked up in the working directory.
+pub const PROJECT_CONFIG_FILE: &str = ".cltree.toml";
+
+/// Where a config file was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// `~/.config/cltree/config.toml`, written by the user.
+    Global,
+    /// `<project>/.cltree.toml`, which arrives with whatever repository was
+    /// cloned, so it may not change the environment Claude runs in.
+    Project,
+}
+
+/// Environment changes applied to the spawned child process.
+///
+/// Within one source, unsets run before sets so an explicit `--env` survives a
+/// wildcard scrub like `--unset-env 'AWS_*'`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EnvOverrides {
+    pub set: Vec<(String, String)>,
+    pub unset: Vec<String>,
+}
+
+impl EnvOverrides {
+    /// Parse a `KEY=VAL` argument.
+    pub fn parse_assignment(arg: &str) -> Result<(String, String)> {
+        match arg.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+            _ => bail!("invalid environment assignment '{arg}', expected KEY=VALUE"),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.unset.is_empty()
+    }
+}
+
+/// Whether an unset pattern matches a variable name. A trailing `*` matches any suffix.
+pub fn env_pattern_matches(pattern: &str, key: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => key.starts_with(prefix),
+        None => pattern == key,
+    }
+}
+
+/// A config file as written. Names are checked when it is merged into a
+/// [`Config`]; unknown keys are ignored so older builds tolerate newer files.
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    paste_confirm_bytes: Option<usize>,
+    prefix: Option<String>,
+    palette: Option<String>,
+    icons: Option<String>,
+    columns: Option<Vec<String>>,
+    max_children: Option<usize>,
+    compact: Option<bool>,
+    layout: Option<String>,
+    stack_below: Option<u16>,
+    status_bar: Option<Vec<String>>,
+    #[serde(default)]
+    unset_env: Vec<String>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+}
+
+/// Settings read from `~/.config/cltree/config.toml` and `<project>/.cltree.toml`.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// Each source's env changes, in the order they must be applied.
+    pub env_layers: Vec<EnvOverrides>,
+    /// Multi-line pastes larger than this ask for confirmation; 0 never asks.
+    pub paste_confirm_bytes: Option<usize>,
+    /// Key that starts a cltree command (signals, tree focus).
+    pub prefix: Option<KeyChord>,
+    /// Key that opens the command palette directly.
+    pub palette: Option<KeyChord>,
+    /// Glyph set for tree nodes.
+    pub icons: Option<IconTheme>,
+    /// Metadata columns shown in the tree at startup.
+    pub columns: Option<TreeColumns>,
+    /// Children a directory shows before the rest are summarized; 0 shows all.
+    pub max_children: Option<usize>,
+    /// Merge chains of single subdirectories into one tree row.
+    pub compact: Option<bool>,
+    /// Where the tree pane sits next to the terminal.
+    pub layout: Option<TreeSide>,
+    /// Screens narrower than this many columns stack the panes; 0 never does.
+    pub stack_below: Option<u16>,
+    /// Status bar segments, left to right; an empty list hides the bar.
+    pub status_bar: Option<Vec<Segment>>,
+    /// Settings that were read but not applied, to tell the user about.
+    pub warnings: Vec<String>,
+}
+
+impl Config {
+    /// Load the global config, then the project config on top of it.
+    /// Missing files are not an error.
+    pub fn load(project_root: &Path) -> Result<Self> {
+        let mut config = Config::default();
+        let mut paths: Vec<(PathBuf, Source)> = Vec::new();
+        if let Some(dir) = dirs::config_dir() {
+            paths.push((dir.join("cltree").join("config.toml"), Source::Global));
+        }
+        paths.push((project_root.join(PROJECT_CONFIG_FILE), Source::Project));
+
+        for (path, source) in paths {
+            let text = match std::fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+            };
+            config
+                .merge_str(&text, source)
+                .with_context(|| format!("in {}", path.display()))?;
+        }
+        Ok(config)
+    }
+
+    /// Parse config text and layer it over the current values.
+    pub fn merge_str(&mut self, text: &str, source: Source) -> Result<()> {
+        let file: ConfigFile = toml::from_str(text)?;
+
+        let mut env = EnvOverrides {
+            set: file.env.into_iter().collect(),
+            unset: file.unset_env,
+        };
+        if source == Source::Project && !env.is_empty() {
+            // Someone else's repository must not keep cltree from starting either
+            self.warnings.push(format!(
+                "Ignored [env] and unset_env in {PROJECT_CONFIG_FILE}; \
+                 set them in ~/.config/cltree/config.toml or with --env/--unset-env"
+            ));
+            env = EnvOverrides::default();
+        }
+
+        if let Some(bytes) = file.paste_confirm_bytes {
+            self.paste_confirm_bytes = Some(bytes);
+        }
+        if let Some(spec) = file.prefix {
+            self.prefix = Some(KeyChord::parse(&spec).context("prefix")?);
+        }
+        if let Some(spec) = file.palette {
+            self.palette = Some(KeyChord::parse(&spec).context("palette")?);
+        }
+        if let Some(name) = file.icons {
+            let theme = IconTheme::parse(&name).with_context(|| {
+                format!(
+                    "unknown icon theme '{name}' (expected one of {})",
+                    IconTheme::names()
+                )
+            })?;
+            self.icons = Some(theme);
+        }
+        if let Some(list) = file.columns {
+            self.columns = Some(TreeColumns::parse(&list.join(",")).context("columns")?);
+        }
+        if let Some(max) = file.max_children {
+            self.max_children = Some(max);
+        }
+        if let Some(on) = file.compact {
+            self.compact = Some(on);
+        }
+        if let Some(name) = file.layout {
+            let side = TreeSide::parse(&name).with_context(|| {
+                format!(
+                    "unknown layout '{name}' (expected one of {})",
+                    TreeSide::names()
+                )
+            })?;
+            self.layout = Some(side);
+        }
+        if let Some(cols) = file.stack_below {
+            self.stack_below = Some(cols);
+        }
+        if let Some(list) = file.status_bar {
+            self.status_bar = Some(Segment::parse_list(&list.join(",")).context("status_bar")?);
+        }
+
+        self.env_layers.push(env);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_toml_strings_and_keys() {
+        let mut config = Config::default();
+        config
+            .merge_str(
+                "# comment\n\
+                 unset_env = [\"GITHUB_TOKEN\", 'AWS_*']\n\
+                 \n\
+                 [env]\n\
+                 ANTHROPIC_BASE_URL = \"https://example.test/#frag\" # trailing\n\
+                 \"WEIRD KEY\" = 'c:\\path'\n\
+                 QUOTED = \"say \\\"hi\\\"\\u00e9\"\n",
+                Source::Global,
+            )
+            .unwrap();
+
+        assert_eq!(
+            config.env_layers,
+            vec![EnvOverrides {
+                set: vec![
+                    (
+                        "ANTHROPIC_BASE_URL".into(),
+                        "https://example.test/#frag".into()
+                    ),
+                    ("QUOTED".into(), "say \"hi\"\u{e9}".into()),
+                    ("WEIRD KEY".into(), "c:\\path".into()),
+                ],
+                unset: vec!["GITHUB_TOKEN".into(), "AWS_*".into()],
+            }]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        let mut config = Config::default();
+        assert!(config.merge_str("[env", Source::Global).is_err());
+        assert!(config.merge_str("just words", Source::Global).is_err());
+        assert!(config.merge_str("prefix = \"open", Source::Global).is_err());
+        assert!(config
+            .merge_str("columns = [\"x\" \"y\"]", Source::Global)
+            .is_err());
+        assert!(config.env_layers.is_empty());
+    }
+
+    #[test]
+    fn project_config_cannot_change_env() {
+        let mut config = Config::default();
+        config
+            .merge_str(
+                "icons = \"nerd\"\nunset_env = [\"HOME\"]\n[env]\nPATH = \"/tmp/evil\"\n",
+                Source::Project,
+            )
+            .unwrap();
+        assert_eq!(config.env_layers, vec![EnvOverrides::default()]);
+        assert_eq!(config.icons, Some(IconTheme::Nerd));
+        assert_eq!(config.warnings.len(), 1);
+
+        config
+            .merge_str("icons = \"ascii\"\n", Source::Project)
+            .unwrap();
+        assert_eq!(config.warnings.len(), 1);
+    }
+
+    #[test]
+    fn config_collects_env_layer() {
+        let mut config = Config::default();
+        config
+            .merge_str(
+                "unset_env = [\"SECRET\"]\n[env]\nCOLORTERM = \"24bit\"\n",
+                Source::Global,
+            )
+            .unwrap();
+
+        assert_eq!(
+            config.env_layers,
+            vec![EnvOverrides {
+                set: vec![("COLORTERM".into(), "24bit".into())],
+                unset: vec!["SECRET".into()],
+            }]
+        );
+    }
+
+    #[test]
+    fn parses_prefix_key() {
+        let mut config = Config::default();
+        config
+            .merge_str("prefix = \"ctrl-b\"\n", Source::Global)
+            .unwrap();
+        assert_eq!(config.prefix, Some(KeyChord::parse("ctrl-b").unwrap()));
+        assert!(config
+            .merge_str("prefix = \"b\"\n", Source::Global)
+            .is_err());
+    }
+
+    #[test]
+    fn parses_palette_key() {
+        let mut config = Config::default();
+        config
+            .merge_str("palette = \"ctrl-space\"\n", Source::Global)
+            .unwrap();
+        assert_eq!(config.palette, Some(KeyChord::parse("ctrl-space").unwrap()));
+        assert!(config
+            .merge_str("palette = \"space\"\n", Source::Global)
+            .is_err());
+    }
+
+    #[test]
+    fn parses_icon_theme() {
+        let mut config = Config::default();
+        config
+            .merge_str("icons = \"nerd\"\n", Source::Global)
+            .unwrap();
+        assert_eq!(config.icons, Some(IconTheme::Nerd));
+        assert!(config
+            .merge_str("icons = \"fancy\"\n", Source::Global)
+            .is_err());
+    }
+
+    #[test]
+    fn parses_column_list() {
+        let mut config = Config::default();
+        config
+            .merge_str("columns = [\"size\", \"modified\"]\n", Source::Global)
+            .unwrap();
+        let columns = config.columns.unwrap();
+        assert!(columns.size && columns.modified && !columns.permissions);
+        assert!(config
+            .merge_str("columns = [\"owner\"]\n", Source::Global)
+            .is_err());
+        assert!(config
+            .merge_str("columns = \"size\"\n", Source::Global)
+            .is_err());
+    }
+
+    #[test]
+    fn parses_tree_size_limits() {
+        let mut config = Config::default();
+        config
+            .merge_str(
+                "max_children = 200
+compact = false
+",
+                Source::Global,
+            )
+            .unwrap();
+        assert_eq!(config.max_children, Some(200));
+        assert_eq!(config.compact, Some(false));
+        assert!(config
+            .merge_str("compact = \"no\"\n", Source::Global)
+            .is_err());
+        assert!(config
+            .merge_str("max_children = -1\n", Source::Global)
+            .is_err());
+    }
+
+    #[test]
+    fn parses_layout() {
+        let mut config = Config::default();
+        config
+            .merge_str("layout = \"bottom\"\nstack_below = 0\n", Source::Global)
+            .unwrap();
+        assert_eq!(config.layout, Some(TreeSide::Bottom));
+        assert_eq!(config.stack_below, Some(0));
+        assert!(config
+            .merge_str("layout = \"middle\"\n", Source::Global)
+            .is_err());
+    }
+
+    #[test]
+    fn parses_status_bar_segments() {
+        let mut config = Config::default();
+        config
+            .merge_str("status_bar = [\"git\", \"mode\"]\n", Source::Global)
+            .unwrap();
+        assert_eq!(config.status_bar, Some(vec![Segment::Git, Segment::Mode]));
+        config
+            .merge_str("status_bar = []\n", Source::Global)
+            .unwrap();
+        assert_eq!(config.status_bar, Some(Vec::new()));
+        assert!(config
+            .merge_str("status_bar = [\"clock\"]\n", Source::Global)
+            .is_err());
+    }
+
+    #[test]
+    fn later_files_override_paste_threshold() {
+        let mut config = Config::default();
+        config
+            .merge_str("paste_confirm_bytes = 100\n", Source::Global)
+            .unwrap();
+        config
+            .merge_str("paste_confirm_bytes = 0\n", Source::Global)
+            .unwrap();
+        assert_eq!(config.paste_confirm_bytes, Some(0));
+        assert!(config
+            .merge_str("paste_confirm_bytes = \"big\"\n", Source::Global)
+            .is_err());
+    }
+
+    #[test]
+    fn env_assignment_and_patterns() {
+        assert_eq!(
+            EnvOverrides::parse_assignment("A=b=c").unwrap(),
+            ("A".to_string(), "b=c".to_string())
+        );
+        assert!(EnvOverrides::parse_assignment("=x").is_err());
+        assert!(EnvOverrides::parse_assignment("NOVALUE").is_err());
+
+        assert!(env_pattern_matches("AWS_*", "AWS_SECRET_ACCESS_KEY"));
+        assert!(!env_pattern_matches("AWS_*", "XAWS_"));
+        assert!(env_pattern_matches("TOKEN", "TOKEN"));
+        assert!(!env_pattern_matches("TOKEN", "TOKENS"));
+    }
+}
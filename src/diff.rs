@@ -0,0 +1,507 @@
+use similar::{ChangeTag, TextDiff};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::preview::MAX_PREVIEW_BYTES;
+
+/// Unchanged lines shown around each change.
+const CONTEXT_LINES: usize = 3;
+
+/// What a file is being compared against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaselineSource {
+    /// The committed version, for files tracked by git
+    Head,
+    /// The contents when cltree first saw the file, or when the baseline was last accepted
+    Snapshot,
+}
+
+impl BaselineSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            BaselineSource::Head => "HEAD",
+            BaselineSource::Snapshot => "snapshot",
+        }
+    }
+}
+
+/// Why a file cannot be shown as a line diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Undiffable {
+    /// The baseline or the current file is binary; holds its size in bytes
+    Binary(u64),
+    /// The baseline or the current file is over `MAX_PREVIEW_BYTES`; holds its size
+    TooLarge(u64),
+}
+
+/// One side of a diff.
+#[derive(Clone)]
+enum Content {
+    // A missing file diffs as empty, so creations and deletions show every line
+    Missing,
+    Text(String),
+    Undiffable(Undiffable),
+}
+
+impl Content {
+    fn from_bytes(bytes: Vec<u8>) -> Self {
+        let size = bytes.len() as u64;
+        if size > MAX_PREVIEW_BYTES {
+            return Content::Undiffable(Undiffable::TooLarge(size));
+        }
+        if bytes.contains(&0) {
+            return Content::Undiffable(Undiffable::Binary(size));
+        }
+        match String::from_utf8(bytes) {
+            Ok(text) => Content::Text(text),
+            Err(_) => Content::Undiffable(Undiffable::Binary(size)),
+        }
+    }
+
+    fn text(&self) -> Result<&str, Undiffable> {
+        match self {
+            Content::Missing => Ok(""),
+            Content::Text(text) => Ok(text),
+            Content::Undiffable(reason) => Err(*reason),
+        }
+    }
+}
+
+struct Snapshot {
+    content: Content,
+    // Accepted snapshots take precedence over HEAD
+    accepted: bool,
+}
+
+/// Baseline contents for files seen during the session.
+#[derive(Default)]
+pub struct Baselines {
+    snapshots: HashMap<PathBuf, Snapshot>,
+}
+
+impl Baselines {
+    /// Remember `path`'s current contents, unless it already has a baseline.
+    pub fn remember(&mut self, path: &Path) {
+        if !self.snapshots.contains_key(path) {
+            let content = read_content(path);
+            self.snapshots.insert(
+                path.to_path_buf(),
+                Snapshot {
+                    content,
+                    accepted: false,
+                },
+            );
+        }
+    }
+
+    /// Make the current contents the baseline, replacing any earlier snapshot and HEAD.
+    pub fn accept(&mut self, path: &Path) {
+        let content = read_content(path);
+        self.snapshots.insert(
+            path.to_path_buf(),
+            Snapshot {
+                content,
+                accepted: true,
+            },
+        );
+    }
+
+    /// Diff the file on disk against its baseline: an accepted snapshot, else HEAD
+    /// for tracked files, else the first snapshot (taken now if there is none).
+    pub fn diff(&mut self, path: &Path) -> FileDiff {
+        let accepted = self
+            .snapshots
+            .get(path)
+            .filter(|s| s.accepted)
+            .map(|s| s.content.clone());
+        let (old, source) = match accepted {
+            Some(content) => (content, BaselineSource::Snapshot),
+            None => match head_content(path) {
+                Some(head) => (head, BaselineSource::Head),
+                None => {
+                    self.remember(path);
+                    let content = self.snapshots[path].content.clone();
+                    (content, BaselineSource::Snapshot)
+                }
+            },
+        };
+        let new = read_content(path);
+        // The current file's reason wins, as that is what the user is looking at
+        match (old.text(), new.text()) {
+            (Ok(old), Ok(new)) => FileDiff::compute(path, source, old, new),
+            (_, Err(reason)) | (Err(reason), _) => FileDiff::undiffable(path, source, reason),
+        }
+    }
+}
+
+/// File contents, or why they cannot be diffed.
+fn read_content(path: &Path) -> Content {
+    let Ok(meta) = fs::metadata(path) else {
+        return Content::Missing;
+    };
+    if !meta.is_file() {
+        return Content::Missing;
+    }
+    if meta.len() > MAX_PREVIEW_BYTES {
+        return Content::Undiffable(Undiffable::TooLarge(meta.len()));
+    }
+    match fs::read(path) {
+        Ok(bytes) => Content::from_bytes(bytes),
+        Err(_) => Content::Missing,
+    }
+}
+
+/// The committed contents of `path`, if it is tracked in a git repository.
+fn head_content(path: &Path) -> Option<Content> {
+    let dir = path.parent()?;
+    let name = path.file_name()?.to_string_lossy();
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["--no-optional-locks", "show"])
+        // `./` makes the path relative to -C rather than the repository root
+        .arg(format!("HEAD:./{name}"))
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(Content::from_bytes(output.stdout))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineTag {
+    Context,
+    Removed,
+    Added,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffLine {
+    pub tag: LineTag,
+    /// 1-based line numbers in the baseline and the current file
+    pub old_no: Option<usize>,
+    pub new_no: Option<usize>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+impl Hunk {
+    pub fn header(&self) -> String {
+        format!(
+            "@@ -{},{} +{},{} @@",
+            self.old_start, self.old_len, self.new_start, self.new_len
+        )
+    }
+}
+
+/// A line diff of one file against its baseline.
+pub struct FileDiff {
+    pub path: PathBuf,
+    pub source: BaselineSource,
+    pub hunks: Vec<Hunk>,
+    /// Set instead of hunks when either side is binary or too large
+    pub undiffable: Option<Undiffable>,
+}
+
+impl FileDiff {
+    pub fn compute(path: &Path, source: BaselineSource, old: &str, new: &str) -> Self {
+        let diff = TextDiff::from_lines(old, new);
+        let hunks = diff
+            .grouped_ops(CONTEXT_LINES)
+            .iter()
+            .filter_map(|ops| {
+                let (first, last) = (ops.first()?, ops.last()?);
+                let old_range = first.old_range().start..last.old_range().end;
+                let new_range = first.new_range().start..last.new_range().end;
+                let lines = ops
+                    .iter()
+                    .flat_map(|op| diff.iter_changes(op))
+                    .map(|change| DiffLine {
+                        tag: match change.tag() {
+                            ChangeTag::Equal => LineTag::Context,
+                            ChangeTag::Delete => LineTag::Removed,
+                            ChangeTag::Insert => LineTag::Added,
+                        },
+                        old_no: change.old_index().map(|i| i + 1),
+                        new_no: change.new_index().map(|i| i + 1),
+                        text: change.value().trim_end_matches(['\n', '\r']).to_string(),
+                    })
+                    .collect();
+                Some(Hunk {
+                    // Like `diff -u`, an empty range starts at the line before it
+                    old_start: old_range.start + usize::from(!old_range.is_empty()),
+                    old_len: old_range.len(),
+                    new_start: new_range.start + usize::from(!new_range.is_empty()),
+                    new_len: new_range.len(),
+                    lines,
+                })
+            })
+            .collect();
+        Self {
+            path: path.to_path_buf(),
+            source,
+            hunks,
+            undiffable: None,
+        }
+    }
+
+    pub fn undiffable(path: &Path, source: BaselineSource, reason: Undiffable) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            source,
+            hunks: Vec::new(),
+            undiffable: Some(reason),
+        }
+    }
+
+    /// No changes against the baseline. False when the file could not be diffed.
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty() && self.undiffable.is_none()
+    }
+
+    /// (added, removed) line counts.
+    pub fn stats(&self) -> (usize, usize) {
+        let lines = self.hunks.iter().flat_map(|h| &h.lines);
+        lines.fold((0, 0), |(added, removed), line| match line.tag {
+            LineTag::Added => (added + 1, removed),
+            LineTag::Removed => (added, removed + 1),
+            LineTag::Context => (added, removed),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLayout {
+    Unified,
+    SideBySide,
+}
+
+/// One display row of a diff.
+pub enum DiffRow<'a> {
+    Header(&'a Hunk),
+    Line(&'a DiffLine),
+    /// Side by side: the baseline line and the current line
+    Pair(Option<&'a DiffLine>, Option<&'a DiffLine>),
+}
+
+/// State of the diff popup.
+pub struct DiffView {
+    pub diff: FileDiff,
+    pub layout: DiffLayout,
+    pub scroll: usize,
+}
+
+impl DiffView {
+    pub fn new(diff: FileDiff) -> Self {
+        Self {
+            diff,
+            layout: DiffLayout::Unified,
+            scroll: 0,
+        }
+    }
+
+    /// Swap in a recomputed diff, keeping the scroll position where possible.
+    pub fn update(&mut self, diff: FileDiff) {
+        self.diff = diff;
+        self.scroll = self.scroll.min(self.rows().len().saturating_sub(1));
+    }
+
+    pub fn toggle_layout(&mut self) {
+        self.layout = match self.layout {
+            DiffLayout::Unified => DiffLayout::SideBySide,
+            DiffLayout::SideBySide => DiffLayout::Unified,
+        };
+        // Keep the current hunk in view
+        let hunk = self.current_hunk();
+        self.scroll = self.hunk_rows().get(hunk).copied().unwrap_or(0);
+    }
+
+    pub fn rows(&self) -> Vec<DiffRow<'_>> {
+        let mut rows = Vec::new();
+        for hunk in &self.diff.hunks {
+            rows.push(DiffRow::Header(hunk));
+            match self.layout {
+                DiffLayout::Unified => rows.extend(hunk.lines.iter().map(DiffRow::Line)),
+                DiffLayout::SideBySide => push_pairs(&mut rows, &hunk.lines),
+            }
+        }
+        rows
+    }
+
+    pub fn scroll_by(&mut self, delta: isize) {
+        let last = self.rows().len().saturating_sub(1);
+        self.scroll = self.scroll.saturating_add_signed(delta).min(last);
+    }
+
+    pub fn next_hunk(&mut self) {
+        if let Some(&row) = self.hunk_rows().iter().find(|&&row| row > self.scroll) {
+            self.scroll = row;
+        }
+    }
+
+    pub fn prev_hunk(&mut self) {
+        if let Some(&row) = self
+            .hunk_rows()
+            .iter()
+            .rev()
+            .find(|&&row| row < self.scroll)
+        {
+            self.scroll = row;
+        }
+    }
+
+    /// Index of the hunk at the top of the view.
+    pub fn current_hunk(&self) -> usize {
+        let rows = self.hunk_rows();
+        rows.iter()
+            .rposition(|&row| row <= self.scroll)
+            .unwrap_or(0)
+    }
+
+    fn hunk_rows(&self) -> Vec<usize> {
+        self.rows()
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| matches!(row, DiffRow::Header(_)))
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// Side-by-side rows for a hunk: removals paired with the additions that replace them.
+fn push_pairs<'a>(rows: &mut Vec<DiffRow<'a>>, lines: &'a [DiffLine]) {
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].tag == LineTag::Context {
+            rows.push(DiffRow::Pair(Some(&lines[i]), Some(&lines[i])));
+            i += 1;
+            continue;
+        }
+        let removed_end = i + lines[i..]
+            .iter()
+            .take_while(|l| l.tag == LineTag::Removed)
+            .count();
+        let added_end = removed_end
+            + lines[removed_end..]
+                .iter()
+                .take_while(|l| l.tag == LineTag::Added)
+                .count();
+        let removed = &lines[i..removed_end];
+        let added = &lines[removed_end..added_end];
+        for row in 0..removed.len().max(added.len()) {
+            rows.push(DiffRow::Pair(removed.get(row), added.get(row)));
+        }
+        i = added_end;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::git;
+
+    const OLD: &str = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+
+    #[test]
+    fn hunks_carry_context_and_line_numbers() {
+        let new = OLD.replace("b\n", "B\n").replace("k\n", "k\nk2\n");
+        let diff = FileDiff::compute(Path::new("/p/f"), BaselineSource::Snapshot, OLD, &new);
+        assert_eq!(diff.hunks.len(), 2);
+        assert_eq!(diff.hunks[0].header(), "@@ -1,5 +1,5 @@");
+        assert_eq!(diff.hunks[1].header(), "@@ -9,4 +9,5 @@");
+        assert_eq!(diff.stats(), (2, 1));
+
+        let added = diff.hunks[1]
+            .lines
+            .iter()
+            .find(|l| l.tag == LineTag::Added)
+            .unwrap();
+        assert_eq!((added.old_no, added.new_no), (None, Some(12)));
+        assert_eq!(added.text, "k2");
+    }
+
+    #[test]
+    fn hunk_navigation_and_side_by_side_pairs() {
+        let new = OLD.replace("b\n", "B\n").replace("k\n", "k\nk2\n");
+        let diff = FileDiff::compute(Path::new("/p/f"), BaselineSource::Snapshot, OLD, &new);
+        let mut view = DiffView::new(diff);
+        view.next_hunk();
+        assert_eq!(view.current_hunk(), 1);
+        view.prev_hunk();
+        assert_eq!((view.scroll, view.current_hunk()), (0, 0));
+
+        view.toggle_layout();
+        let rows = view.rows();
+        // The changed line sits next to its replacement
+        let paired = rows.iter().any(|row| {
+            matches!(row, DiffRow::Pair(Some(old), Some(new))
+                if old.text == "b" && new.text == "B")
+        });
+        assert!(paired);
+    }
+
+    #[test]
+    fn baselines_prefer_accepted_then_head_then_snapshot() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        git(root, &["init", "-q"]);
+        let tracked = root.join("tracked.txt");
+        let untracked = root.join("notes.txt");
+        fs::write(&tracked, "one\n").unwrap();
+        git(root, &["add", "."]);
+        git(root, &["commit", "-q", "-m", "init"]);
+        fs::write(&tracked, "two\n").unwrap();
+        fs::write(&untracked, "first\n").unwrap();
+
+        let mut baselines = Baselines::default();
+        // Tracked files compare against HEAD even without a snapshot
+        let diff = baselines.diff(&tracked);
+        assert_eq!((diff.source, diff.stats()), (BaselineSource::Head, (1, 1)));
+
+        // Untracked files start from their first snapshot
+        assert!(baselines.diff(&untracked).is_empty());
+        fs::write(&untracked, "first\nsecond\n").unwrap();
+        let diff = baselines.diff(&untracked);
+        assert_eq!(
+            (diff.source, diff.stats()),
+            (BaselineSource::Snapshot, (1, 0))
+        );
+
+        baselines.accept(&tracked);
+        let diff = baselines.diff(&tracked);
+        assert_eq!(diff.source, BaselineSource::Snapshot);
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn binary_and_large_files_are_not_diffed_against_empty_text() {
+        let temp = tempfile::tempdir().unwrap();
+        let blob = temp.path().join("blob.bin");
+        fs::write(&blob, "text\n").unwrap();
+        let mut baselines = Baselines::default();
+        assert!(baselines.diff(&blob).is_empty());
+
+        fs::write(&blob, [1, 0, 2]).unwrap();
+        let diff = baselines.diff(&blob);
+        assert_eq!(diff.undiffable, Some(Undiffable::Binary(3)));
+        assert!(!diff.is_empty());
+
+        let large = temp.path().join("large.txt");
+        let size = MAX_PREVIEW_BYTES + 1;
+        fs::write(&large, vec![b'a'; size as usize]).unwrap();
+        let diff = baselines.diff(&large);
+        assert_eq!(diff.undiffable, Some(Undiffable::TooLarge(size)));
+        assert_eq!(diff.stats(), (0, 0));
+    }
+}
@@ -6,9 +6,12 @@ use notify_debouncer_mini::{
     new_debouncer_opt, Config as DebounceConfig, DebounceEventResult, DebouncedEventKind, Debouncer,
 };
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::sync::mpsc;
 
+use crate::tree::{DirSizes, ExcludeRules, GitStatusMap, PathFilter, ScanResult};
+
 // Tuned for faster UI reflection while keeping duplicate event noise manageable.
 const WATCH_POLL_INTERVAL_MS: u64 = 75;
 const WATCH_DEBOUNCE_TIMEOUT_MS: u64 = 50;
@@ -25,13 +28,25 @@ pub enum Event {
     FileChange(PathBuf),
     PtyOutput,
     Signal,
+    /// A background tree scan finished: (scan generation, result)
+    TreeScanned(u64, Box<ScanResult>),
+    /// `git status` was read for a tree root: (root, status if inside a repository)
+    GitStatus(PathBuf, Box<Option<GitStatusMap>>),
+    /// Directory sizes were totalled for the size column
+    DirSizes(Box<DirSizes>),
+    /// Files listed for the fuzzy finder: (root, paths relative to it)
+    FinderFiles(PathBuf, Vec<String>),
 }
 
 pub struct EventHandler {
+    tx: mpsc::UnboundedSender<Event>,
     rx: mpsc::UnboundedReceiver<Event>,
     // Keep the debouncer alive to prevent it from being dropped
     debouncer: Option<Debouncer<PollWatcher>>,
     watched_path: Option<PathBuf>,
+    // Changes under paths hidden from the tree are dropped before they are sent
+    path_filter: Arc<Mutex<Option<PathFilter>>>,
+    exclude_rules: ExcludeRules,
 }
 
 impl EventHandler {
@@ -130,10 +145,14 @@ impl EventHandler {
             }
         });
 
+        let path_filter = Arc::new(Mutex::new(None));
         let mut handler = Self {
+            tx: tx.clone(),
             rx,
-            debouncer: Self::build_debouncer(tx.clone()).ok(),
+            debouncer: Self::build_debouncer(tx.clone(), Arc::clone(&path_filter)).ok(),
             watched_path: None,
+            path_filter,
+            exclude_rules: ExcludeRules::default(),
         };
         handler.update_watch_path(watch_path);
         handler
@@ -141,6 +160,7 @@ impl EventHandler {
 
     fn build_debouncer(
         fs_tx: mpsc::UnboundedSender<Event>,
+        path_filter: Arc<Mutex<Option<PathFilter>>>,
     ) -> notify::Result<Debouncer<PollWatcher>> {
         // Use PollWatcher explicitly because FSEvent can miss events in sandboxed/virtualized environments.
         let notify_cfg = NotifyConfig::default()
@@ -152,10 +172,19 @@ impl EventHandler {
         new_debouncer_opt::<_, PollWatcher>(debounce_cfg, move |result: DebounceEventResult| {
             if let Ok(events) = result {
                 for fs_event in events {
-                    if matches!(
-                        fs_event.kind,
-                        DebouncedEventKind::Any | DebouncedEventKind::AnyContinuous
-                    ) {
+                    let excluded = path_filter
+                        .lock()
+                        .ok()
+                        .and_then(|mut filter| {
+                            filter.as_mut().map(|f| f.is_excluded(&fs_event.path))
+                        })
+                        .unwrap_or(false);
+                    if !excluded
+                        && matches!(
+                            fs_event.kind,
+                            DebouncedEventKind::Any | DebouncedEventKind::AnyContinuous
+                        )
+                    {
                         let _ = fs_tx.send(Event::FileChange(fs_event.path));
                     }
                 }
@@ -186,6 +215,28 @@ impl EventHandler {
                 self.watched_path = Some(path);
             }
         }
+        self.rebuild_path_filter();
+    }
+
+    /// Drop changes to paths the tree excludes by these rules or a `.cltreeignore`.
+    pub fn set_exclude_rules(&mut self, rules: ExcludeRules) {
+        self.exclude_rules = rules;
+        self.rebuild_path_filter();
+    }
+
+    fn rebuild_path_filter(&mut self) {
+        let filter = self
+            .watched_path
+            .as_deref()
+            .and_then(|root| PathFilter::new(root, &self.exclude_rules).ok());
+        if let Ok(mut current) = self.path_filter.lock() {
+            *current = filter;
+        }
+    }
+
+    /// A sender for events produced outside the handler (e.g. background scans).
+    pub fn sender(&self) -> mpsc::UnboundedSender<Event> {
+        self.tx.clone()
     }
 
     pub async fn next(&mut self) -> Result<Event> {
@@ -0,0 +1,202 @@
+use ignore::WalkBuilder;
+use std::path::{Component, Path, PathBuf};
+
+use crate::fuzzy::{FuzzyList, TieBreak};
+
+/// Files beyond this are not offered; a walk this large is almost certainly a mistake.
+const MAX_FILES: usize = 200_000;
+
+/// Only this many best matches are kept and shown.
+const MAX_RESULTS: usize = 500;
+
+/// Every file under `root` that the tree would show, as paths relative to `root`.
+pub fn list_files(root: &Path, show_hidden: bool) -> Vec<String> {
+    let mut files: Vec<String> = WalkBuilder::new(root)
+        .hidden(!show_hidden)
+        .git_ignore(true)
+        .git_global(true)
+        .git_exclude(true)
+        // Repository internals are never worth referencing, even with hidden files shown
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| !t.is_dir()))
+        .filter_map(|entry| {
+            let rel = entry.path().strip_prefix(root).ok()?;
+            Some(rel.to_string_lossy().into_owned())
+        })
+        .take(MAX_FILES)
+        .collect();
+    files.sort_unstable();
+    files
+}
+
+/// `path` relative to `base`, stepping up with `..` where needed. Both must be absolute.
+pub fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let path: Vec<Component> = path.components().collect();
+    let base: Vec<Component> = base.components().collect();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    let mut rel = PathBuf::new();
+    for _ in common..base.len() {
+        rel.push("..");
+    }
+    for component in &path[common..] {
+        rel.push(component);
+    }
+    rel
+}
+
+/// State of the fuzzy file finder popup.
+pub struct Finder {
+    root: PathBuf,
+    // False until the background walk delivers the files
+    loaded: bool,
+    // Files relative to `root`
+    list: FuzzyList,
+    // Indices into the files, in the order they were marked
+    marked: Vec<usize>,
+}
+
+impl Finder {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            loaded: false,
+            list: FuzzyList::new(TieBreak::Shorter, MAX_RESULTS),
+            marked: Vec::new(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn is_loading(&self) -> bool {
+        !self.loaded
+    }
+
+    pub fn set_files(&mut self, files: Vec<String>) {
+        self.loaded = true;
+        self.marked.clear();
+        self.list.set_candidates(files);
+    }
+
+    pub fn list(&self) -> &FuzzyList {
+        &self.list
+    }
+
+    pub fn list_mut(&mut self) -> &mut FuzzyList {
+        &mut self.list
+    }
+
+    pub fn is_marked(&self, index: usize) -> bool {
+        self.marked.contains(&index)
+    }
+
+    pub fn marked_count(&self) -> usize {
+        self.marked.len()
+    }
+
+    /// Mark or unmark the file under the cursor.
+    pub fn toggle_mark(&mut self) {
+        let Some(index) = self.list.current() else {
+            return;
+        };
+        if let Some(pos) = self.marked.iter().position(|&i| i == index) {
+            self.marked.remove(pos);
+        } else {
+            self.marked.push(index);
+        }
+    }
+
+    /// Absolute paths to insert: the marked files, or else the one under the cursor.
+    pub fn chosen(&self) -> Vec<PathBuf> {
+        let indices: Vec<usize> = if self.marked.is_empty() {
+            self.list.current().into_iter().collect()
+        } else {
+            self.marked.clone()
+        };
+        indices
+            .into_iter()
+            .map(|i| self.root.join(self.list.candidate(i)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn ranked(query: &str, files: &[&str]) -> Vec<String> {
+        let mut finder = Finder::new(PathBuf::from("/p"));
+        finder.set_files(files.iter().map(|f| f.to_string()).collect());
+        for c in query.chars() {
+            finder.list_mut().push_char(c);
+        }
+        let list = finder.list();
+        list.matches()
+            .iter()
+            .map(|m| list.candidate(m.index).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn prefers_file_names_boundaries_and_runs() {
+        let files = ["docs/mapping.md", "src/main.rs", "src/ui/main_view.rs"];
+        assert_eq!(ranked("main", &files)[0], "src/main.rs");
+
+        let files = ["tests/tree_perf_test.rs", "src/tree/mod.rs"];
+        assert_eq!(ranked("treemod", &files)[0], "src/tree/mod.rs");
+
+        let files = ["src/a/b/c/d.rs", "src/abcd.rs"];
+        assert_eq!(ranked("abcd", &files)[0], "src/abcd.rs");
+    }
+
+    #[test]
+    fn marks_choose_several_files_in_order() {
+        let mut finder = Finder::new(PathBuf::from("/p"));
+        finder.set_files(vec!["a.rs".into(), "b.rs".into(), "c.rs".into()]);
+        assert_eq!(finder.chosen(), vec![PathBuf::from("/p/a.rs")]);
+
+        finder.list_mut().move_selection(2, 10);
+        finder.toggle_mark();
+        finder.list_mut().move_selection(-2, 10);
+        finder.toggle_mark();
+        assert_eq!(
+            finder.chosen(),
+            vec![PathBuf::from("/p/c.rs"), PathBuf::from("/p/a.rs")]
+        );
+        finder.toggle_mark();
+        assert_eq!(finder.chosen(), vec![PathBuf::from("/p/c.rs")]);
+    }
+
+    #[test]
+    fn relative_paths_step_up_from_the_cwd() {
+        let rel = |p: &str, base: &str| relative_path(Path::new(p), Path::new(base));
+        assert_eq!(rel("/p/src/app.rs", "/p"), PathBuf::from("src/app.rs"));
+        assert_eq!(rel("/p/src/app.rs", "/p/src"), PathBuf::from("app.rs"));
+        assert_eq!(
+            rel("/p/README.md", "/p/src/ui"),
+            PathBuf::from("../../README.md")
+        );
+    }
+
+    #[test]
+    fn lists_files_respecting_gitignore() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join("src/main.rs"), "").unwrap();
+        fs::write(root.join("target/out"), "").unwrap();
+        fs::write(root.join(".hidden"), "").unwrap();
+        fs::write(root.join(".ignore"), "target/\n").unwrap();
+
+        assert_eq!(list_files(root, false), vec!["src/main.rs"]);
+        assert_eq!(
+            list_files(root, true),
+            vec![".hidden", ".ignore", "src/main.rs"]
+        );
+    }
+}
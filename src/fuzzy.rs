@@ -0,0 +1,299 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::cmp::Reverse;
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_CONSECUTIVE: i64 = 12;
+const BONUS_BOUNDARY: i64 = 10;
+const BONUS_FILE_NAME: i64 = 6;
+const PENALTY_GAP: i64 = 1;
+
+/// Score `candidate` against `query` as an in-order subsequence.
+/// Returns the score and the char indices of the matched characters.
+/// Matching ignores case unless the query contains an uppercase letter.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    let case_sensitive = query.chars().any(char::is_uppercase);
+    let fold = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_ascii_lowercase()
+        }
+    };
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(fold)
+        .collect();
+    let chars: Vec<char> = candidate.chars().collect();
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    // Leftmost end of a match, then the latest start that still matches up to it:
+    // the shortest window ending there, which avoids scattering across the path
+    let mut qi = 0;
+    let mut end = None;
+    for (i, &c) in chars.iter().enumerate() {
+        if fold(c) == query[qi] {
+            qi += 1;
+            if qi == query.len() {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+    let end = end?;
+    let mut qi = query.len();
+    let mut start = end;
+    for i in (0..=end).rev() {
+        if fold(chars[i]) == query[qi - 1] {
+            qi -= 1;
+            if qi == 0 {
+                start = i;
+                break;
+            }
+        }
+    }
+
+    let file_name_start = chars
+        .iter()
+        .rposition(|&c| c == '/')
+        .map_or(0, |slash| slash + 1);
+    let mut positions = Vec::with_capacity(query.len());
+    let mut score = 0;
+    let mut qi = 0;
+    for (i, &c) in chars.iter().enumerate().take(end + 1).skip(start) {
+        if qi == query.len() || fold(c) != query[qi] {
+            continue;
+        }
+        score += SCORE_MATCH;
+        if positions.last() == Some(&(i.wrapping_sub(1))) {
+            score += BONUS_CONSECUTIVE;
+        }
+        if is_boundary(&chars, i) {
+            score += BONUS_BOUNDARY;
+        }
+        if i >= file_name_start {
+            score += BONUS_FILE_NAME;
+        }
+        positions.push(i);
+        qi += 1;
+    }
+    let window = (end - start + 1) as i64;
+    score -= (window - query.len() as i64) * PENALTY_GAP;
+    Some((score, positions))
+}
+
+/// Start of a path segment or word: after a separator, or a lower-to-upper case change.
+fn is_boundary(chars: &[char], i: usize) -> bool {
+    let Some(&prev) = i.checked_sub(1).and_then(|p| chars.get(p)) else {
+        return true;
+    };
+    matches!(prev, '/' | '_' | '-' | '.' | ' ') || (prev.is_lowercase() && chars[i].is_uppercase())
+}
+
+/// How matches with equal scores are ordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    /// Shorter candidates first, then list order
+    Shorter,
+    /// List order only
+    ListOrder,
+}
+
+#[derive(Debug, Clone)]
+pub struct FuzzyMatch {
+    /// Index into the list's candidates
+    pub index: usize,
+    pub score: i64,
+    /// Char indices of the matched characters, for highlighting
+    pub positions: Vec<usize>,
+}
+
+/// Query, ranked matches and cursor of a fuzzy-searched popup list, shared
+/// by the file finder and the command palette.
+#[derive(Debug, Clone)]
+pub struct FuzzyList {
+    candidates: Vec<String>,
+    tie_break: TieBreak,
+    /// Only this many best matches are kept
+    limit: usize,
+    query: String,
+    matches: Vec<FuzzyMatch>,
+    selected: usize,
+    offset: usize,
+}
+
+impl FuzzyList {
+    pub fn new(tie_break: TieBreak, limit: usize) -> Self {
+        Self {
+            candidates: Vec::new(),
+            tie_break,
+            limit,
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+            offset: 0,
+        }
+    }
+
+    pub fn set_candidates(&mut self, candidates: Vec<String>) {
+        self.candidates = candidates;
+        self.refilter();
+    }
+
+    pub fn candidate(&self, index: usize) -> &str {
+        &self.candidates[index]
+    }
+
+    pub fn candidate_count(&self) -> usize {
+        self.candidates.len()
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        self.query.push(c);
+        self.refilter();
+    }
+
+    pub fn pop_char(&mut self) {
+        if self.query.pop().is_some() {
+            self.refilter();
+        }
+    }
+
+    /// Delete the last word of the query, like Ctrl+W in a shell.
+    pub fn pop_word(&mut self) {
+        let trimmed = self.query.trim_end_matches(['/', ' ']);
+        let cut = trimmed.rfind(['/', ' ']).map_or(0, |i| i + 1);
+        self.query.truncate(cut);
+        self.refilter();
+    }
+
+    pub fn clear_query(&mut self) {
+        self.query.clear();
+        self.refilter();
+    }
+
+    pub fn matches(&self) -> &[FuzzyMatch] {
+        &self.matches
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Index of the candidate under the cursor.
+    pub fn current(&self) -> Option<usize> {
+        self.matches.get(self.selected).map(|m| m.index)
+    }
+
+    /// Move the cursor by `delta` rows and keep it within a window of `height` rows.
+    pub fn move_selection(&mut self, delta: isize, height: usize) {
+        let last = self.matches.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+        let height = height.max(1);
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + height {
+            self.offset = self.selected + 1 - height;
+        }
+    }
+
+    /// Editing and cursor keys common to every fuzzy popup; `page` is the
+    /// list's height. Returns false for keys left to the popup itself.
+    pub fn handle_key(&mut self, key: &KeyEvent, page: usize) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Up => self.move_selection(-1, page),
+            KeyCode::Char('p') | KeyCode::Char('k') if ctrl => self.move_selection(-1, page),
+            KeyCode::Down => self.move_selection(1, page),
+            KeyCode::Char('n') | KeyCode::Char('j') if ctrl => self.move_selection(1, page),
+            KeyCode::PageUp => self.move_selection(-(page as isize), page),
+            KeyCode::PageDown => self.move_selection(page as isize, page),
+            KeyCode::Backspace => self.pop_char(),
+            KeyCode::Char('w') if ctrl => self.pop_word(),
+            KeyCode::Char('u') if ctrl => self.clear_query(),
+            KeyCode::Char(c) if !ctrl => self.push_char(c),
+            _ => return false,
+        }
+        true
+    }
+
+    fn refilter(&mut self) {
+        self.selected = 0;
+        self.offset = 0;
+        let mut matches: Vec<FuzzyMatch> = self
+            .candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(index, candidate)| {
+                let (score, positions) = fuzzy_match(&self.query, candidate)?;
+                Some(FuzzyMatch {
+                    index,
+                    score,
+                    positions,
+                })
+            })
+            .collect();
+        match self.tie_break {
+            TieBreak::Shorter => {
+                matches.sort_by_key(|m| (Reverse(m.score), self.candidates[m.index].len(), m.index))
+            }
+            TieBreak::ListOrder => matches.sort_by_key(|m| (Reverse(m.score), m.index)),
+        }
+        matches.truncate(self.limit);
+        self.matches = matches;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_subsequences_and_reports_positions() {
+        let (_, positions) = fuzzy_match("apmo", "src/app/mod.rs").unwrap();
+        assert_eq!(positions, vec![4, 5, 8, 9]);
+        assert!(fuzzy_match("xyz", "src/app.rs").is_none());
+        assert!(fuzzy_match("APP", "src/app.rs").is_none());
+        assert!(fuzzy_match("app", "src/APP.rs").is_some());
+    }
+
+    #[test]
+    fn ties_follow_the_list_or_length() {
+        let names = vec!["bb-long".to_string(), "b".to_string()];
+        let mut list = FuzzyList::new(TieBreak::ListOrder, usize::MAX);
+        list.set_candidates(names.clone());
+        assert_eq!(list.current(), Some(0));
+
+        let mut list = FuzzyList::new(TieBreak::Shorter, 1);
+        list.set_candidates(names);
+        assert_eq!(list.current(), Some(1));
+        assert_eq!(list.matches().len(), 1);
+    }
+
+    #[test]
+    fn editing_keys_refilter_and_reset_the_cursor() {
+        let mut list = FuzzyList::new(TieBreak::ListOrder, usize::MAX);
+        list.set_candidates(vec!["src/app.rs".into(), "src/main.rs".into()]);
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert!(list.handle_key(&key(KeyCode::Down), 10));
+        assert_eq!(list.current(), Some(1));
+
+        assert!(list.handle_key(&key(KeyCode::Char('a')), 10));
+        assert!(list.handle_key(&key(KeyCode::Char('p')), 10));
+        assert_eq!((list.query(), list.current()), ("ap", Some(0)));
+        let ctrl_w = KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert!(list.handle_key(&ctrl_w, 10));
+        assert_eq!(list.query(), "");
+        assert!(!list.handle_key(&key(KeyCode::Enter), 10));
+    }
+}
@@ -0,0 +1,126 @@
+use anyhow::{bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt;
+
+/// The default cltree prefix: rarely used by shells or Claude itself.
+pub const DEFAULT_PREFIX: &str = "ctrl-]";
+
+/// A single key with modifiers, written like `ctrl-]`, `alt-t` or `ctrl-space` in config files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    pub key: char,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = spec.trim();
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            let (modifier, len) = if lower.starts_with("ctrl-") {
+                (KeyModifiers::CONTROL, 5)
+            } else if lower.starts_with("alt-") {
+                (KeyModifiers::ALT, 4)
+            } else {
+                break;
+            };
+            modifiers |= modifier;
+            rest = &rest[len..];
+        }
+
+        if rest.eq_ignore_ascii_case("space") && modifiers != KeyModifiers::NONE {
+            return Ok(Self {
+                key: ' ',
+                modifiers,
+            });
+        }
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(key), None) if modifiers != KeyModifiers::NONE => Ok(Self {
+                key: key.to_ascii_lowercase(),
+                modifiers,
+            }),
+            _ => bail!("invalid key '{spec}', expected something like ctrl-] or alt-t"),
+        }
+    }
+
+    pub fn matches(&self, event: &KeyEvent) -> bool {
+        let mods = event.modifiers - KeyModifiers::SHIFT;
+        if mods != self.modifiers {
+            return false;
+        }
+        match event.code {
+            KeyCode::Char(c) => {
+                c.to_ascii_lowercase() == self.key || legacy_control_alias(self.key) == Some(c)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Default for KeyChord {
+    fn default() -> Self {
+        Self::parse(DEFAULT_PREFIX).expect("default prefix parses")
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("Alt+")?;
+        }
+        match self.key {
+            ' ' => f.write_str("Space"),
+            key => write!(f, "{key}"),
+        }
+    }
+}
+
+/// Legacy terminals send Ctrl+\ ] ^ _ as 0x1C-0x1F, which crossterm reports as Ctrl+4-7.
+fn legacy_control_alias(key: char) -> Option<char> {
+    match key {
+        '\\' => Some('4'),
+        ']' => Some('5'),
+        '^' => Some('6'),
+        '_' => Some('7'),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: char, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(code), modifiers)
+    }
+
+    #[test]
+    fn parses_and_matches_chords() {
+        let prefix = KeyChord::parse("Ctrl-]").unwrap();
+        assert!(prefix.matches(&key(']', KeyModifiers::CONTROL)));
+        assert!(prefix.matches(&key('5', KeyModifiers::CONTROL)));
+        assert!(!prefix.matches(&key(']', KeyModifiers::NONE)));
+        assert_eq!(prefix.to_string(), "Ctrl+]");
+
+        let alt = KeyChord::parse("alt-t").unwrap();
+        assert!(alt.matches(&key('t', KeyModifiers::ALT)));
+        assert!(alt.matches(&key('T', KeyModifiers::ALT | KeyModifiers::SHIFT)));
+        assert!(!alt.matches(&key('t', KeyModifiers::CONTROL | KeyModifiers::ALT)));
+
+        let space = KeyChord::parse("ctrl-Space").unwrap();
+        assert!(space.matches(&key(' ', KeyModifiers::CONTROL)));
+        assert_eq!(space.to_string(), "Ctrl+Space");
+    }
+
+    #[test]
+    fn rejects_bare_or_multi_char_keys() {
+        assert!(KeyChord::parse("t").is_err());
+        assert!(KeyChord::parse("ctrl-").is_err());
+        assert!(KeyChord::parse("ctrl-tab").is_err());
+    }
+}
@@ -1 +1,5 @@
+pub mod asciicast;
+#[cfg(test)]
+mod test_util;
+pub mod tree;
 pub mod vterm;
@@ -1,6 +1,22 @@
+mod actions;
 mod app;
+mod asciicast;
+mod changes;
+mod config;
+mod diff;
 mod event;
+mod finder;
+mod fuzzy;
+mod keys;
+mod preview;
+mod project_state;
+mod pty_writer;
+mod replay;
+mod scanner;
+mod signals;
 mod terminal;
+#[cfg(test)]
+mod test_util;
 mod tree;
 mod ui;
 pub mod vterm;
@@ -19,14 +35,38 @@ use std::io;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use app::App;
+use app::{App, AppOptions, RecordOptions};
+use asciicast::Cast;
+use config::{Config, EnvOverrides};
 use event::EventHandler;
+use project_state::ProjectState;
+use tree::{ExcludeRules, SortMode, SortOrder, TreeFilter};
+use ui::{IconTheme, Segment, TreeColumns, TreeSide, DEFAULT_STACK_BELOW};
+
+const DEFAULT_TREE_WIDTH: u16 = 30;
+const DEFAULT_DEPTH: usize = 10;
 
 struct Args {
     path: PathBuf,
-    tree_width: u16,
-    show_hidden: bool,
-    depth: usize,
+    // Unset unless given, so the project's saved values apply
+    tree_width: Option<u16>,
+    show_hidden: Option<bool>,
+    show_ignored: bool,
+    follow_symlinks: bool,
+    depth: Option<usize>,
+    sort: SortOrder,
+    filter: Option<TreeFilter>,
+    excludes: ExcludeRules,
+    icons: Option<IconTheme>,
+    columns: Option<TreeColumns>,
+    layout: Option<TreeSide>,
+    status_bar: Option<Vec<Segment>>,
+    max_children: Option<usize>,
+    compact: Option<bool>,
+    env: EnvOverrides,
+    record: Option<PathBuf>,
+    record_input: bool,
+    replay: Option<PathBuf>,
     claude_args: Vec<String>,
 }
 
@@ -35,19 +75,133 @@ fn parse_args() -> Args {
     let raw: Vec<String> = std::env::args().skip(1).collect();
 
     let mut path = PathBuf::from(".");
-    let mut tree_width: u16 = 30;
-    let mut show_hidden = false;
-    let mut depth: usize = 10;
+    let mut tree_width = None;
+    let mut show_hidden = None;
+    let mut show_ignored = false;
+    let mut follow_symlinks = true;
+    let mut depth = None;
+    let mut sort = SortOrder::default();
+    let mut filter = None;
+    let mut excludes = ExcludeRules::default();
+    let mut icons = None;
+    let mut columns = None;
+    let mut layout = None;
+    let mut status_bar = None;
+    let mut no_status_bar = false;
+    let mut max_children = None;
+    let mut compact = None;
+    let mut env = EnvOverrides::default();
+    let mut record = None;
+    let mut record_input = false;
+    let mut replay = None;
     let mut claude_args = Vec::new();
 
-    // Known flags that take a value
+    // Known flags that take a value (long name last)
     let value_flags: &[&[&str]] = &[
         &["-p", "--path"],
         &["-w", "--tree-width"],
         &["-d", "--depth"],
+        &["--sort"],
+        &["--filter"],
+        &["--exclude"],
+        &["--include"],
+        &["--icons"],
+        &["--columns"],
+        &["--layout"],
+        &["--status-bar"],
+        &["--max-children"],
+        &["--env"],
+        &["--unset-env"],
+        &["--record"],
     ];
 
+    let mut apply_value = |long: &str, val: &str| match long {
+        "--path" => path = PathBuf::from(val),
+        "--tree-width" => tree_width = Some(val.parse().unwrap_or(DEFAULT_TREE_WIDTH)),
+        "--depth" => depth = Some(val.parse().unwrap_or(DEFAULT_DEPTH)),
+        "--sort" => match SortMode::parse(val) {
+            Some(mode) => sort.mode = mode,
+            None => {
+                eprintln!(
+                    "cltree: unknown sort mode '{val}' (expected name, natural, modified, size or extension)"
+                );
+                std::process::exit(2);
+            }
+        },
+        "--filter" => match TreeFilter::parse(val) {
+            Ok(parsed) => filter = Some(parsed),
+            Err(e) => {
+                eprintln!("cltree: {e:#}");
+                std::process::exit(2);
+            }
+        },
+        "--columns" => match TreeColumns::parse(val) {
+            Ok(parsed) => columns = Some(parsed),
+            Err(e) => {
+                eprintln!("cltree: {e:#}");
+                std::process::exit(2);
+            }
+        },
+        "--layout" => match TreeSide::parse(val) {
+            Some(side) => layout = Some(side),
+            None => {
+                eprintln!(
+                    "cltree: unknown layout '{val}' (expected one of {})",
+                    TreeSide::names()
+                );
+                std::process::exit(2);
+            }
+        },
+        "--status-bar" => match Segment::parse_list(val) {
+            Ok(segments) => status_bar = Some(segments),
+            Err(e) => {
+                eprintln!("cltree: {e:#}");
+                std::process::exit(2);
+            }
+        },
+        "--max-children" => match val.parse() {
+            Ok(n) => max_children = Some(n),
+            Err(_) => {
+                eprintln!("cltree: --max-children expects a number, got '{val}'");
+                std::process::exit(2);
+            }
+        },
+        "--exclude" => excludes.exclude.push(val.to_string()),
+        "--include" => excludes.include.push(val.to_string()),
+        "--icons" => match IconTheme::parse(val) {
+            Some(theme) => icons = Some(theme),
+            None => {
+                eprintln!(
+                    "cltree: unknown icon theme '{val}' (expected one of {})",
+                    IconTheme::names()
+                );
+                std::process::exit(2);
+            }
+        },
+        "--env" => match EnvOverrides::parse_assignment(val) {
+            Ok(pair) => env.set.push(pair),
+            Err(e) => {
+                eprintln!("cltree: {e}");
+                std::process::exit(2);
+            }
+        },
+        "--unset-env" => env.unset.push(val.to_string()),
+        "--record" => record = Some(PathBuf::from(val)),
+        _ => {}
+    };
+
     let mut i = 0;
+    // `cltree replay FILE` plays a recording instead of starting Claude
+    if raw.first().map(String::as_str) == Some("replay") {
+        match raw.get(1) {
+            Some(file) if !file.starts_with('-') => replay = Some(PathBuf::from(file)),
+            _ => {
+                eprintln!("cltree: usage: cltree replay <FILE>");
+                std::process::exit(2);
+            }
+        }
+        i = 2;
+    }
     while i < raw.len() {
         let arg = &raw[i];
 
@@ -55,12 +209,31 @@ fn parse_args() -> Args {
         if arg == "-h" || arg == "--help" {
             eprintln!(
                 "A TUI file explorer for Claude Code CLI\n\n\
-                 Usage: cltree [OPTIONS] [CLAUDE_ARGS...]\n\n\
+                 Usage: cltree [OPTIONS] [CLAUDE_ARGS...]\n\
+                 \x20      cltree replay <FILE> [OPTIONS]\n\n\
                  Options:\n\
                  \x20 -p, --path <PATH>         Working directory [default: .]\n\
                  \x20 -w, --tree-width <WIDTH>   Tree panel width %% (10-50) [default: 30]\n\
                  \x20 -a, --show-hidden          Show hidden files\n\
                  \x20 -d, --depth <DEPTH>        Max tree depth [default: 10]\n\
+                 \x20     --sort <MODE>          Tree order: name, natural, modified, size, extension\n\
+                 \x20     --mixed                List directories among files instead of first\n\
+                 \x20     --filter <EXPR>        Only show entries matching a substring, glob or /regex/\n\
+                 \x20     --exclude <GLOB>       Hide matching paths from the tree (repeatable)\n\
+                 \x20     --include <GLOB>       Only list files matching a glob (repeatable)\n\
+                 \x20     --show-ignored         List gitignored entries, greyed out\n\
+                 \x20     --no-follow            List symlinked directories without walking into them\n\
+                 \x20     --icons <THEME>        Node icons: plain, nerd, emoji, ascii\n\
+                 \x20     --columns <LIST>       Show size, modified and/or permissions columns\n\
+                 \x20     --layout <SIDE>        Tree position: left, right, top, bottom [default: right]\n\
+                 \x20     --status-bar <LIST>    Status bar segments: mode, cwd, git, process, scroll\n\
+                 \x20     --no-status-bar        Hide the status bar\n\
+                 \x20     --max-children <N>     Summarize directories past N entries, 0 for never [default: 500]\n\
+                 \x20     --no-compact           Don't merge single-child directory chains into one row\n\
+                 \x20     --env <KEY=VAL>        Set a variable for Claude (repeatable)\n\
+                 \x20     --unset-env <KEY>      Remove a variable, KEY* matches a prefix (repeatable)\n\
+                 \x20     --record <FILE>        Record the session to an asciicast v2 file\n\
+                 \x20     --record-input         Include keyboard input in the recording\n\
                  \x20 -h, --help                 Print help\n\
                  \x20 -V, --version              Print version\n\n\
                  All other arguments are passed through to Claude Code CLI.\n\
@@ -78,14 +251,10 @@ fn parse_args() -> Args {
         let mut matched_value_flag = false;
         for names in value_flags {
             // Handle --flag=value form
+            let long = names[names.len() - 1];
             for name in *names {
                 if let Some(val) = arg.strip_prefix(&format!("{name}=")) {
-                    match *name {
-                        "-p" | "--path" => path = PathBuf::from(val),
-                        "-w" | "--tree-width" => tree_width = val.parse().unwrap_or(30),
-                        "-d" | "--depth" => depth = val.parse().unwrap_or(10),
-                        _ => {}
-                    }
+                    apply_value(long, val);
                     matched_value_flag = true;
                     break;
                 }
@@ -97,12 +266,7 @@ fn parse_args() -> Args {
             // Handle --flag value form
             if names.contains(&arg.as_str()) {
                 let val = raw.get(i + 1).cloned().unwrap_or_default();
-                match names[1] {
-                    "--path" => path = PathBuf::from(&val),
-                    "--tree-width" => tree_width = val.parse().unwrap_or(30),
-                    "--depth" => depth = val.parse().unwrap_or(10),
-                    _ => {}
-                }
+                apply_value(long, &val);
                 i += 2;
                 matched_value_flag = true;
                 break;
@@ -119,7 +283,37 @@ fn parse_args() -> Args {
 
         // Boolean flag
         if arg == "-a" || arg == "--show-hidden" {
-            show_hidden = true;
+            show_hidden = Some(true);
+            i += 1;
+            continue;
+        }
+        if arg == "--show-ignored" {
+            show_ignored = true;
+            i += 1;
+            continue;
+        }
+        if arg == "--no-follow" {
+            follow_symlinks = false;
+            i += 1;
+            continue;
+        }
+        if arg == "--no-status-bar" {
+            no_status_bar = true;
+            i += 1;
+            continue;
+        }
+        if arg == "--no-compact" {
+            compact = Some(false);
+            i += 1;
+            continue;
+        }
+        if arg == "--mixed" {
+            sort.directories_first = false;
+            i += 1;
+            continue;
+        }
+        if arg == "--record-input" {
+            record_input = true;
             i += 1;
             continue;
         }
@@ -129,11 +323,35 @@ fn parse_args() -> Args {
         i += 1;
     }
 
+    if let Err(e) = excludes.overrides(&path) {
+        eprintln!("cltree: {e:#}");
+        std::process::exit(2);
+    }
+
     Args {
         path,
         tree_width,
         show_hidden,
+        show_ignored,
+        follow_symlinks,
         depth,
+        sort,
+        filter,
+        excludes,
+        icons,
+        columns,
+        layout,
+        status_bar: if no_status_bar {
+            Some(Vec::new())
+        } else {
+            status_bar
+        },
+        max_children,
+        compact,
+        env,
+        record,
+        record_input,
+        replay,
         claude_args,
     }
 }
@@ -157,6 +375,12 @@ static TERMINAL_INITIALIZED: AtomicBool = AtomicBool::new(false);
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = parse_args();
+    // Read config before touching the terminal so errors print normally
+    let config = Config::load(&args.path)?;
+    let cast = match &args.replay {
+        Some(file) => Some(Cast::read(file)?),
+        None => None,
+    };
 
     // Install panic hook to restore terminal state on panic.
     // Without this, a panic leaves the terminal in raw mode (unusable).
@@ -182,25 +406,10 @@ async fn main() -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Create PTY output notification channel
-    let (pty_tx, pty_rx) = tokio::sync::mpsc::unbounded_channel();
-
-    // Create app state
-    let mut app = App::new(
-        args.path,
-        args.tree_width,
-        args.show_hidden,
-        args.depth,
-        args.claude_args,
-        pty_tx,
-    )?;
-
-    // Create event handler with file watching enabled for the tree root
-    let watch_path = Some(app.tree.root_path().to_path_buf());
-    let event_handler = EventHandler::new(200, watch_path, pty_rx);
-
-    // Run the app
-    let result = run_app(&mut terminal, &mut app, event_handler).await;
+    let result = match cast {
+        Some(cast) => run_replay(&mut terminal, cast, &args, &config).await,
+        None => run_session(&mut terminal, args, config).await,
+    };
 
     // Restore terminal
     restore_terminal();
@@ -214,6 +423,102 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+async fn run_session(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    args: Args,
+    config: Config,
+) -> Result<()> {
+    // Create PTY output notification channel
+    let (pty_tx, pty_rx) = tokio::sync::mpsc::unbounded_channel();
+
+    // Create app state
+    let mut env_layers = config.env_layers;
+    env_layers.push(args.env);
+    // Flags win over what was last used in this project
+    let project = args
+        .path
+        .canonicalize()
+        .unwrap_or_else(|_| args.path.clone());
+    let state_file = ProjectState::file_for(&project);
+    let state = state_file
+        .as_deref()
+        .map(ProjectState::load)
+        .unwrap_or_default();
+    let options = AppOptions {
+        path: args.path,
+        tree_width: args
+            .tree_width
+            .or(state.tree_width)
+            .unwrap_or(DEFAULT_TREE_WIDTH),
+        show_hidden: args.show_hidden.or(state.show_hidden).unwrap_or(false),
+        show_ignored: args.show_ignored,
+        follow_symlinks: args.follow_symlinks,
+        max_depth: args.depth.or(state.depth).unwrap_or(DEFAULT_DEPTH),
+        sort: args.sort,
+        filter: args.filter,
+        excludes: args.excludes.clone(),
+        icons: args.icons.or(config.icons).unwrap_or_default(),
+        columns: args.columns.or(config.columns).unwrap_or_default(),
+        layout: args.layout.or(config.layout).unwrap_or_default(),
+        stack_below: config.stack_below.unwrap_or(DEFAULT_STACK_BELOW),
+        status_bar: args
+            .status_bar
+            .or_else(|| config.status_bar.clone())
+            .unwrap_or_else(|| Segment::ALL.to_vec()),
+        max_children: match args.max_children.or(config.max_children) {
+            Some(0) => None,
+            limit => Some(limit.unwrap_or(tree::DEFAULT_CHILD_LIMIT)),
+        },
+        compact_dirs: args.compact.or(config.compact).unwrap_or(true),
+        claude_args: args.claude_args,
+        env_layers,
+        record: args.record.map(|path| RecordOptions {
+            path,
+            input: args.record_input,
+        }),
+        paste_confirm_bytes: config
+            .paste_confirm_bytes
+            .unwrap_or(app::DEFAULT_PASTE_CONFIRM_BYTES),
+        prefix: config.prefix.unwrap_or_default(),
+        palette_key: config.palette,
+        state_file,
+    };
+    let mut event_handler = EventHandler::new(200, None, pty_rx);
+    let mut app = App::new(options, pty_tx, event_handler.sender())?;
+    if !config.warnings.is_empty() {
+        app.show_message(config.warnings.join("; "));
+    }
+
+    // Enable file watching for the tree root
+    event_handler.set_exclude_rules(args.excludes);
+    event_handler.update_watch_path(Some(app.tree.root_path().to_path_buf()));
+
+    run_app(terminal, &mut app, event_handler).await
+}
+
+async fn run_replay(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    cast: Cast,
+    args: &Args,
+    config: &Config,
+) -> Result<()> {
+    let mut player = replay::Player::new(
+        cast,
+        &args.path,
+        args.tree_width.unwrap_or(DEFAULT_TREE_WIDTH),
+        args.show_hidden.unwrap_or(false),
+        args.depth.unwrap_or(DEFAULT_DEPTH),
+    )?;
+    player.app.icons = args.icons.or(config.icons).unwrap_or_default();
+    player.app.tree_side = args.layout.or(config.layout).unwrap_or_default();
+    player.app.stack_below = config.stack_below.unwrap_or(DEFAULT_STACK_BELOW);
+    if let Some(segments) = args.status_bar.as_ref().or(config.status_bar.as_ref()) {
+        player.app.status_bar_shown = !segments.is_empty();
+        player.app.status_segments = segments.clone();
+    }
+    replay::run(terminal, &mut player).await
+}
+
 async fn run_app(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut App,
@@ -258,6 +563,18 @@ async fn run_app(
             event::Event::Signal => {
                 return Ok(());
             }
+            event::Event::TreeScanned(generation, result) => {
+                app.handle_tree_scanned(generation, *result);
+            }
+            event::Event::GitStatus(root, status) => {
+                app.handle_git_status(root, *status);
+            }
+            event::Event::DirSizes(sizes) => {
+                app.handle_dir_sizes(*sizes);
+            }
+            event::Event::FinderFiles(root, files) => {
+                app.handle_finder_files(root, files);
+            }
         }
     }
 }
@@ -0,0 +1,294 @@
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use syntect::highlighting::{
+    FontStyle, HighlightState, Highlighter, RangedHighlightIterator, Theme, ThemeSet,
+};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxSet};
+
+/// Files larger than this are not previewed.
+pub const MAX_PREVIEW_BYTES: u64 = 1024 * 1024;
+
+/// A NUL byte in this many leading bytes marks a file as binary.
+const BINARY_SNIFF_BYTES: usize = 8192;
+
+const TAB_WIDTH: usize = 4;
+
+const THEME: &str = "base16-ocean.dark";
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme() -> &'static Theme {
+    static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+    &THEME_SET.get_or_init(ThemeSet::load_defaults).themes[THEME]
+}
+
+/// What the preview pane shows for its file.
+pub enum PreviewBody {
+    Text(HighlightedText),
+    Binary(u64),
+    TooLarge(u64),
+    Unreadable(String),
+}
+
+/// A file's lines, highlighted lazily from the top as far as they are displayed.
+pub struct HighlightedText {
+    lines: Vec<String>,
+    highlighted: Vec<Line<'static>>,
+    // None for plain text, or once highlighting hit a parse error
+    state: Option<(ParseState, HighlightState)>,
+}
+
+impl HighlightedText {
+    fn new(path: &Path, text: &str) -> Self {
+        let lines: Vec<String> = text.lines().map(clean_line).collect();
+        let syntaxes = syntax_set();
+        let first_line = lines.first().map(String::as_str).unwrap_or("");
+        let syntax = syntaxes
+            .find_syntax_for_file(path)
+            .ok()
+            .flatten()
+            .or_else(|| syntaxes.find_syntax_by_first_line(first_line))
+            .filter(|syntax| syntax.name != "Plain Text");
+        let state = syntax.map(|syntax| {
+            let highlighter = Highlighter::new(theme());
+            (
+                ParseState::new(syntax),
+                HighlightState::new(&highlighter, ScopeStack::new()),
+            )
+        });
+        Self {
+            lines,
+            highlighted: Vec::new(),
+            state,
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Highlight every line before `end`.
+    pub fn prepare(&mut self, end: usize) {
+        let end = end.min(self.lines.len());
+        let highlighter = Highlighter::new(theme());
+        while self.highlighted.len() < end {
+            let text = &self.lines[self.highlighted.len()];
+            let line = match self.state.as_mut() {
+                Some((parse, highlight)) => {
+                    let with_newline = format!("{text}\n");
+                    match parse.parse_line(&with_newline, syntax_set()) {
+                        Ok(ops) => Line::from(
+                            RangedHighlightIterator::new(
+                                highlight,
+                                &ops,
+                                &with_newline,
+                                &highlighter,
+                            )
+                            .map(|(style, piece, _)| {
+                                Span::styled(
+                                    piece.trim_end_matches('\n').to_string(),
+                                    convert_style(style),
+                                )
+                            })
+                            .collect::<Vec<_>>(),
+                        ),
+                        Err(_) => {
+                            // Highlighting state is unusable from here on
+                            self.state = None;
+                            Line::from(text.clone())
+                        }
+                    }
+                }
+                None => Line::from(text.clone()),
+            };
+            self.highlighted.push(line);
+        }
+    }
+
+    /// Lines `start..end`, highlighted as far as [`prepare`](Self::prepare) got.
+    pub fn lines(&self, start: usize, end: usize) -> impl Iterator<Item = Line<'_>> {
+        let end = end.min(self.lines.len());
+        (start.min(end)..end).map(move |i| match self.highlighted.get(i) {
+            Some(line) => line.clone(),
+            None => Line::from(self.lines[i].as_str()),
+        })
+    }
+}
+
+/// Tabs expanded and other control characters dropped, so cell widths add up.
+pub(crate) fn clean_line(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    for c in line.chars() {
+        match c {
+            '\t' => {
+                let pad = TAB_WIDTH - out.chars().count() % TAB_WIDTH;
+                out.extend(std::iter::repeat_n(' ', pad));
+            }
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn convert_style(style: syntect::highlighting::Style) -> Style {
+    let fg = style.foreground;
+    let mut out = Style::default().fg(Color::Rgb(fg.r, fg.g, fg.b));
+    if style.font_style.contains(FontStyle::BOLD) {
+        out = out.add_modifier(Modifier::BOLD);
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        out = out.add_modifier(Modifier::ITALIC);
+    }
+    out
+}
+
+/// The file shown in the preview pane.
+pub struct Preview {
+    path: PathBuf,
+    body: PreviewBody,
+    scroll: usize,
+}
+
+impl Preview {
+    pub fn load(path: PathBuf) -> Self {
+        let body = read_body(&path);
+        Self {
+            path,
+            body,
+            scroll: 0,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn body(&self) -> &PreviewBody {
+        &self.body
+    }
+
+    pub fn body_mut(&mut self) -> &mut PreviewBody {
+        &mut self.body
+    }
+
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    /// Re-read the file after it changed on disk, keeping the scroll position.
+    pub fn reload(&mut self) {
+        self.body = read_body(&self.path);
+        self.scroll = self.scroll.min(self.max_scroll());
+    }
+
+    pub fn scroll_by(&mut self, delta: isize) {
+        self.scroll = self
+            .scroll
+            .saturating_add_signed(delta)
+            .min(self.max_scroll());
+    }
+
+    fn max_scroll(&self) -> usize {
+        match &self.body {
+            PreviewBody::Text(text) => text.line_count().saturating_sub(1),
+            _ => 0,
+        }
+    }
+}
+
+fn read_body(path: &Path) -> PreviewBody {
+    let size = match fs::metadata(path) {
+        Ok(meta) => meta.len(),
+        Err(e) => return PreviewBody::Unreadable(e.to_string()),
+    };
+    if size > MAX_PREVIEW_BYTES {
+        return PreviewBody::TooLarge(size);
+    }
+    let mut bytes = Vec::with_capacity(size as usize);
+    if let Err(e) = fs::File::open(path).and_then(|mut file| file.read_to_end(&mut bytes)) {
+        return PreviewBody::Unreadable(e.to_string());
+    }
+    let sniff = &bytes[..bytes.len().min(BINARY_SNIFF_BYTES)];
+    if sniff.contains(&0) {
+        return PreviewBody::Binary(size);
+    }
+    match String::from_utf8(bytes) {
+        Ok(text) => PreviewBody::Text(HighlightedText::new(path, &text)),
+        Err(_) => PreviewBody::Binary(size),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(preview: &mut Preview) -> &mut HighlightedText {
+        match preview.body_mut() {
+            PreviewBody::Text(text) => text,
+            _ => panic!("expected a text preview"),
+        }
+    }
+
+    #[test]
+    fn highlights_known_languages() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("main.rs");
+        fs::write(&path, "fn main() {\n\tlet x = 1;\n}\n").unwrap();
+
+        let mut preview = Preview::load(path);
+        let text = text(&mut preview);
+        assert_eq!(text.line_count(), 3);
+        text.prepare(3);
+        let lines: Vec<Line> = text.lines(0, 3).collect();
+        // `fn` and `main` get different colors
+        assert!(lines[0].spans.len() > 1);
+        assert_eq!(lines[1].to_string(), "    let x = 1;");
+    }
+
+    #[test]
+    fn detects_binary_large_and_missing_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let binary = temp.path().join("blob.bin");
+        fs::write(&binary, [0x7f, b'E', b'L', b'F', 0, 1, 2]).unwrap();
+        assert!(matches!(
+            Preview::load(binary).body(),
+            PreviewBody::Binary(7)
+        ));
+
+        let large = temp.path().join("large.txt");
+        fs::write(&large, vec![b'a'; MAX_PREVIEW_BYTES as usize + 1]).unwrap();
+        assert!(matches!(
+            Preview::load(large).body(),
+            PreviewBody::TooLarge(_)
+        ));
+
+        let missing = temp.path().join("missing");
+        assert!(matches!(
+            Preview::load(missing).body(),
+            PreviewBody::Unreadable(_)
+        ));
+    }
+
+    #[test]
+    fn reload_keeps_scroll_within_the_new_length() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("notes.txt");
+        fs::write(&path, "a\nb\nc\nd\n").unwrap();
+        let mut preview = Preview::load(path.clone());
+        preview.scroll_by(2);
+        assert_eq!(preview.scroll(), 2);
+
+        fs::write(&path, "only\n").unwrap();
+        preview.reload();
+        assert_eq!(preview.scroll(), 0);
+        assert_eq!(text(&mut preview).line_count(), 1);
+    }
+}
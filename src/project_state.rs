@@ -0,0 +1,108 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// View settings changed at runtime, remembered per project so the next
+/// session in the same directory starts the way the last one ended.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct ProjectState {
+    pub show_hidden: Option<bool>,
+    pub depth: Option<usize>,
+    /// Tree panel width in percent.
+    pub tree_width: Option<u16>,
+}
+
+impl ProjectState {
+    /// Where the state of `project_root` is kept: one file per project under
+    /// the platform's state directory (`~/.local/state/cltree/projects` on Linux).
+    pub fn file_for(project_root: &Path) -> Option<PathBuf> {
+        let dir = dirs::state_dir().or_else(dirs::data_local_dir)?;
+        Some(
+            dir.join("cltree")
+                .join("projects")
+                .join(file_name(project_root)),
+        )
+    }
+
+    /// Read saved state. A missing or unreadable file is an empty state, so a
+    /// damaged file never keeps cltree from starting.
+    pub fn load(file: &Path) -> Self {
+        std::fs::read_to_string(file)
+            .ok()
+            .and_then(|text| Self::parse(&text).ok())
+            .unwrap_or_default()
+    }
+
+    fn parse(text: &str) -> Result<Self> {
+        let mut state: Self = toml::from_str(text)?;
+        state.depth = state.depth.filter(|&depth| depth > 0);
+        state.tree_width = state.tree_width.filter(|&width| width > 0);
+        Ok(state)
+    }
+
+    pub fn save(&self, file: &Path) -> Result<()> {
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+        }
+        let text = toml::to_string(self)?;
+        std::fs::write(file, text).with_context(|| format!("writing {}", file.display()))
+    }
+}
+
+/// `/home/me/proj` as `%2Fhome%2Fme%2Fproj.toml`: readable, free of separators, and
+/// percent-encoded so that no two paths share a file.
+fn file_name(project_root: &Path) -> String {
+    let path = project_root.to_string_lossy();
+    let mut name = String::with_capacity(path.len() + 5);
+    for c in path.chars() {
+        match c {
+            '%' => name.push_str("%25"),
+            '/' => name.push_str("%2F"),
+            '\\' => name.push_str("%5C"),
+            ':' => name.push_str("%3A"),
+            c => name.push(c),
+        }
+    }
+    name.push_str(".toml");
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_round_trips_through_its_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("nested/state.toml");
+        assert_eq!(ProjectState::load(&file), ProjectState::default());
+
+        let state = ProjectState {
+            show_hidden: Some(true),
+            depth: Some(4),
+            tree_width: Some(35),
+        };
+        state.save(&file).unwrap();
+        assert_eq!(ProjectState::load(&file), state);
+
+        std::fs::write(&file, "depth = \"deep\"\ntree_width = 40\n[").unwrap();
+        assert_eq!(ProjectState::load(&file), ProjectState::default());
+    }
+
+    #[test]
+    fn projects_get_separate_files() {
+        assert_eq!(
+            file_name(Path::new("/home/me/proj")),
+            "%2Fhome%2Fme%2Fproj.toml"
+        );
+        assert_ne!(
+            file_name(Path::new("/home/me/a")),
+            file_name(Path::new("/home/me/b"))
+        );
+        // Separators and a literal `%` each get their own escape
+        let names = ["/a:b", "/a/b", "/a\\b", "/a%b", "/a%2Fb"].map(|p| file_name(Path::new(p)));
+        for (i, name) in names.iter().enumerate() {
+            assert!(!names[..i].contains(name), "{name} is not unique");
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+
+/// Largest single `write` issued to the PTY. Big pastes go out in pieces of this size
+/// so the pending counter moves and the child can drain its input buffer in between.
+pub const WRITE_CHUNK: usize = 4096;
+
+/// Upper bound on queued-but-unwritten user input.
+pub const MAX_PENDING_INPUT: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueFull;
+
+#[derive(Default)]
+struct Queues {
+    // Terminal replies (DSR/CPR) from the reader thread
+    responses: VecDeque<Vec<u8>>,
+    // User input messages (keys, pastes, focus events); each is written without interleaving
+    input: VecDeque<Vec<u8>>,
+    closed: bool,
+}
+
+struct Shared {
+    queues: Mutex<Queues>,
+    ready: Condvar,
+    pending_input: AtomicUsize,
+}
+
+/// Handle to the thread that owns the PTY writer.
+///
+/// Responses and user input keep their own order. Responses may overtake queued input,
+/// but only between input messages, never in the middle of one (a reply inside a
+/// bracketed paste would be read as pasted text).
+#[derive(Clone)]
+pub struct PtyWriter {
+    shared: Arc<Shared>,
+}
+
+impl PtyWriter {
+    pub fn spawn(writer: Box<dyn Write + Send>) -> Self {
+        let shared = Arc::new(Shared {
+            queues: Mutex::new(Queues::default()),
+            ready: Condvar::new(),
+            pending_input: AtomicUsize::new(0),
+        });
+        let thread_shared = Arc::clone(&shared);
+        thread::spawn(move || run(writer, thread_shared));
+        Self { shared }
+    }
+
+    /// Queue user input. Never blocks; fails when the queue is over its byte budget.
+    pub fn send_input(&self, bytes: Vec<u8>) -> Result<(), QueueFull> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        let mut queues = self.lock();
+        if queues.closed {
+            return Ok(());
+        }
+        let pending = self.shared.pending_input.load(Ordering::SeqCst);
+        if pending + bytes.len() > MAX_PENDING_INPUT {
+            return Err(QueueFull);
+        }
+        self.shared
+            .pending_input
+            .fetch_add(bytes.len(), Ordering::SeqCst);
+        queues.input.push_back(bytes);
+        self.shared.ready.notify_one();
+        Ok(())
+    }
+
+    /// Queue a terminal reply generated by the virtual terminal.
+    pub fn send_response(&self, bytes: Vec<u8>) {
+        let mut queues = self.lock();
+        if !queues.closed {
+            queues.responses.push_back(bytes);
+            self.shared.ready.notify_one();
+        }
+    }
+
+    /// Bytes of user input accepted but not yet written to the PTY.
+    pub fn pending_input(&self) -> usize {
+        self.shared.pending_input.load(Ordering::SeqCst)
+    }
+
+    /// Stop the writer thread once the queues drain.
+    pub fn close(&self) {
+        self.lock().closed = true;
+        self.shared.ready.notify_one();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Queues> {
+        lock_queues(&self.shared)
+    }
+}
+
+fn lock_queues(shared: &Shared) -> MutexGuard<'_, Queues> {
+    shared
+        .queues
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn run(mut writer: Box<dyn Write + Send>, shared: Arc<Shared>) {
+    loop {
+        let (bytes, is_input) = {
+            let mut queues = lock_queues(&shared);
+            loop {
+                if let Some(resp) = queues.responses.pop_front() {
+                    break (resp, false);
+                }
+                if let Some(input) = queues.input.pop_front() {
+                    break (input, true);
+                }
+                if queues.closed {
+                    return;
+                }
+                queues = shared
+                    .ready
+                    .wait(queues)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+        };
+
+        let mut ok = true;
+        for chunk in bytes.chunks(WRITE_CHUNK) {
+            ok = writer.write_all(chunk).is_ok() && writer.flush().is_ok();
+            if is_input {
+                shared
+                    .pending_input
+                    .fetch_sub(chunk.len(), Ordering::SeqCst);
+            }
+            if !ok {
+                break;
+            }
+        }
+
+        if !ok {
+            // The child is gone; drop everything so callers stop seeing pending input
+            let mut queues = lock_queues(&shared);
+            queues.closed = true;
+            queues.responses.clear();
+            queues.input.clear();
+            shared.pending_input.store(0, Ordering::SeqCst);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    type Writes = Arc<Mutex<Vec<Vec<u8>>>>;
+
+    /// A writer that records every write and can be held shut by the test.
+    struct GatedWriter {
+        out: Writes,
+        gate: mpsc::Receiver<()>,
+    }
+
+    impl Write for GatedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let _ = self.gate.recv();
+            self.out.lock().unwrap().push(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn gated() -> (PtyWriter, Writes, mpsc::Sender<()>) {
+        let out = Arc::new(Mutex::new(Vec::new()));
+        let (gate_tx, gate) = mpsc::channel();
+        let writer = PtyWriter::spawn(Box::new(GatedWriter {
+            out: Arc::clone(&out),
+            gate,
+        }));
+        (writer, out, gate_tx)
+    }
+
+    fn wait_for(mut cond: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !cond() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn large_input_is_chunked_and_tracked() {
+        let (writer, out, gate) = gated();
+        writer.send_input(vec![b'x'; WRITE_CHUNK * 2 + 10]).unwrap();
+        assert_eq!(writer.pending_input(), WRITE_CHUNK * 2 + 10);
+
+        gate.send(()).unwrap();
+        wait_for(|| writer.pending_input() == WRITE_CHUNK + 10);
+        gate.send(()).unwrap();
+        gate.send(()).unwrap();
+        wait_for(|| writer.pending_input() == 0);
+
+        let sizes: Vec<usize> = out.lock().unwrap().iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![WRITE_CHUNK, WRITE_CHUNK, 10]);
+    }
+
+    #[test]
+    fn responses_overtake_input_only_between_messages() {
+        let (writer, out, gate) = gated();
+        // Two-chunk paste starts writing and blocks inside the first chunk
+        writer.send_input(vec![b'p'; WRITE_CHUNK + 1]).unwrap();
+        writer.send_input(b"k".to_vec()).unwrap();
+        wait_for(|| lock_queues(&writer.shared).input.len() == 1);
+        writer.send_response(b"R1".to_vec());
+        writer.send_response(b"R2".to_vec());
+
+        for _ in 0..5 {
+            gate.send(()).unwrap();
+        }
+        wait_for(|| out.lock().unwrap().len() == 5);
+
+        let writes: Vec<Vec<u8>> = out.lock().unwrap().clone();
+        assert_eq!(writes[0].len(), WRITE_CHUNK);
+        assert_eq!(writes[1], vec![b'p']);
+        assert_eq!(writes[2], b"R1");
+        assert_eq!(writes[3], b"R2");
+        assert_eq!(writes[4], b"k");
+    }
+
+    #[test]
+    fn input_over_budget_is_rejected() {
+        let (writer, _out, gate) = gated();
+        writer.send_input(vec![0; MAX_PENDING_INPUT - 1]).unwrap();
+        assert_eq!(writer.send_input(vec![0; 2]), Err(QueueFull));
+        assert_eq!(writer.pending_input(), MAX_PENDING_INPUT - 1);
+
+        drop(gate);
+        wait_for(|| writer.pending_input() == 0);
+        assert_eq!(
+            writer.send_input(vec![0; MAX_PENDING_INPUT + 1]),
+            Err(QueueFull)
+        );
+        assert!(writer.send_input(vec![0; 2]).is_ok());
+    }
+
+    #[test]
+    fn write_error_drops_pending_input() {
+        struct Broken;
+        impl Write for Broken {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::BrokenPipe.into())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let writer = PtyWriter::spawn(Box::new(Broken));
+        writer.send_input(vec![1; 10]).unwrap();
+        wait_for(|| writer.pending_input() == 0);
+        assert!(lock_queues(&writer.shared).closed);
+    }
+}
@@ -0,0 +1,245 @@
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::prelude::*;
+use std::io;
+use std::path::Path;
+use std::time::Instant;
+use tokio::sync::mpsc;
+
+use crate::app::App;
+use crate::asciicast::{Cast, EventKind};
+use crate::event::{self, EventHandler};
+use crate::terminal::TerminalPane;
+use crate::tree::FileTree;
+use crate::ui;
+
+const SEEK_STEP_SECS: f64 = 5.0;
+const MIN_SPEED: f64 = 0.25;
+const MAX_SPEED: f64 = 16.0;
+
+/// Plays an asciicast recording through a detached terminal pane.
+pub struct Player {
+    pub app: App,
+    cast: Cast,
+    next_event: usize,
+    clock: f64,
+    speed: f64,
+    paused: bool,
+    last_instant: Instant,
+}
+
+impl Player {
+    pub fn new(
+        cast: Cast,
+        fallback_root: &Path,
+        tree_width: u16,
+        show_hidden: bool,
+        max_depth: usize,
+    ) -> Result<Self> {
+        let root = cast
+            .header
+            .root
+            .clone()
+            .unwrap_or_else(|| fallback_root.to_path_buf());
+        // Prefer the recorded tree; fall back to whatever is on disk now
+        let tree = if cast.header.tree.is_empty() {
+            FileTree::new(&root, show_hidden, max_depth)?
+        } else {
+            FileTree::from_snapshot(&root, &cast.header.tree)
+        };
+        let terminal = TerminalPane::detached(&root, cast.header.width, cast.header.height);
+
+        let mut app = App::from_parts(tree, terminal, tree_width);
+        app.tree_loading = false;
+
+        Ok(Self {
+            app,
+            cast,
+            next_event: 0,
+            clock: 0.0,
+            speed: 1.0,
+            paused: false,
+            last_instant: Instant::now(),
+        })
+    }
+
+    /// Advance playback by the wall-clock time since the last call.
+    pub fn advance(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_instant).as_secs_f64();
+        self.last_instant = now;
+        if self.paused {
+            return;
+        }
+        let target = self.clock + elapsed * self.speed;
+        self.play_until(target);
+        if self.at_end() {
+            self.paused = true;
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        self.next_event >= self.cast.events.len()
+    }
+
+    fn play_until(&mut self, target: f64) {
+        while let Some(event) = self.cast.events.get(self.next_event) {
+            if event.time > target {
+                break;
+            }
+            match event.kind {
+                EventKind::Output => self.app.terminal.feed(event.data.as_bytes()),
+                EventKind::Resize => {
+                    if let Some((cols, rows)) = event.resize() {
+                        self.app.terminal.resize_detached(cols, rows);
+                    }
+                }
+                EventKind::Input | EventKind::Marker => {}
+            }
+            self.next_event += 1;
+        }
+        self.clock = target.min(self.cast.duration()).max(0.0);
+        self.app.terminal.tick();
+    }
+
+    fn seek(&mut self, target: f64) {
+        let target = target.clamp(0.0, self.cast.duration());
+        if target < self.clock {
+            // The terminal cannot run backwards; replay from the start
+            self.app
+                .terminal
+                .reset(self.cast.header.width, self.cast.header.height);
+            self.next_event = 0;
+        }
+        self.play_until(target);
+    }
+
+    /// Returns true when the player should quit.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if key.kind == KeyEventKind::Release {
+            return false;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return true,
+            KeyCode::Char(' ') => {
+                if self.at_end() {
+                    self.seek(0.0);
+                }
+                self.paused = !self.paused;
+            }
+            KeyCode::Right | KeyCode::Char('l') => self.seek(self.clock + SEEK_STEP_SECS),
+            KeyCode::Left | KeyCode::Char('h') => self.seek(self.clock - SEEK_STEP_SECS),
+            KeyCode::Home | KeyCode::Char('0') => self.seek(0.0),
+            KeyCode::End => self.seek(self.cast.duration()),
+            KeyCode::Char('.') => {
+                // Step to the next event while paused
+                if let Some(event) = self.cast.events.get(self.next_event) {
+                    let time = event.time;
+                    self.paused = true;
+                    self.seek(time);
+                }
+            }
+            KeyCode::Up | KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.speed = (self.speed * 2.0).min(MAX_SPEED);
+            }
+            KeyCode::Down | KeyCode::Char('-') => {
+                self.speed = (self.speed / 2.0).max(MIN_SPEED);
+            }
+            _ => {}
+        }
+        false
+    }
+
+    pub fn status_line(&self) -> String {
+        let state = if self.at_end() {
+            "■"
+        } else if self.paused {
+            "⏸"
+        } else {
+            "▶"
+        };
+        format!(
+            " {state} {} / {}  {}x  [space] play/pause  [←/→] seek  [+/-] speed  [.] step  [q] quit ",
+            format_time(self.clock),
+            format_time(self.cast.duration()),
+            self.speed
+        )
+    }
+}
+
+fn format_time(secs: f64) -> String {
+    let total = secs.max(0.0) as u64;
+    format!("{:02}:{:02}", total / 60, total % 60)
+}
+
+pub async fn run(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    player: &mut Player,
+) -> Result<()> {
+    // No child process: keep the sender alive so the PTY branch just stays idle
+    let (_pty_tx, pty_rx) = mpsc::unbounded_channel();
+    let mut events = EventHandler::new(33, None, pty_rx);
+
+    loop {
+        player.advance();
+        terminal.draw(|frame| {
+            ui::draw(frame, &mut player.app);
+            if let Some(inner) = player.app.terminal_area {
+                ui::draw_border_status(frame, inner, &player.status_line());
+            }
+        })?;
+
+        match events.next().await? {
+            event::Event::Key(key) if player.handle_key(key) => return Ok(()),
+            event::Event::Mouse(mouse) => player.app.handle_mouse(mouse),
+            event::Event::Signal => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(cast: &str) -> Player {
+        let temp = tempfile::tempdir().unwrap();
+        let cast = Cast::parse(cast).unwrap();
+        Player::new(cast, temp.path(), 30, false, 10).unwrap()
+    }
+
+    const CAST: &str = "{\"version\": 2, \"width\": 20, \"height\": 3, \"cltree\": {\"root\": \"/tmp/demo\", \"tree\": [\"src/\", \"src/main.rs\"]}}\n\
+        [0.5, \"o\", \"one \"]\n\
+        [1.0, \"r\", \"30x4\"]\n\
+        [2.0, \"i\", \"x\"]\n\
+        [3.0, \"o\", \"two\"]\n";
+
+    #[test]
+    fn seek_feeds_events_up_to_target() {
+        let mut p = player(CAST);
+        p.seek(1.5);
+        assert_eq!(p.app.terminal.vterm_lock().row_text(0), "one");
+        assert_eq!(p.app.terminal.vterm_lock().cols(), 30);
+
+        p.seek(10.0);
+        assert_eq!(p.app.terminal.vterm_lock().row_text(0), "one two");
+        assert!(p.at_end());
+    }
+
+    #[test]
+    fn seeking_backwards_rebuilds_the_screen() {
+        let mut p = player(CAST);
+        p.seek(10.0);
+        p.seek(0.6);
+        assert_eq!(p.app.terminal.vterm_lock().row_text(0), "one");
+        assert_eq!(p.app.terminal.vterm_lock().cols(), 20);
+    }
+
+    #[test]
+    fn uses_recorded_tree_snapshot() {
+        let p = player(CAST);
+        let names: Vec<&str> = p.app.tree.nodes().iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["demo", "src", "main.rs"]);
+    }
+}
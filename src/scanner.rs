@@ -0,0 +1,159 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+use crate::event::Event;
+use crate::finder;
+use crate::tree::{GitStatusMap, Scan, ScanControl, SizeScan};
+
+/// Runs tree scans on a worker thread, one at a time from the UI's point of view:
+/// starting a scan cancels the previous one, and only the latest result is accepted.
+pub struct TreeScanner {
+    tx: mpsc::UnboundedSender<Event>,
+    generation: u64,
+    current: Option<Arc<ScanControl>>,
+}
+
+impl TreeScanner {
+    pub fn new(tx: mpsc::UnboundedSender<Event>) -> Self {
+        Self {
+            tx,
+            generation: 0,
+            current: None,
+        }
+    }
+
+    pub fn start(&mut self, scan: Scan) {
+        if let Some(stale) = self.current.take() {
+            stale.cancel();
+        }
+        self.generation += 1;
+        self.current = Some(scan.control());
+
+        let generation = self.generation;
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            if let Some(result) = scan.run() {
+                let _ = tx.send(Event::TreeScanned(generation, Box::new(result)));
+            }
+        });
+    }
+
+    /// Whether a result belongs to the latest scan. Marks the scan finished if so.
+    pub fn finish(&mut self, generation: u64) -> bool {
+        if generation != self.generation || self.current.is_none() {
+            return false;
+        }
+        self.current = None;
+        true
+    }
+
+    /// Entries read by the running scan, if one is running.
+    pub fn progress(&self) -> Option<usize> {
+        self.current.as_ref().map(|control| control.entries())
+    }
+}
+
+impl Drop for TreeScanner {
+    fn drop(&mut self) {
+        if let Some(control) = self.current.take() {
+            control.cancel();
+        }
+    }
+}
+
+/// Runs `git status` on a worker thread, at most one at a time.
+pub struct GitStatusReader {
+    tx: mpsc::UnboundedSender<Event>,
+    running: bool,
+}
+
+impl GitStatusReader {
+    pub fn new(tx: mpsc::UnboundedSender<Event>) -> Self {
+        Self { tx, running: false }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn start(&mut self, root: PathBuf) {
+        self.running = true;
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            // Not a repository, or git missing or failing: show no markers
+            let status = GitStatusMap::read(&root).ok().flatten();
+            let _ = tx.send(Event::GitStatus(root, Box::new(status)));
+        });
+    }
+
+    pub fn finish(&mut self) {
+        self.running = false;
+    }
+}
+
+/// Shortest pause between two directory size walks.
+const SIZE_RESCAN_MIN_GAP: Duration = Duration::from_secs(2);
+
+/// Totals directory sizes for the size column on a worker thread, at most one at a time.
+///
+/// Every walk covers the whole tree, so a busy watcher would keep one running
+/// back to back. After each walk the reader rests for at least as long as the
+/// walk took, which keeps large trees to half a core at most.
+pub struct DirSizeReader {
+    tx: mpsc::UnboundedSender<Event>,
+    // When the running walk started
+    started: Option<Instant>,
+    rest_until: Option<Instant>,
+}
+
+impl DirSizeReader {
+    pub fn new(tx: mpsc::UnboundedSender<Event>) -> Self {
+        Self {
+            tx,
+            started: None,
+            rest_until: None,
+        }
+    }
+
+    /// No walk is running and the rest after the last one is over.
+    pub fn is_ready(&self) -> bool {
+        self.started.is_none() && self.rest_until.is_none_or(|t| Instant::now() >= t)
+    }
+
+    pub fn start(&mut self, scan: SizeScan) {
+        self.started = Some(Instant::now());
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let _ = tx.send(Event::DirSizes(Box::new(scan.run())));
+        });
+    }
+
+    pub fn finish(&mut self) {
+        if let Some(started) = self.started.take() {
+            let took = started.elapsed();
+            self.rest_until = Some(Instant::now() + took.max(SIZE_RESCAN_MIN_GAP));
+        }
+    }
+}
+
+/// Lists every file under a root for the fuzzy finder, on a worker thread.
+pub struct FileLister {
+    tx: mpsc::UnboundedSender<Event>,
+}
+
+impl FileLister {
+    pub fn new(tx: mpsc::UnboundedSender<Event>) -> Self {
+        Self { tx }
+    }
+
+    pub fn start(&self, root: PathBuf, show_hidden: bool) {
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let files = finder::list_files(&root, show_hidden);
+            let _ = tx.send(Event::FinderFiles(root, files));
+        });
+    }
+}
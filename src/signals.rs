@@ -0,0 +1,89 @@
+use std::io;
+
+/// Signals cltree can deliver to the child's foreground process group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChildSignal {
+    Interrupt,
+    Terminate,
+    Kill,
+    Stop,
+    Continue,
+}
+
+impl ChildSignal {
+    pub fn name(self) -> &'static str {
+        match self {
+            ChildSignal::Interrupt => "SIGINT",
+            ChildSignal::Terminate => "SIGTERM",
+            ChildSignal::Kill => "SIGKILL",
+            ChildSignal::Stop => "SIGSTOP",
+            ChildSignal::Continue => "SIGCONT",
+        }
+    }
+
+    #[cfg(unix)]
+    fn number(self) -> libc::c_int {
+        match self {
+            ChildSignal::Interrupt => libc::SIGINT,
+            ChildSignal::Terminate => libc::SIGTERM,
+            ChildSignal::Kill => libc::SIGKILL,
+            ChildSignal::Stop => libc::SIGSTOP,
+            ChildSignal::Continue => libc::SIGCONT,
+        }
+    }
+
+    /// Send the signal to every process in the group `pgid`.
+    #[cfg(unix)]
+    pub fn send_to_group(self, pgid: i32) -> io::Result<()> {
+        // kill(0) and kill(-1) would hit our own group or every process we own
+        if pgid <= 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("refusing to signal process group {pgid}"),
+            ));
+        }
+        // SAFETY: kill has no memory-safety preconditions
+        if unsafe { libc::kill(-pgid, self.number()) } == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn send_to_group(self, _pgid: i32) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "process groups are not supported on this platform",
+        ))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::process::{CommandExt, ExitStatusExt};
+    use std::process::Command;
+
+    #[test]
+    fn signals_reach_the_whole_group() {
+        let mut child = Command::new("sleep")
+            .arg("30")
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        let pgid = child.id() as i32;
+
+        ChildSignal::Stop.send_to_group(pgid).unwrap();
+        ChildSignal::Continue.send_to_group(pgid).unwrap();
+        ChildSignal::Terminate.send_to_group(pgid).unwrap();
+        assert_eq!(child.wait().unwrap().signal(), Some(libc::SIGTERM));
+    }
+
+    #[test]
+    fn refuses_special_groups() {
+        assert!(ChildSignal::Kill.send_to_group(0).is_err());
+        assert!(ChildSignal::Kill.send_to_group(1).is_err());
+        assert!(ChildSignal::Kill.send_to_group(-5).is_err());
+    }
+}
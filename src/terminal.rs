@@ -1,12 +1,18 @@
+use anyhow::Context;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use portable_pty::{native_pty_system, CommandBuilder, PtyPair, PtySize};
-use std::io::{Read, Write};
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+use crate::asciicast::Recorder;
+use crate::config::{env_pattern_matches, EnvOverrides};
+use crate::pty_writer::PtyWriter;
+use crate::signals::ChildSignal;
 use crate::vterm::VirtualTerminal;
 
 /// RAII guard that ensures the child process is waited on when dropped,
@@ -32,83 +38,104 @@ fn lock_or_recover<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
 
 pub struct TerminalPane {
     pty_pair: Option<PtyPair>,
-    pty_writer: Arc<Mutex<Option<Box<dyn Write + Send>>>>,
+    pty_writer: Option<PtyWriter>,
+    // When input was last rejected because the write queue was full
+    input_rejected_at: Option<Instant>,
     vterm: Arc<Mutex<VirtualTerminal>>,
     cwd: PathBuf,
     child_pid: Option<u32>,
+    // When the child was spawned, for the status bar's run time
+    started_at: Option<Instant>,
     process_exited: Arc<AtomicBool>,
     last_cols: u16,
     last_rows: u16,
     // Debounce: pending CWD change must be detected consistently before applying
     pending_cwd: Option<PathBuf>,
     pending_cwd_count: u32,
+    recorder: Option<Arc<Mutex<Recorder>>>,
+    // The command the child was started with, kept for restarts; None when detached
+    launch: Option<(CommandBuilder, mpsc::UnboundedSender<()>)>,
+    // Detached panes (replay) keep the vterm size instead of following the layout
+    fixed_size: bool,
+    // Process group we stopped with SIGSTOP, so resume targets the same one
+    suspended_pgid: Option<i32>,
 }
 
 impl TerminalPane {
     pub fn new(
         cwd: &Path,
         claude_args: &[String],
+        env_layers: &[EnvOverrides],
+        recorder: Option<Recorder>,
         pty_tx: mpsc::UnboundedSender<()>,
     ) -> anyhow::Result<Self> {
         let vterm = Arc::new(Mutex::new(VirtualTerminal::new(80, 24)));
         let process_exited = Arc::new(AtomicBool::new(false));
-
-        let pty_writer: Arc<Mutex<Option<Box<dyn Write + Send>>>> = Arc::new(Mutex::new(None));
+        let recorder = recorder.map(|r| Arc::new(Mutex::new(r)));
 
         // Try to create PTY and spawn claude process
-        let (pty_pair, child_pid) = match Self::try_spawn_claude(
-            cwd,
-            &vterm,
-            claude_args,
-            &process_exited,
-            pty_tx,
-            &pty_writer,
-        ) {
-            Ok((pair, pid)) => (Some(pair), pid),
-            Err(e) => {
-                // Store error message in vterm so user can see it
-                let msg = format!(
-                    "Failed to start Claude Code: {}\r\n\r\n\
-                     Make sure 'claude' CLI is installed and in your PATH.\r\n\
-                     Install: npm install -g @anthropic-ai/claude-code\r\n",
-                    e
-                );
-                lock_or_recover(&vterm).feed(msg.as_bytes());
-                (None, None)
-            }
-        };
+        let cmd = Self::build_command(cwd, claude_args, env_layers);
+        let launch = Some((cmd.clone(), pty_tx.clone()));
+        let (pty_pair, child_pid, pty_writer) =
+            match Self::try_spawn_claude(cmd, &vterm, &process_exited, pty_tx, recorder.clone()) {
+                Ok((pair, pid, writer)) => (Some(pair), pid, writer),
+                Err(e) => {
+                    // Store error message in vterm so user can see it
+                    lock_or_recover(&vterm).feed(spawn_failure_message(&e).as_bytes());
+                    (None, None, None)
+                }
+            };
 
         Ok(Self {
             pty_pair,
             pty_writer,
+            input_rejected_at: None,
             vterm,
             cwd: cwd.to_path_buf(),
             child_pid,
+            started_at: Some(Instant::now()),
             process_exited,
             last_cols: 80,
             last_rows: 24,
             pending_cwd: None,
             pending_cwd_count: 0,
+            recorder,
+            launch,
+            fixed_size: false,
+            suspended_pgid: None,
         })
     }
 
-    fn try_spawn_claude(
+    /// A pane with no child process whose content is fed by the caller (replay).
+    pub fn detached(cwd: &Path, cols: u16, rows: u16) -> Self {
+        Self {
+            pty_pair: None,
+            pty_writer: None,
+            input_rejected_at: None,
+            vterm: Arc::new(Mutex::new(VirtualTerminal::new(
+                cols as usize,
+                rows as usize,
+            ))),
+            cwd: cwd.to_path_buf(),
+            child_pid: None,
+            started_at: None,
+            process_exited: Arc::new(AtomicBool::new(false)),
+            last_cols: cols,
+            last_rows: rows,
+            pending_cwd: None,
+            pending_cwd_count: 0,
+            recorder: None,
+            launch: None,
+            fixed_size: true,
+            suspended_pgid: None,
+        }
+    }
+
+    fn build_command(
         cwd: &Path,
-        vterm: &Arc<Mutex<VirtualTerminal>>,
         claude_args: &[String],
-        process_exited: &Arc<AtomicBool>,
-        pty_tx: mpsc::UnboundedSender<()>,
-        pty_writer: &Arc<Mutex<Option<Box<dyn Write + Send>>>>,
-    ) -> anyhow::Result<(PtyPair, Option<u32>)> {
-        // Create PTY
-        let pty_system = native_pty_system();
-        let pty_pair = pty_system.openpty(PtySize {
-            rows: 24,
-            cols: 80,
-            pixel_width: 0,
-            pixel_height: 0,
-        })?;
-
+        env_layers: &[EnvOverrides],
+    ) -> CommandBuilder {
         // Spawn claude process via login shell so that shell profiles (.bashrc, .zshrc)
         // are sourced — this ensures nvm/fnm/node and other environment setup is available.
         let command = std::env::var("CLTREE_COMMAND").unwrap_or_else(|_| "claude".to_string());
@@ -139,26 +166,40 @@ impl TerminalPane {
         cmd.arg("-c");
         cmd.arg(&full_cmd);
         cmd.cwd(cwd);
-        cmd.env("TERM", "xterm-256color");
-        // Remove CLAUDECODE env var to allow nested Claude sessions
-        cmd.env_remove("CLAUDECODE");
+        apply_env(&mut cmd, env_layers);
+        cmd
+    }
+
+    fn try_spawn_claude(
+        cmd: CommandBuilder,
+        vterm: &Arc<Mutex<VirtualTerminal>>,
+        process_exited: &Arc<AtomicBool>,
+        pty_tx: mpsc::UnboundedSender<()>,
+        recorder: Option<Arc<Mutex<Recorder>>>,
+    ) -> anyhow::Result<(PtyPair, Option<u32>, Option<PtyWriter>)> {
+        // Create PTY
+        let pty_system = native_pty_system();
+        let pty_pair = pty_system.openpty(PtySize {
+            rows: 24,
+            cols: 80,
+            pixel_width: 0,
+            pixel_height: 0,
+        })?;
 
         let child = pty_pair.slave.spawn_command(cmd)?;
 
         // Get child PID before moving child into the thread
         let child_pid = child.process_id();
 
-        // Take the writer from master PTY (can only be called once)
-        // Store it in the shared Arc<Mutex<>> so both main thread and reader thread can use it
-        if let Ok(writer) = pty_pair.master.take_writer() {
-            *lock_or_recover(pty_writer) = Some(writer);
-        }
+        // Take the writer from master PTY (can only be called once) and hand it to
+        // a dedicated writer thread so slow child reads never block the UI
+        let pty_writer = pty_pair.master.take_writer().ok().map(PtyWriter::spawn);
 
         // Read output in background thread
         let mut reader = pty_pair.master.try_clone_reader()?;
         let vterm_clone = Arc::clone(vterm);
         let exited_clone = Arc::clone(process_exited);
-        let writer_clone = Arc::clone(pty_writer);
+        let writer_clone = pty_writer.clone();
 
         thread::spawn(move || {
             // ChildGuard ensures wait() is called even on panic
@@ -171,19 +212,17 @@ impl TerminalPane {
                 match reader.read(&mut buf) {
                     Ok(0) => break,
                     Ok(n) => {
+                        if let Some(ref recorder) = recorder {
+                            lock_or_recover(recorder).output(&buf[..n]);
+                        }
                         let mut vt = lock_or_recover(&vterm_clone);
                         vt.feed(&buf[..n]);
                         // Flush any DSR/CPR responses back to the PTY
                         let responses = vt.take_responses();
-                        drop(vt); // Release lock before I/O
-                        if !responses.is_empty() {
-                            if let Ok(mut guard) = writer_clone.lock() {
-                                if let Some(ref mut writer) = *guard {
-                                    for resp in responses {
-                                        let _ = writer.write_all(&resp);
-                                    }
-                                    let _ = writer.flush();
-                                }
+                        drop(vt);
+                        if let Some(ref writer) = writer_clone {
+                            for resp in responses {
+                                writer.send_response(resp);
                             }
                         }
                         let _ = pty_tx.send(());
@@ -197,7 +236,7 @@ impl TerminalPane {
             // ChildGuard::drop will set exited flag and wait for child
         });
 
-        Ok((pty_pair, child_pid))
+        Ok((pty_pair, child_pid, pty_writer))
     }
 
     pub fn cwd(&self) -> &Path {
@@ -332,6 +371,124 @@ impl TerminalPane {
         self.process_exited.load(Ordering::SeqCst)
     }
 
+    pub fn child_pid(&self) -> Option<u32> {
+        self.child_pid
+    }
+
+    /// How long the child has been running; None for a pane without one.
+    pub fn run_time(&self) -> Option<Duration> {
+        Some(self.started_at?.elapsed())
+    }
+
+    /// Start the child's command again in a fresh terminal, then hang up on
+    /// the old child. If the new one fails to start, the old one keeps running.
+    pub fn restart(&mut self) -> anyhow::Result<()> {
+        let (cmd, pty_tx) = self.launch.clone().context("no command to restart")?;
+
+        // Output still in flight from the old child lands in the old terminal, and
+        // its exit must not end the session, so both get replaced
+        let vterm = Arc::new(Mutex::new(VirtualTerminal::new(
+            self.last_cols as usize,
+            self.last_rows as usize,
+        )));
+        let process_exited = Arc::new(AtomicBool::new(false));
+        let (pty_pair, child_pid, pty_writer) = match Self::try_spawn_claude(
+            cmd,
+            &vterm,
+            &process_exited,
+            pty_tx,
+            self.recorder.clone(),
+        ) {
+            Ok(spawned) => spawned,
+            Err(e) => {
+                lock_or_recover(&self.vterm).feed(spawn_failure_message(&e).as_bytes());
+                return Err(e);
+            }
+        };
+        let _ = pty_pair.master.resize(PtySize {
+            rows: self.last_rows,
+            cols: self.last_cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        });
+
+        if !self.is_process_exited() {
+            if let Some(pid) = self.child_pid {
+                // The child leads its own session, so its pid is also its group
+                let _ = ChildSignal::Terminate.send_to_group(pid as i32);
+            }
+        }
+        if let Some(writer) = self.pty_writer.take() {
+            writer.close();
+        }
+
+        self.pty_pair = Some(pty_pair);
+        self.pty_writer = pty_writer;
+        self.vterm = vterm;
+        self.process_exited = process_exited;
+        self.child_pid = child_pid;
+        self.started_at = Some(Instant::now());
+        self.input_rejected_at = None;
+        self.suspended_pgid = None;
+        self.pending_cwd = None;
+        self.pending_cwd_count = 0;
+        Ok(())
+    }
+
+    /// The PTY's foreground process group (`tcgetpgrp`), e.g. a tool Claude is running.
+    pub fn foreground_pgid(&self) -> Option<i32> {
+        #[cfg(unix)]
+        {
+            self.pty_pair
+                .as_ref()?
+                .master
+                .process_group_leader()
+                .filter(|&pgid| pgid > 0)
+        }
+        #[cfg(not(unix))]
+        {
+            None
+        }
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.suspended_pgid.is_some()
+    }
+
+    /// Signal the foreground process group, falling back to the child's own group.
+    /// Returns the group that was signalled.
+    pub fn signal(&mut self, signal: ChildSignal) -> anyhow::Result<i32> {
+        if self.is_process_exited() {
+            anyhow::bail!("the child process has exited");
+        }
+        let pgid = match (signal, self.suspended_pgid) {
+            (ChildSignal::Continue, Some(pgid)) => pgid,
+            _ => self
+                .foreground_pgid()
+                .or(self.child_pid.map(|pid| pid as i32))
+                .context("no child process")?,
+        };
+        signal
+            .send_to_group(pgid)
+            .with_context(|| format!("sending {} to process group {pgid}", signal.name()))?;
+        match signal {
+            ChildSignal::Stop => self.suspended_pgid = Some(pgid),
+            ChildSignal::Continue | ChildSignal::Kill => self.suspended_pgid = None,
+            ChildSignal::Interrupt | ChildSignal::Terminate => {}
+        }
+        Ok(pgid)
+    }
+
+    /// SIGSTOP the foreground group, or SIGCONT the group stopped earlier.
+    pub fn toggle_suspend(&mut self) -> anyhow::Result<(ChildSignal, i32)> {
+        let signal = if self.is_suspended() {
+            ChildSignal::Continue
+        } else {
+            ChildSignal::Stop
+        };
+        self.signal(signal).map(|pgid| (signal, pgid))
+    }
+
     pub fn handle_key(&mut self, key: KeyEvent) {
         // Compute modifier parameter for CSI sequences (xterm style)
         // 1=none, 2=Shift, 3=Alt, 4=Shift+Alt, 5=Ctrl, 6=Ctrl+Shift, 7=Ctrl+Alt, 8=Ctrl+Shift+Alt
@@ -589,25 +746,73 @@ impl TerminalPane {
             _ => return,
         };
 
-        if let Ok(mut guard) = self.pty_writer.lock() {
-            if let Some(ref mut writer) = *guard {
-                let _ = writer.write_all(&bytes);
-                let _ = writer.flush();
+        self.send_input(bytes);
+    }
+
+    /// Queue bytes for the child. Returns false if the write queue was full.
+    fn send_input(&mut self, bytes: Vec<u8>) -> bool {
+        let Some(ref writer) = self.pty_writer else {
+            return true;
+        };
+        // Record only what the child will actually receive
+        let recorded = self.recorder.as_ref().map(|_| bytes.clone());
+        match writer.send_input(bytes) {
+            Ok(()) => {
+                if let (Some(recorder), Some(bytes)) = (&self.recorder, recorded) {
+                    lock_or_recover(recorder).input(&bytes);
+                }
+                true
+            }
+            Err(_) => {
+                self.input_rejected_at = Some(Instant::now());
+                false
             }
         }
     }
 
-    /// Send pasted text wrapped in bracketed-paste escape sequences.
-    /// This prevents the terminal from interpreting newlines as Enter keypresses.
+    /// Bytes of input accepted but not yet written to the child.
+    pub fn pending_input(&self) -> usize {
+        self.pty_writer
+            .as_ref()
+            .map(PtyWriter::pending_input)
+            .unwrap_or(0)
+    }
+
+    /// Whether input was dropped recently because the child is not reading it.
+    pub fn input_recently_rejected(&self) -> bool {
+        self.input_rejected_at
+            .is_some_and(|at| at.elapsed() < Duration::from_secs(3))
+    }
+
+    /// Feed output into the virtual terminal directly (replay).
+    pub fn feed(&self, bytes: &[u8]) {
+        lock_or_recover(&self.vterm).feed(bytes);
+    }
+
+    /// Replace the virtual terminal with a blank one of the given size (replay seek).
+    pub fn reset(&mut self, cols: u16, rows: u16) {
+        *lock_or_recover(&self.vterm) = VirtualTerminal::new(cols as usize, rows as usize);
+        self.last_cols = cols;
+        self.last_rows = rows;
+    }
+
+    /// Resize the virtual terminal of a detached pane.
+    pub fn resize_detached(&mut self, cols: u16, rows: u16) {
+        self.last_cols = cols;
+        self.last_rows = rows;
+        lock_or_recover(&self.vterm).resize(cols as usize, rows as usize);
+    }
+
+    /// Send pasted text to the child.
+    ///
+    /// If the child enabled bracketed paste (DECSET 2004) the text is wrapped in
+    /// `ESC[200~`/`ESC[201~` so newlines are not taken as Enter presses; otherwise it
+    /// is sent as if typed, with line breaks turned into carriage returns. Either way
+    /// control characters are stripped so a paste cannot smuggle in escape sequences.
     pub fn handle_paste(&mut self, text: String) {
-        if let Ok(mut guard) = self.pty_writer.lock() {
-            if let Some(ref mut writer) = *guard {
-                let _ = writer.write_all(b"\x1b[200~");
-                let _ = writer.write_all(text.as_bytes());
-                let _ = writer.write_all(b"\x1b[201~");
-                let _ = writer.flush();
-            }
-        }
+        let bracketed = self.vterm_lock().bracketed_paste_enabled();
+        // One message, so the writer never splits the markers from the payload
+        self.send_input(encode_paste(&text, bracketed));
     }
 
     pub fn send_focus_event(&mut self, gained: bool) {
@@ -622,12 +827,7 @@ impl TerminalPane {
         } else {
             b"\x1b[O"
         };
-        if let Ok(mut guard) = self.pty_writer.lock() {
-            if let Some(ref mut writer) = *guard {
-                let _ = writer.write_all(seq);
-                let _ = writer.flush();
-            }
-        }
+        self.send_input(seq.to_vec());
     }
 
     /// Acquire a poison-safe lock on the virtual terminal.
@@ -712,11 +912,14 @@ impl TerminalPane {
     }
 
     pub fn resize(&mut self, cols: u16, rows: u16) {
-        if cols == self.last_cols && rows == self.last_rows {
+        if self.fixed_size || (cols == self.last_cols && rows == self.last_rows) {
             return;
         }
         self.last_cols = cols;
         self.last_rows = rows;
+        if let Some(ref recorder) = self.recorder {
+            lock_or_recover(recorder).resize(cols, rows);
+        }
 
         // Resize the PTY
         if let Some(ref pty_pair) = self.pty_pair {
@@ -735,11 +938,52 @@ impl TerminalPane {
 
 impl Drop for TerminalPane {
     fn drop(&mut self) {
-        // PTY will be cleaned up automatically
+        // Let the writer thread exit; PTY will be cleaned up automatically
+        if let Some(ref writer) = self.pty_writer {
+            writer.close();
+        }
         self.pty_pair.take();
     }
 }
 
+/// Shown in the terminal pane when the child cannot be started.
+fn spawn_failure_message(e: &anyhow::Error) -> String {
+    format!(
+        "Failed to start Claude Code: {e}\r\n\r\n\
+         Make sure 'claude' CLI is installed and in your PATH.\r\n\
+         Install: npm install -g @anthropic-ai/claude-code\r\n"
+    )
+}
+
+/// Set up the child environment: cltree's defaults first, then each override
+/// layer in order (config files, then CLI flags).
+fn apply_env(cmd: &mut CommandBuilder, env_layers: &[EnvOverrides]) {
+    cmd.env("TERM", "xterm-256color");
+    cmd.env("COLORTERM", "truecolor");
+    // Let tools detect that they run inside cltree
+    cmd.env("TERM_PROGRAM", "cltree");
+    cmd.env("TERM_PROGRAM_VERSION", env!("CARGO_PKG_VERSION"));
+    // Remove CLAUDECODE env var to allow nested Claude sessions
+    cmd.env_remove("CLAUDECODE");
+
+    for layer in env_layers {
+        if !layer.unset.is_empty() {
+            let doomed: Vec<String> = cmd
+                .iter_full_env_as_str()
+                .map(|(key, _)| key)
+                .filter(|key| layer.unset.iter().any(|p| env_pattern_matches(p, key)))
+                .map(str::to_string)
+                .collect();
+            for key in doomed {
+                cmd.env_remove(key);
+            }
+        }
+        for (key, value) in &layer.set {
+            cmd.env(key, value);
+        }
+    }
+}
+
 /// Get the current working directory of a process by PID.
 /// Uses macOS `proc_pidinfo` API or Linux `/proc/PID/cwd`.
 #[cfg(target_os = "macos")]
@@ -813,3 +1057,237 @@ fn get_process_cwd(pid: u32) -> Option<PathBuf> {
 fn get_process_cwd(_pid: u32) -> Option<PathBuf> {
     None
 }
+
+/// Bytes to write to the child for a paste.
+pub(crate) fn encode_paste(text: &str, bracketed: bool) -> Vec<u8> {
+    let mut clean = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' | '\n' => {
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                clean.push(if bracketed { '\n' } else { '\r' });
+            }
+            '\t' => clean.push(c),
+            // Drop whole escape sequences, including embedded ESC[201~ paste markers
+            '\x1b' => match chars.peek() {
+                Some('[') => {
+                    // CSI: parameters and intermediates up to a final byte
+                    chars.next();
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                Some(']') => {
+                    // OSC: up to BEL or ST
+                    chars.next();
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                // Other escapes are intermediates and a final byte. A lone ESC goes
+                // alone, so a newline or tab right after it is kept.
+                _ => {
+                    let mut rest = chars.clone();
+                    while rest.next_if(|c| ('\x20'..='\x2f').contains(c)).is_some() {}
+                    if rest.next_if(|c| ('\x30'..='\x7e').contains(c)).is_some() {
+                        chars = rest;
+                    }
+                }
+            },
+            // Remaining C0, DEL and C1 controls
+            c if c.is_control() => {}
+            c => clean.push(c),
+        }
+    }
+
+    if !bracketed {
+        return clean.into_bytes();
+    }
+    let mut bytes = Vec::with_capacity(clean.len() + 12);
+    bytes.extend_from_slice(b"\x1b[200~");
+    bytes.extend_from_slice(clean.as_bytes());
+    bytes.extend_from_slice(b"\x1b[201~");
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run `env` in a PTY with the given overrides and return its output.
+    fn child_env(env_layers: &[EnvOverrides]) -> String {
+        let pair = native_pty_system()
+            .openpty(PtySize {
+                rows: 24,
+                cols: 200,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .expect("failed to open pty");
+        let mut cmd = CommandBuilder::new("env");
+        cmd.env("CLTREE_TEST_SECRET_A", "a");
+        cmd.env("CLTREE_TEST_SECRET_B", "b");
+        cmd.env("CLAUDECODE", "1");
+        apply_env(&mut cmd, env_layers);
+
+        let mut child = pair.slave.spawn_command(cmd).expect("failed to spawn env");
+        drop(pair.slave);
+        let mut reader = pair.master.try_clone_reader().expect("no reader");
+        let mut out = Vec::new();
+        let mut buf = [0u8; 4096];
+        // Linux reports EIO instead of EOF once the child side closes
+        while let Ok(n) = reader.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&buf[..n]);
+        }
+        let _ = child.wait();
+        String::from_utf8_lossy(&out).replace("\r\n", "\n")
+    }
+
+    fn has_line(output: &str, line: &str) -> bool {
+        output.lines().any(|l| l == line)
+    }
+
+    #[test]
+    fn child_gets_default_environment() {
+        let out = child_env(&[]);
+        assert!(has_line(&out, "TERM=xterm-256color"), "{out}");
+        assert!(has_line(&out, "COLORTERM=truecolor"), "{out}");
+        assert!(has_line(&out, "TERM_PROGRAM=cltree"), "{out}");
+        assert!(
+            has_line(
+                &out,
+                &format!("TERM_PROGRAM_VERSION={}", env!("CARGO_PKG_VERSION"))
+            ),
+            "{out}"
+        );
+        assert!(!out.contains("CLAUDECODE="), "{out}");
+    }
+
+    #[test]
+    fn child_env_layers_apply_in_order() {
+        let config = EnvOverrides {
+            set: vec![
+                ("CLAUDE_CONFIG_DIR".into(), "/tmp/claude-work".into()),
+                ("COLORTERM".into(), "24bit".into()),
+            ],
+            unset: vec![],
+        };
+        let cli = EnvOverrides {
+            set: vec![("CLTREE_TEST_SECRET_B".into(), "kept".into())],
+            unset: vec!["CLTREE_TEST_SECRET_*".into(), "CLAUDE_CONFIG_DIR".into()],
+        };
+        let out = child_env(&[config, cli]);
+
+        assert!(has_line(&out, "COLORTERM=24bit"), "{out}");
+        assert!(!out.contains("CLTREE_TEST_SECRET_A="), "{out}");
+        assert!(has_line(&out, "CLTREE_TEST_SECRET_B=kept"), "{out}");
+        assert!(!out.contains("CLAUDE_CONFIG_DIR="), "{out}");
+    }
+
+    #[test]
+    fn paste_without_bracketing_is_typed() {
+        assert_eq!(encode_paste("a\r\nb\nc", false), b"a\rb\rc");
+        assert_eq!(encode_paste("tab\there", false), b"tab\there");
+    }
+
+    #[test]
+    fn bracketed_paste_is_wrapped_and_sanitized() {
+        assert_eq!(
+            encode_paste("one\r\ntwo", true),
+            b"\x1b[200~one\ntwo\x1b[201~"
+        );
+        // An embedded end marker must not terminate the paste early
+        assert_eq!(
+            encode_paste("x\x1b[201~rm -rf ~\n", true),
+            b"\x1b[200~xrm -rf ~\n\x1b[201~"
+        );
+        // Other escapes, C0 and C1 controls are dropped
+        assert_eq!(
+            encode_paste("a\x1b[31mb\x07c\u{9b}d\x7f\x1b]0;title\x07e", true),
+            b"\x1b[200~abcde\x1b[201~"
+        );
+        // Two-byte and charset escapes go whole; a lone ESC takes nothing with it
+        assert_eq!(
+            encode_paste("a\x1bcb\x1b(Bc\x1b\nd\x1b\te\x1b", true),
+            b"\x1b[200~abc\nd\te\x1b[201~"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn signals_target_the_foreground_group() {
+        let pair = native_pty_system()
+            .openpty(PtySize::default())
+            .expect("failed to open pty");
+        let mut cmd = CommandBuilder::new("sleep");
+        cmd.arg("30");
+        let mut child = pair
+            .slave
+            .spawn_command(cmd)
+            .expect("failed to spawn sleep");
+        let pid = child.process_id();
+
+        let mut pane = TerminalPane::detached(Path::new("/"), 80, 24);
+        pane.pty_pair = Some(pair);
+        pane.child_pid = pid;
+        // The child is a session leader, so it owns the foreground group
+        assert_eq!(pane.foreground_pgid(), pid.map(|p| p as i32));
+
+        let (signal, pgid) = pane.toggle_suspend().unwrap();
+        assert_eq!(
+            (signal, Some(pgid)),
+            (ChildSignal::Stop, pane.foreground_pgid())
+        );
+        assert!(pane.is_suspended());
+        let (signal, _) = pane.toggle_suspend().unwrap();
+        assert_eq!(signal, ChildSignal::Continue);
+        assert!(!pane.is_suspended());
+
+        pane.signal(ChildSignal::Kill).unwrap();
+        let status = child.wait().unwrap();
+        assert!(status.signal().is_some_and(|s| s.contains("Killed")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_restart_keeps_the_running_child() {
+        let pair = native_pty_system()
+            .openpty(PtySize::default())
+            .expect("failed to open pty");
+        let mut cmd = CommandBuilder::new("sleep");
+        cmd.arg("30");
+        let mut child = pair
+            .slave
+            .spawn_command(cmd)
+            .expect("failed to spawn sleep");
+        let pid = child.process_id();
+
+        let mut pane = TerminalPane::detached(Path::new("/"), 80, 24);
+        pane.pty_pair = Some(pair);
+        pane.child_pid = pid;
+        let (tx, _rx) = mpsc::unbounded_channel();
+        pane.launch = Some((CommandBuilder::new("/nonexistent/cltree-test-claude"), tx));
+
+        assert!(pane.restart().is_err());
+        assert!(pane.pty_pair.is_some());
+        assert_eq!(pane.child_pid, pid);
+        assert!(child.try_wait().unwrap().is_none());
+        assert!(pane
+            .vterm_lock()
+            .row_text(0)
+            .starts_with("Failed to start Claude Code"));
+
+        child.kill().unwrap();
+        let _ = child.wait();
+    }
+}
@@ -0,0 +1,16 @@
+//! Helpers shared by unit tests in several modules.
+
+use std::path::Path;
+use std::process::Command;
+
+/// Run git in `dir` with a fixed identity, panicking if it fails.
+pub fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .output()
+        .expect("git not available");
+    assert!(status.status.success(), "git {args:?} failed");
+}
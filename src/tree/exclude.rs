@@ -0,0 +1,242 @@
+use anyhow::{Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::Match;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Ignore file read in every directory, in gitignore syntax, that hides entries
+/// from the tree without affecting git.
+pub const TREE_IGNORE_FILE: &str = ".cltreeignore";
+
+/// `--exclude` and `--include` globs, matched against paths relative to the tree root.
+///
+/// Like ripgrep's `-g`: once there is an include glob, only files matching one
+/// are listed (directories are still walked), and included files are shown even
+/// if an ignore file would hide them. Excludes win over includes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExcludeRules {
+    pub exclude: Vec<String>,
+    pub include: Vec<String>,
+}
+
+impl ExcludeRules {
+    pub fn is_empty(&self) -> bool {
+        self.exclude.is_empty() && self.include.is_empty()
+    }
+
+    /// Compile the globs for a tree rooted at `root`.
+    pub fn overrides(&self, root: &Path) -> Result<Override> {
+        let mut builder = OverrideBuilder::new(root);
+        for glob in &self.include {
+            builder
+                .add(glob)
+                .with_context(|| format!("invalid --include glob '{glob}'"))?;
+        }
+        for glob in &self.exclude {
+            builder
+                .add(&format!("!{glob}"))
+                .with_context(|| format!("invalid --exclude glob '{glob}'"))?;
+        }
+        Ok(builder.build()?)
+    }
+}
+
+/// Decides whether a path is hidden from the tree by the exclude rules or a
+/// `.cltreeignore`, so file watcher events for it can be dropped.
+/// Gitignore is left out: ignored files still change git status.
+pub struct PathFilter {
+    root: PathBuf,
+    overrides: Override,
+    // Parsed `.cltreeignore` per directory; None where there is none
+    ignores: HashMap<PathBuf, Option<Gitignore>>,
+}
+
+impl PathFilter {
+    pub fn new(root: &Path, rules: &ExcludeRules) -> Result<Self> {
+        Ok(Self {
+            root: root.to_path_buf(),
+            overrides: rules.overrides(root)?,
+            ignores: HashMap::new(),
+        })
+    }
+
+    /// Whether `path` or one of its directories is excluded.
+    pub fn is_excluded(&mut self, path: &Path) -> bool {
+        let Ok(rel) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        if path.file_name().is_some_and(|n| n == TREE_IGNORE_FILE) {
+            if let Some(dir) = path.parent() {
+                self.ignores.remove(dir);
+            }
+        }
+        let mut current = self.root.clone();
+        let count = rel.components().count();
+        for (i, component) in rel.components().enumerate() {
+            current.push(component);
+            let is_dir = i + 1 < count || current.is_dir();
+            match self.overrides.matched(&current, is_dir) {
+                Match::Ignore(_) => return true,
+                // Included files are shown whatever the ignore files say
+                Match::Whitelist(_) => continue,
+                Match::None => {}
+            }
+            if self.ignored_by_files(&current, is_dir) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Check `path` against the `.cltreeignore` files above it, deepest first.
+    fn ignored_by_files(&mut self, path: &Path, is_dir: bool) -> bool {
+        let dirs: Vec<PathBuf> = path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(&self.root))
+            .map(Path::to_path_buf)
+            .collect();
+        for dir in dirs {
+            let ignore = self
+                .ignores
+                .entry(dir.clone())
+                .or_insert_with(|| read_ignore_file(&dir));
+            match ignore.as_ref().map(|gi| gi.matched(path, is_dir)) {
+                Some(Match::Ignore(_)) => return true,
+                Some(Match::Whitelist(_)) => return false,
+                _ => {}
+            }
+        }
+        false
+    }
+}
+
+fn read_ignore_file(dir: &Path) -> Option<Gitignore> {
+    let file = dir.join(TREE_IGNORE_FILE);
+    if !file.is_file() {
+        return None;
+    }
+    let mut builder = GitignoreBuilder::new(dir);
+    builder.add(file);
+    builder.build().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::FileTree;
+    use std::fs;
+    use std::process::Command;
+
+    #[test]
+    fn watcher_filter_follows_rules_and_ignore_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("vendor/lib")).unwrap();
+        fs::create_dir_all(root.join("src/fixtures")).unwrap();
+        fs::create_dir_all(root.join("dist")).unwrap();
+        fs::write(root.join(TREE_IGNORE_FILE), "vendor/\n").unwrap();
+        fs::write(
+            root.join("src").join(TREE_IGNORE_FILE),
+            "fixtures/\n*.snap\n!keep.snap\n",
+        )
+        .unwrap();
+
+        let rules = ExcludeRules {
+            exclude: vec!["dist/".into()],
+            include: vec![],
+        };
+        let mut filter = PathFilter::new(&root, &rules).unwrap();
+        assert!(filter.is_excluded(&root.join("vendor/lib/a.rs")));
+        assert!(filter.is_excluded(&root.join("src/fixtures/big.json")));
+        assert!(filter.is_excluded(&root.join("src/x.snap")));
+        assert!(filter.is_excluded(&root.join("dist/bundle.js")));
+        assert!(!filter.is_excluded(&root.join("src/main.rs")));
+        assert!(!filter.is_excluded(&root.join("src/keep.snap")));
+
+        // Editing an ignore file is picked up
+        fs::write(root.join(TREE_IGNORE_FILE), "").unwrap();
+        assert!(!filter.is_excluded(&root.join(TREE_IGNORE_FILE)));
+        assert!(!filter.is_excluded(&root.join("vendor/lib/a.rs")));
+    }
+
+    #[test]
+    fn include_globs_limit_files_but_not_directories() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir(root.join("src")).unwrap();
+        let rules = ExcludeRules {
+            exclude: vec!["src/gen.rs".into()],
+            include: vec!["*.rs".into()],
+        };
+        let mut filter = PathFilter::new(&root, &rules).unwrap();
+        assert!(!filter.is_excluded(&root.join("src/main.rs")));
+        assert!(filter.is_excluded(&root.join("README.md")));
+        assert!(!filter.is_excluded(&root.join("src")));
+        assert!(filter.is_excluded(&root.join("src/gen.rs")));
+
+        let bad = ExcludeRules {
+            exclude: vec!["a{".into()],
+            include: vec![],
+        };
+        assert!(bad.overrides(&root).is_err());
+    }
+
+    fn names(tree: &FileTree) -> Vec<&str> {
+        tree.nodes().iter().map(|n| n.name.as_str()).collect()
+    }
+
+    #[test]
+    fn tree_leaves_out_excluded_entries() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("src/fixtures")).unwrap();
+        fs::create_dir(root.join("vendor")).unwrap();
+        fs::create_dir(root.join("dist")).unwrap();
+        fs::write(root.join("src/fixtures/a.json"), "").unwrap();
+        fs::write(root.join("src/main.rs"), "").unwrap();
+        fs::write(root.join("vendor/lib.rs"), "").unwrap();
+        fs::write(root.join("dist/app.js"), "").unwrap();
+        fs::write(root.join("notes.md"), "").unwrap();
+        // Rules in the root's file apply to subdirectories read on their own
+        fs::write(root.join(TREE_IGNORE_FILE), "vendor/\nfixtures/\n").unwrap();
+
+        let mut tree = FileTree::new(&root, false, 10).unwrap();
+        assert_eq!(
+            names(&tree)[1..],
+            ["dist", "app.js", "src", "main.rs", "notes.md"]
+        );
+
+        tree.set_excludes(ExcludeRules {
+            exclude: vec!["dist/".into()],
+            include: vec!["*.rs".into()],
+        })
+        .unwrap();
+        tree.refresh();
+        assert_eq!(names(&tree)[1..], ["src", "main.rs"]);
+    }
+
+    #[test]
+    fn gitignored_entries_can_be_listed() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let init = Command::new("git")
+            .arg("init")
+            .arg("-q")
+            .arg(&root)
+            .status();
+        if !init.is_ok_and(|s| s.success()) {
+            return;
+        }
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        fs::create_dir(root.join("target")).unwrap();
+        fs::write(root.join("main.rs"), "").unwrap();
+
+        let mut tree = FileTree::new(&root, false, 10).unwrap();
+        assert_eq!(names(&tree)[1..], ["main.rs"]);
+        tree.show_gitignored = true;
+        tree.refresh();
+        assert_eq!(names(&tree)[1..], ["target", "main.rs"]);
+    }
+}
@@ -1,4 +1,6 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use super::{EntryMeta, GitStatus, Symlink};
 
 #[derive(Debug, Clone)]
 pub struct FileNode {
@@ -8,6 +10,16 @@ pub struct FileNode {
     pub is_dir: bool,
     pub is_last: bool,
     pub connector: Vec<bool>,
+    pub git_status: Option<GitStatus>,
+    pub symlink: Option<Symlink>,
+    /// Read when metadata columns are shown.
+    pub meta: Option<EntryMeta>,
+    /// Directories merged into this row above `path`, outermost first, when
+    /// single-child chains are compacted.
+    pub compacted: Vec<PathBuf>,
+    /// Set on the row closing a summarized listing: how many entries of the
+    /// directory at `path` are not shown.
+    pub more: Option<usize>,
 }
 
 impl FileNode {
@@ -26,74 +38,16 @@ impl FileNode {
             is_dir,
             is_last,
             connector,
+            git_status: None,
+            symlink: None,
+            meta: None,
+            compacted: Vec::new(),
+            more: None,
         }
     }
 
-    pub fn expanded_icon(&self, expanded: bool) -> &'static str {
-        if self.is_dir {
-            if expanded {
-                "▾ "
-            } else {
-                "▸ "
-            }
-        } else {
-            "· "
-        }
-    }
-
-    pub fn display_color(&self) -> ratatui::style::Color {
-        use ratatui::style::Color;
-        if self.is_dir {
-            Color::Rgb(209, 164, 73)
-        } else {
-            self.file_type_color()
-        }
-    }
-
-    fn file_type_color(&self) -> ratatui::style::Color {
-        use ratatui::style::Color;
-        let ext = self.path.extension().and_then(|e| e.to_str()).unwrap_or("");
-
-        match ext.to_lowercase().as_str() {
-            // Rust
-            "rs" => Color::Rgb(255, 150, 50),
-            // JavaScript/TypeScript
-            "js" | "mjs" | "cjs" => Color::LightYellow,
-            "ts" | "mts" | "cts" => Color::Rgb(50, 150, 255),
-            "jsx" | "tsx" => Color::Rgb(100, 200, 255),
-            // Python
-            "py" | "pyw" | "pyi" => Color::Rgb(80, 180, 80),
-            // Web
-            "html" | "htm" => Color::Rgb(230, 120, 50),
-            "css" | "scss" | "sass" | "less" => Color::Rgb(180, 100, 255),
-            "vue" | "svelte" => Color::LightGreen,
-            // Data/Config
-            "json" => Color::LightYellow,
-            "yaml" | "yml" | "toml" => Color::Rgb(180, 180, 180),
-            "xml" => Color::Rgb(200, 150, 50),
-            "sql" => Color::Rgb(200, 200, 50),
-            // Docs
-            "md" | "markdown" => Color::Rgb(100, 180, 255),
-            "txt" => Color::Rgb(180, 180, 180),
-            // Shell
-            "sh" | "bash" | "zsh" | "fish" => Color::LightGreen,
-            // Go
-            "go" => Color::Cyan,
-            // Java/Kotlin
-            "java" => Color::Rgb(255, 100, 100),
-            "kt" | "kts" => Color::Rgb(200, 120, 255),
-            // C/C++
-            "c" | "h" => Color::Rgb(100, 150, 255),
-            "cpp" | "cc" | "cxx" | "hpp" => Color::Rgb(100, 150, 255),
-            // Ruby
-            "rb" => Color::LightRed,
-            // Config/Lock
-            "lock" => Color::DarkGray,
-            "env" | "gitignore" | "dockerignore" => Color::DarkGray,
-            // Images
-            "png" | "jpg" | "jpeg" | "gif" | "svg" | "ico" | "webp" => Color::LightMagenta,
-            // Default
-            _ => Color::Rgb(180, 180, 180),
-        }
+    /// Whether this row shows the directory `dir`, on its own or merged into a compacted row.
+    pub fn shows_dir(&self, dir: &Path) -> bool {
+        self.is_dir && (self.path == dir || self.compacted.iter().any(|d| d == dir))
     }
 }
@@ -0,0 +1,355 @@
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+use super::{FileNode, FileTree};
+
+/// An expression that narrows the tree to matching entries and their ancestors.
+///
+/// - `/pattern/` is a regular expression
+/// - anything with `*`, `?` or `[` is a glob (`**` crosses directories)
+/// - anything else matches as a substring
+///
+/// Lowercase expressions ignore case. Expressions containing `/` are matched
+/// against the path relative to the root, others against the name only.
+#[derive(Debug, Clone)]
+pub struct TreeFilter {
+    expr: String,
+    regex: Regex,
+    // Globs are anchored; only their literal parts (capture groups) are highlighted
+    is_glob: bool,
+    on_path: bool,
+}
+
+impl TreeFilter {
+    pub fn parse(expr: &str) -> Result<Self> {
+        let (pattern, is_glob, on_path) = match expr
+            .strip_prefix('/')
+            .and_then(|e| e.strip_suffix('/'))
+        {
+            Some(re) if !re.is_empty() => (re.to_string(), false, re.contains('/')),
+            _ if expr.contains(['*', '?', '[']) => (glob_to_regex(expr), true, expr.contains('/')),
+            _ => (regex::escape(expr), false, expr.contains('/')),
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!expr.chars().any(char::is_uppercase))
+            .build()
+            .with_context(|| format!("invalid filter '{expr}'"))?;
+        Ok(Self {
+            expr: expr.to_string(),
+            regex,
+            is_glob,
+            on_path,
+        })
+    }
+
+    pub fn expr(&self) -> &str {
+        &self.expr
+    }
+
+    /// Byte ranges of `name` to highlight if the entry matches, else None.
+    /// `rel_path` is the entry's path relative to the root, ending in `name`.
+    pub fn find(&self, rel_path: &str, name: &str) -> Option<Vec<Range<usize>>> {
+        let (text, offset) = if self.on_path {
+            (rel_path, rel_path.len().saturating_sub(name.len()))
+        } else {
+            (name, 0)
+        };
+        let ranges: Vec<Range<usize>> = if self.is_glob {
+            let captures = self.regex.captures(text)?;
+            captures
+                .iter()
+                .skip(1)
+                .flatten()
+                .map(|m| m.range())
+                .collect()
+        } else {
+            vec![self.regex.find(text)?.range()]
+        };
+        // Only the part inside the name is drawn highlighted
+        Some(
+            ranges
+                .into_iter()
+                .filter(|r| r.end > offset && r.start < r.end)
+                .map(|r| r.start.max(offset) - offset..r.end - offset)
+                .collect(),
+        )
+    }
+
+    pub fn matches(&self, rel_path: &str, name: &str) -> bool {
+        if self.on_path {
+            self.regex.is_match(rel_path)
+        } else {
+            self.regex.is_match(name)
+        }
+    }
+}
+
+/// Translate a glob into an anchored regex with each literal run in a capture group.
+fn glob_to_regex(glob: &str) -> String {
+    let mut out = String::from("^");
+    let mut literal = String::new();
+    let flush = |out: &mut String, literal: &mut String| {
+        if !literal.is_empty() {
+            out.push('(');
+            out.push_str(&regex::escape(literal));
+            out.push(')');
+            literal.clear();
+        }
+    };
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => {
+                flush(&mut out, &mut literal);
+                if chars.next_if_eq(&'*').is_some() {
+                    // `**/` also matches no directories at all
+                    if chars.next_if_eq(&'/').is_some() {
+                        out.push_str("(?:.*/)?");
+                    } else {
+                        out.push_str(".*");
+                    }
+                } else {
+                    out.push_str("[^/]*");
+                }
+            }
+            '?' => {
+                flush(&mut out, &mut literal);
+                out.push_str("[^/]");
+            }
+            '[' => {
+                let class: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                flush(&mut out, &mut literal);
+                let class = match class.strip_prefix('!') {
+                    Some(rest) => format!("^{rest}"),
+                    None => class,
+                };
+                out.push('[');
+                out.push_str(&class.replace('\\', "\\\\").replace('[', "\\["));
+                out.push(']');
+            }
+            c => literal.push(c),
+        }
+    }
+    flush(&mut out, &mut literal);
+    out.push('$');
+    out
+}
+
+impl FileTree {
+    pub fn filter(&self) -> Option<&TreeFilter> {
+        self.filter.as_ref()
+    }
+
+    /// Narrow the tree to entries matching `filter`, or show everything again with None.
+    /// The filter stays in place across refreshes and root changes.
+    pub fn set_filter(&mut self, filter: Option<TreeFilter>) {
+        self.filter = filter;
+        self.filter_hidden = 0;
+        self.forced_open.clear();
+        // An unscanned tree is filtered by its scan
+        if self.snapshot.is_some() || !self.listings.is_empty() {
+            self.rebuild_visible_nodes();
+        }
+    }
+
+    /// Entries left out by the filter.
+    pub fn filter_hidden(&self) -> usize {
+        self.filter_hidden
+    }
+
+    /// Path of a node relative to the root, as filters see it.
+    pub fn relative_name(&self, node: &FileNode) -> String {
+        node.path
+            .strip_prefix(&self.root)
+            .map(|rel| rel.to_string_lossy().to_string())
+            .unwrap_or_else(|_| node.name.clone())
+    }
+
+    /// Keep the matching nodes of a fully expanded node list, their ancestors,
+    /// and the expanded contents of matching directories.
+    pub(super) fn filter_nodes(&mut self, all: Vec<FileNode>) -> Vec<FileNode> {
+        self.forced_open.clear();
+        let Some(filter) = &self.filter else {
+            self.filter_hidden = 0;
+            return all;
+        };
+        let matched: Vec<bool> = all
+            .iter()
+            .map(|n| n.depth > 0 && filter.matches(&self.relative_name(n), &n.name))
+            .collect();
+
+        // Walking backwards, `found[d]` says whether a node at depth `d` seen
+        // since the last node at a shallower depth matched or contains a match
+        let max_depth = all.iter().map(|n| n.depth).max().unwrap_or(0);
+        let mut found = vec![false; max_depth + 2];
+        let mut match_below = vec![false; all.len()];
+        for (i, node) in all.iter().enumerate().rev() {
+            let d = node.depth;
+            match_below[i] = found[d + 1];
+            found[d] |= matched[i] || match_below[i];
+            found[d + 1..].iter_mut().for_each(|f| *f = false);
+        }
+
+        // Per depth: whether the children of the last node kept there are
+        // shown as the contents of a matching directory
+        let mut open: Vec<bool> = vec![false; max_depth + 1];
+        let mut kept = Vec::with_capacity(all.len());
+        let mut hidden = 0;
+        for (i, node) in all.into_iter().enumerate() {
+            let d = node.depth;
+            let shown_as_content = d > 0 && open[d - 1];
+            let keep = d == 0 || matched[i] || match_below[i] || shown_as_content;
+            if !keep {
+                hidden += 1;
+                open[d] = false;
+                continue;
+            }
+            let expanded = node.is_dir && self.is_path_expanded(&node.path);
+            open[d] = expanded && (matched[i] || shown_as_content);
+            if node.is_dir && d > 0 && !expanded && match_below[i] {
+                self.forced_open.insert(node.path.clone());
+            }
+            kept.push(node);
+        }
+        super::relink(&mut kept);
+        self.filter_hidden = hidden;
+        kept
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    fn highlighted(filter: &str, rel: &str) -> Option<Vec<String>> {
+        let name = Path::new(rel).file_name().unwrap().to_str().unwrap();
+        let ranges = TreeFilter::parse(filter).unwrap().find(rel, name)?;
+        Some(ranges.into_iter().map(|r| name[r].to_string()).collect())
+    }
+
+    #[test]
+    fn substring_glob_and_regex_expressions() {
+        assert_eq!(
+            highlighted("handler", "src/api/Handler.rs"),
+            Some(vec!["Handler".into()])
+        );
+        assert_eq!(highlighted("Handler", "src/handler.rs"), None);
+        assert_eq!(highlighted("*.rs", "src/main.rs"), Some(vec![".rs".into()]));
+        assert_eq!(highlighted("*.rs", "src/main.rs.bak"), None);
+        assert_eq!(
+            highlighted("test_*_io?.rs", "tests/test_file_io2.rs"),
+            Some(vec!["test_".into(), "_io".into(), ".rs".into()])
+        );
+        assert_eq!(
+            highlighted("[mM]ain.*", "Main.java"),
+            Some(vec!["ain.".into()])
+        );
+        assert_eq!(
+            highlighted("/^ma.n\\./", "main.rs"),
+            Some(vec!["main.".into()])
+        );
+        assert!(TreeFilter::parse("/(/").is_err());
+    }
+
+    #[test]
+    fn path_expressions_match_the_relative_path() {
+        assert_eq!(
+            highlighted("api/h", "src/api/handler.rs"),
+            Some(vec!["h".into()])
+        );
+        assert_eq!(highlighted("src/", "src/api/handler.rs"), Some(vec![]));
+        assert_eq!(
+            highlighted("src/**/*.rs", "src/a/b/c.rs"),
+            Some(vec![".rs".into()])
+        );
+        assert_eq!(
+            highlighted("src/**/*.rs", "src/c.rs"),
+            Some(vec![".rs".into()])
+        );
+        assert_eq!(highlighted("src/*.rs", "src/a/c.rs"), None);
+    }
+
+    fn names(tree: &FileTree) -> Vec<&str> {
+        tree.nodes().iter().map(|n| n.name.as_str()).collect()
+    }
+
+    #[test]
+    fn keeps_matches_and_ancestors_across_refresh_and_root_changes() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("src/api")).unwrap();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("src/api/handler.rs"), "").unwrap();
+        fs::write(root.join("src/api/routes.rs"), "").unwrap();
+        fs::write(root.join("src/main.rs"), "").unwrap();
+        fs::write(root.join("docs/handler.md"), "").unwrap();
+        fs::write(root.join("README.md"), "").unwrap();
+
+        let mut tree = FileTree::new(&root, false, 10).unwrap();
+        tree.collapse_all();
+        tree.set_filter(Some(TreeFilter::parse("handler").unwrap()));
+        assert_eq!(
+            names(&tree)[1..],
+            ["docs", "handler.md", "src", "api", "handler.rs"]
+        );
+        assert_eq!(tree.filter_hidden(), 3);
+        // Ancestors of matches are shown open, and the connectors are relinked
+        assert!(tree.is_expanded(&tree.nodes()[3]));
+        assert!(tree.nodes()[3].is_last && tree.nodes()[5].is_last);
+
+        fs::write(root.join("src/handler_test.rs"), "").unwrap();
+        tree.refresh();
+        assert_eq!(
+            names(&tree)[1..],
+            [
+                "docs",
+                "handler.md",
+                "src",
+                "api",
+                "handler.rs",
+                "handler_test.rs"
+            ]
+        );
+
+        tree.set_root(root.join("src"));
+        assert_eq!(names(&tree)[1..], ["api", "handler.rs", "handler_test.rs"]);
+
+        tree.set_filter(None);
+        assert_eq!(names(&tree)[1..], ["api", "handler_test.rs", "main.rs"]);
+        assert_eq!(tree.filter_hidden(), 0);
+    }
+
+    #[test]
+    fn matching_directories_show_their_contents() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("handlers/inner")).unwrap();
+        fs::write(root.join("handlers/a.rs"), "").unwrap();
+        fs::write(root.join("handlers/inner/b.rs"), "").unwrap();
+        fs::write(root.join("other.rs"), "").unwrap();
+
+        let mut tree = FileTree::new(&root, false, 10).unwrap();
+        tree.set_filter(Some(TreeFilter::parse("handlers").unwrap()));
+        assert_eq!(names(&tree)[1..], ["handlers", "inner", "b.rs", "a.rs"]);
+
+        // Collapsing a matching directory hides its contents again
+        tree.set_expanded(&root.join("handlers"), false);
+        assert_eq!(names(&tree)[1..], ["handlers"]);
+    }
+
+    #[test]
+    fn filters_recorded_trees() {
+        let root = Path::new("/nonexistent/demo");
+        let entries: Vec<String> = ["src/", "src/lib.rs", "src/notes.txt", "Cargo.toml"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut tree = FileTree::from_snapshot(root, &entries);
+        tree.set_filter(Some(TreeFilter::parse("*.rs").unwrap()));
+        assert_eq!(names(&tree)[1..], ["src", "lib.rs"]);
+        assert_eq!(tree.filter_hidden(), 2);
+    }
+}
@@ -0,0 +1,383 @@
+use anyhow::{bail, Context, Result};
+use ratatui::style::Color;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::FileTree;
+
+/// Git state of a file, or the most significant state below a directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GitStatus {
+    // Ordered by significance, lowest first, for rolling up into directories
+    Ignored,
+    Untracked,
+    Added,
+    Renamed,
+    Deleted,
+    Modified,
+    Conflicted,
+}
+
+impl GitStatus {
+    pub fn marker(self) -> char {
+        match self {
+            GitStatus::Ignored => '!',
+            GitStatus::Untracked => '?',
+            GitStatus::Added => 'A',
+            GitStatus::Renamed => 'R',
+            GitStatus::Deleted => 'D',
+            GitStatus::Modified => 'M',
+            GitStatus::Conflicted => 'C',
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            GitStatus::Ignored => Color::DarkGray,
+            GitStatus::Untracked => Color::Rgb(120, 200, 120),
+            GitStatus::Added => Color::Green,
+            GitStatus::Renamed => Color::Cyan,
+            GitStatus::Deleted => Color::Red,
+            GitStatus::Modified => Color::Rgb(230, 180, 60),
+            GitStatus::Conflicted => Color::LightRed,
+        }
+    }
+
+    /// Status from the two-letter XY code of a porcelain v2 entry.
+    fn from_xy(xy: &[u8]) -> Option<Self> {
+        let codes = [*xy.first()?, *xy.get(1)?];
+        let has = |c: u8| codes.contains(&c);
+        if has(b'D') {
+            Some(GitStatus::Deleted)
+        } else if has(b'M') || has(b'T') {
+            Some(GitStatus::Modified)
+        } else if has(b'R') || has(b'C') {
+            Some(GitStatus::Renamed)
+        } else if has(b'A') {
+            Some(GitStatus::Added)
+        } else {
+            None
+        }
+    }
+}
+
+/// Split a record (after its type letter) into at most `n` space-separated fields;
+/// the last one is the path and may itself contain spaces.
+fn fields(rest: &[u8], n: usize) -> Vec<&[u8]> {
+    rest.strip_prefix(b" ")
+        .unwrap_or(rest)
+        .splitn(n, |&b| b == b' ')
+        .collect()
+}
+
+/// The checked-out branch and how far it is from its upstream.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BranchInfo {
+    /// Branch name, or `(detached)` when HEAD is not on a branch.
+    pub head: String,
+    /// Commits not on the upstream yet; zero without an upstream.
+    pub ahead: usize,
+    /// Upstream commits not merged yet.
+    pub behind: usize,
+}
+
+/// `git status` for one repository, with directory roll-ups.
+#[derive(Debug, Clone, Default)]
+pub struct GitStatusMap {
+    files: HashMap<PathBuf, GitStatus>,
+    // Untracked or ignored directories reported as a whole (`dir/`)
+    whole_dirs: HashMap<PathBuf, GitStatus>,
+    // Most significant status of anything below each directory
+    rollup: HashMap<PathBuf, GitStatus>,
+    branch: Option<BranchInfo>,
+    // Tracked entries with changes, and untracked entries, as git reports them
+    changed: usize,
+    untracked: usize,
+}
+
+impl GitStatusMap {
+    /// Run `git status` for the repository containing `dir`.
+    /// Returns None when `dir` is not inside a work tree.
+    pub fn read(dir: &Path) -> Result<Option<Self>> {
+        let toplevel = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["rev-parse", "--show-toplevel"])
+            .output()
+            .context("running git")?;
+        if !toplevel.status.success() {
+            return Ok(None);
+        }
+        let top = String::from_utf8_lossy(&toplevel.stdout).trim().to_string();
+        let top = PathBuf::from(top);
+        let top = top.canonicalize().unwrap_or(top);
+
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&top)
+            // Background reads must not rewrite the index, or they'd trigger the watcher again
+            .arg("--no-optional-locks")
+            .args(["status", "--porcelain=v2", "-z", "--branch", "--ignored"])
+            .output()
+            .context("running git status")?;
+        if !output.status.success() {
+            bail!(
+                "git status failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(Some(Self::parse(&top, &output.stdout)))
+    }
+
+    /// Parse `git status --porcelain=v2 -z` output; paths are relative to `top`.
+    pub fn parse(top: &Path, output: &[u8]) -> Self {
+        let mut map = Self::default();
+        let mut records = output.split(|&b| b == 0);
+        while let Some(record) = records.next() {
+            let Some((&kind, rest)) = record.split_first() else {
+                continue;
+            };
+            let (status, path) = match kind {
+                b'#' => {
+                    map.parse_header(rest);
+                    continue;
+                }
+                // 1 XY sub mH mI mW hH hI path
+                b'1' => {
+                    let f = fields(rest, 8);
+                    (GitStatus::from_xy(f[0]), f.get(7).copied())
+                }
+                // 2 XY sub mH mI mW hH hI Xscore path, then the original path as its own record
+                b'2' => {
+                    let f = fields(rest, 9);
+                    records.next();
+                    (GitStatus::from_xy(f[0]), f.get(8).copied())
+                }
+                // u XY sub m1 m2 m3 mW h1 h2 h3 path
+                b'u' => (
+                    Some(GitStatus::Conflicted),
+                    fields(rest, 10).get(9).copied(),
+                ),
+                b'?' => (Some(GitStatus::Untracked), rest.strip_prefix(b" ")),
+                b'!' => (Some(GitStatus::Ignored), rest.strip_prefix(b" ")),
+                _ => (None, None),
+            };
+            match kind {
+                b'1' | b'2' | b'u' => map.changed += 1,
+                b'?' => map.untracked += 1,
+                _ => {}
+            }
+            if let (Some(status), Some(path)) = (status, path) {
+                map.insert(top, &String::from_utf8_lossy(path), status);
+            }
+        }
+        map
+    }
+
+    /// `# branch.head <name>` and `# branch.ab +<ahead> -<behind>` headers.
+    fn parse_header(&mut self, rest: &[u8]) {
+        let header = String::from_utf8_lossy(rest);
+        let Some((key, value)) = header.trim_start().split_once(' ') else {
+            return;
+        };
+        match key {
+            "branch.head" => {
+                self.branch.get_or_insert_with(Default::default).head = value.to_string()
+            }
+            "branch.ab" => {
+                let branch = self.branch.get_or_insert_with(Default::default);
+                for count in value.split(' ') {
+                    if let Some(n) = count.strip_prefix('+') {
+                        branch.ahead = n.parse().unwrap_or(0);
+                    } else if let Some(n) = count.strip_prefix('-') {
+                        branch.behind = n.parse().unwrap_or(0);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The checked-out branch; None for output read without `--branch`.
+    pub fn branch(&self) -> Option<&BranchInfo> {
+        self.branch.as_ref()
+    }
+
+    /// Number of tracked files with staged or unstaged changes.
+    pub fn changed(&self) -> usize {
+        self.changed
+    }
+
+    /// Number of untracked entries; a new directory counts once.
+    pub fn untracked(&self) -> usize {
+        self.untracked
+    }
+
+    fn insert(&mut self, top: &Path, rel: &str, status: GitStatus) {
+        let is_dir = rel.ends_with('/');
+        let path = top.join(rel.trim_end_matches('/'));
+        if status != GitStatus::Ignored {
+            for dir in path.ancestors().skip(1) {
+                let entry = self.rollup.entry(dir.to_path_buf()).or_insert(status);
+                *entry = (*entry).max(status);
+                if dir == top {
+                    break;
+                }
+            }
+        }
+        if is_dir {
+            self.whole_dirs.insert(path, status);
+        } else {
+            self.files.insert(path, status);
+        }
+    }
+
+    /// Status to show for a tree entry.
+    pub fn status_of(&self, path: &Path, is_dir: bool) -> Option<GitStatus> {
+        if let Some(&status) = self.files.get(path) {
+            return Some(status);
+        }
+        // Inside (or equal to) a directory reported as a whole
+        if let Some(status) = path
+            .ancestors()
+            .find_map(|dir| self.whole_dirs.get(dir).copied())
+        {
+            return Some(status);
+        }
+        if is_dir {
+            return self.rollup.get(path).copied();
+        }
+        None
+    }
+}
+
+impl FileTree {
+    /// Replace the git status shown next to entries; None hides the markers.
+    pub fn set_git_status(&mut self, git: Option<GitStatusMap>) {
+        self.git = git;
+        self.decorate_git();
+    }
+
+    /// The last `git status` read for the root, if it is in a repository.
+    pub fn git(&self) -> Option<&GitStatusMap> {
+        self.git.as_ref()
+    }
+
+    pub fn git_status_of(&self, path: &Path, is_dir: bool) -> Option<GitStatus> {
+        self.git.as_ref()?.status_of(path, is_dir)
+    }
+
+    pub(super) fn decorate_git(&mut self) {
+        for node in self.nodes.iter_mut().filter(|n| n.more.is_none()) {
+            node.git_status = self
+                .git
+                .as_ref()
+                .and_then(|git| git.status_of(&node.path, node.is_dir));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::git;
+    use std::fs;
+
+    #[test]
+    fn parses_porcelain_v2() {
+        let top = Path::new("/repo");
+        let output = b"# branch.oid 0123abcd\0\
+            # branch.head main\0\
+            # branch.upstream origin/main\0\
+            # branch.ab +2 -1\0\
+            1 .M N... 100644 100644 100644 aaa bbb src/main.rs\0\
+            1 A. N... 000000 100644 100644 000 ccc src/new file.rs\0\
+            2 R. N... 100644 100644 100644 ddd ddd R100 docs/b.md\0docs/a.md\0\
+            u UU N... 100644 100644 100644 100644 e f g conflict.txt\0\
+            1 D. N... 100644 000000 000000 hhh 000 gone/old.rs\0\
+            ? notes/\0\
+            ! target/\0";
+        let map = GitStatusMap::parse(top, output);
+        let status = |p: &str, dir: bool| map.status_of(&top.join(p), dir);
+
+        assert_eq!(status("src/main.rs", false), Some(GitStatus::Modified));
+        assert_eq!(status("src/new file.rs", false), Some(GitStatus::Added));
+        assert_eq!(status("docs/b.md", false), Some(GitStatus::Renamed));
+        assert_eq!(status("docs/a.md", false), None);
+        assert_eq!(status("conflict.txt", false), Some(GitStatus::Conflicted));
+        assert_eq!(status("notes/todo.md", false), Some(GitStatus::Untracked));
+        assert_eq!(status("target/debug", true), Some(GitStatus::Ignored));
+        // Directories show the most significant status below them
+        assert_eq!(status("src", true), Some(GitStatus::Modified));
+        assert_eq!(status("gone", true), Some(GitStatus::Deleted));
+        assert_eq!(map.status_of(top, true), Some(GitStatus::Conflicted));
+        assert_eq!(status("clean", true), None);
+
+        let branch = map.branch().unwrap();
+        assert_eq!(
+            (branch.head.as_str(), branch.ahead, branch.behind),
+            ("main", 2, 1)
+        );
+        assert_eq!((map.changed(), map.untracked()), (5, 1));
+    }
+
+    #[test]
+    fn reads_a_real_repository() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        git(&root, &["init", "-q"]);
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "one").unwrap();
+        fs::write(root.join("keep.txt"), "").unwrap();
+        fs::write(root.join(".gitignore"), "build/\n").unwrap();
+        git(&root, &["add", "."]);
+        git(&root, &["commit", "-q", "-m", "init"]);
+
+        fs::write(root.join("src/lib.rs"), "two").unwrap();
+        fs::write(root.join("src/extra.rs"), "").unwrap();
+        fs::remove_file(root.join("keep.txt")).unwrap();
+        fs::create_dir(root.join("build")).unwrap();
+        fs::write(root.join("build/out"), "").unwrap();
+
+        // Reading from a subdirectory finds the work tree root
+        let map = GitStatusMap::read(&root.join("src")).unwrap().unwrap();
+        let status = |p: &str, dir: bool| map.status_of(&root.join(p), dir);
+        assert_eq!(status("src/lib.rs", false), Some(GitStatus::Modified));
+        assert_eq!(status("src/extra.rs", false), Some(GitStatus::Untracked));
+        assert_eq!(status("keep.txt", false), Some(GitStatus::Deleted));
+        assert_eq!(status("build/out", false), Some(GitStatus::Ignored));
+        assert_eq!(status("src", true), Some(GitStatus::Modified));
+        // No upstream: nothing ahead or behind
+        let branch = map.branch().unwrap();
+        assert_eq!((branch.ahead, branch.behind), (0, 0));
+        assert_eq!((map.changed(), map.untracked()), (2, 1));
+
+        let outside = tempfile::tempdir().unwrap();
+        assert!(GitStatusMap::read(outside.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn tree_nodes_carry_status_across_patches() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+        let mut tree = FileTree::new(&root, false, 10).unwrap();
+        let status = b"? src/\0";
+        tree.set_git_status(Some(GitStatusMap::parse(&root, status)));
+
+        fs::write(root.join("src/new.rs"), "").unwrap();
+        tree.apply_changes([root.join("src/new.rs")]);
+        let marked: Vec<_> = tree
+            .nodes()
+            .iter()
+            .map(|n| (n.name.as_str(), n.git_status))
+            .collect();
+        assert!(marked.contains(&("new.rs", Some(GitStatus::Untracked))));
+        assert!(marked.contains(&("src", Some(GitStatus::Untracked))));
+
+        tree.set_git_status(None);
+        assert!(tree.nodes().iter().all(|n| n.git_status.is_none()));
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use super::{relink, symlink, FileNode, FileTree, TREE_IGNORE_FILE};
+
+/// Files whose change can alter which entries are shown anywhere below them.
+const IGNORE_FILES: &[&str] = &[".gitignore", ".ignore", TREE_IGNORE_FILE];
+
+/// What [`FileTree::apply_changes`] did with a batch of changed paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeOutcome {
+    /// No listing changed (content edits, or paths in directories never read)
+    Unchanged,
+    /// Affected directories were re-read and their children patched in place
+    Patched,
+    /// An ignore file changed; the whole tree needs a rescan
+    NeedsRescan,
+}
+
+impl FileTree {
+    /// Update the tree for a batch of created, removed or renamed paths.
+    ///
+    /// Only directories whose cached listing disagrees with the disk are re-read,
+    /// and only their children in `nodes` are replaced; unchanged child subtrees
+    /// are moved over as they are.
+    pub fn apply_changes<I>(&mut self, paths: I) -> ChangeOutcome
+    where
+        I: IntoIterator<Item = PathBuf>,
+    {
+        if self.snapshot.is_some() {
+            return ChangeOutcome::Unchanged;
+        }
+
+        let mut stale: BTreeSet<PathBuf> = BTreeSet::new();
+        for path in paths {
+            if path == self.root || !path.starts_with(&self.root) {
+                continue;
+            }
+            let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+                continue;
+            };
+            if IGNORE_FILES.iter().any(|f| name == *f) {
+                return ChangeOutcome::NeedsRescan;
+            }
+            if !self.show_hidden && name.to_string_lossy().starts_with('.') {
+                continue;
+            }
+            // Directories never read will be read fresh when expanded
+            let Some(entries) = self.listings.get(parent) else {
+                continue;
+            };
+            let listed = entries.iter().find(|e| e.path == path).map(|e| e.is_dir);
+            // Under time and size sorts a content edit can move the entry,
+            // and metadata columns show its new size and time
+            let reorders =
+                listed.is_some() && (self.sort.mode.needs_metadata() || self.collect_meta);
+            if reorders || listed != self.entry_kind(&path) {
+                stale.insert(parent.to_path_buf());
+            }
+        }
+        if stale.is_empty() {
+            return ChangeOutcome::Unchanged;
+        }
+
+        let selected = self.selected_node().map(|n| n.path.clone());
+        // Set when a patch could disagree with a rebuild: compacted chains may
+        // grow or split, and summarized listings depend on everything before them
+        let mut rebuild = self.filter.is_some();
+        // Deepest first, so a parent's patch moves over its children's patched subtrees
+        for dir in stale.iter().rev() {
+            let entries = if dir.is_dir() {
+                self.read_dir_entries(dir)
+            } else {
+                Vec::new()
+            };
+            let old_len = self.listings.get(dir).map_or(0, Vec::len);
+            rebuild |= self.patch_needs_rebuild(dir, old_len, entries.len());
+            let removed_dirs: Vec<PathBuf> = self
+                .listings
+                .get(dir)
+                .into_iter()
+                .flatten()
+                .filter(|old| old.is_dir && !entries.iter().any(|e| e.path == old.path && e.is_dir))
+                .map(|old| old.path.clone())
+                .collect();
+            self.listings.insert(dir.clone(), entries);
+            for gone in removed_dirs {
+                self.listings.retain(|path, _| !path.starts_with(&gone));
+            }
+            if !rebuild {
+                self.patch_children(dir);
+            }
+        }
+        // A filtered tree's rows depend on matches anywhere below; rebuild from the listings
+        // in that case too
+        if rebuild {
+            self.rebuild_visible_nodes();
+            return ChangeOutcome::Patched;
+        }
+        self.decorate_git();
+        self.decorate_sizes();
+        self.restore_selection(selected);
+        ChangeOutcome::Patched
+    }
+
+    /// Replace the visible children of `dir` from its cached listing.
+    fn patch_children(&mut self, dir: &Path) {
+        let Some(index) = self.nodes.iter().position(|n| n.path == dir) else {
+            return;
+        };
+        let depth = self.nodes[index].depth;
+        if !self.is_expanded(&self.nodes[index]) || depth >= self.max_depth {
+            return;
+        }
+        let end = self.nodes[index + 1..]
+            .iter()
+            .position(|n| n.depth <= depth)
+            .map_or(self.nodes.len(), |offset| index + 1 + offset);
+
+        // Existing child subtrees, keyed by the child's path (the first of a compacted row)
+        let mut groups: HashMap<PathBuf, Vec<FileNode>> = HashMap::new();
+        let mut current: Option<PathBuf> = None;
+        for node in self.nodes.drain(index + 1..end) {
+            if node.depth == depth + 1 {
+                let head = node.compacted.first().unwrap_or(&node.path).clone();
+                current = Some(head.clone());
+                groups.insert(head, vec![node]);
+            } else if let Some(group) = current.as_ref().and_then(|p| groups.get_mut(p)) {
+                group.push(node);
+            }
+        }
+
+        let entries = self.listings.get(dir).cloned().unwrap_or_default();
+        let mut subtree = vec![self.nodes[index].clone()];
+        for entry in &entries {
+            match groups.remove(&entry.path) {
+                Some(mut group) if group[0].is_dir == entry.is_dir => {
+                    // Same entry; its size or time may have changed
+                    if group[0].compacted.is_empty() {
+                        group[0].meta = entry.meta;
+                        group[0].symlink = entry.symlink.clone();
+                    }
+                    subtree.extend(group);
+                }
+                _ => {
+                    // New entry; connectors are filled in by relink below
+                    let rows = self.nodes.len() + subtree.len();
+                    let (node, tail) = self.entry_row(entry, depth + 1, false, Vec::new(), rows);
+                    subtree.push(node);
+                    self.push_expanded(&mut subtree, &tail, depth + 1, &[]);
+                }
+            }
+        }
+        relink(&mut subtree);
+        self.nodes.splice(index..index + 1, subtree);
+    }
+}
+
+impl FileTree {
+    /// Whether `path` exists and is a directory, as the tree would list it.
+    fn entry_kind(&self, path: &Path) -> Option<bool> {
+        let meta = std::fs::symlink_metadata(path).ok()?;
+        if meta.file_type().is_symlink() {
+            Some(symlink::inspect(path, self.follow_symlinks).1)
+        } else {
+            Some(meta.is_dir())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    type Shape = Vec<(PathBuf, usize, bool, bool, Vec<bool>)>;
+
+    fn shape(tree: &FileTree) -> Shape {
+        tree.nodes()
+            .iter()
+            .map(|n| {
+                let connector = n.connector.clone();
+                (n.path.clone(), n.depth, n.is_dir, n.is_last, connector)
+            })
+            .collect()
+    }
+
+    fn project() -> (tempfile::TempDir, PathBuf) {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        for dir in ["a/deep", "b", "c"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in ["a/x.rs", "a/deep/y.rs", "b/z.rs", "c/w.rs", "top.md"] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        (temp, root)
+    }
+
+    #[test]
+    fn incremental_matches_full_rebuild() {
+        let (_temp, root) = project();
+        let mut tree = FileTree::new(&root, false, 10).unwrap();
+
+        // Create, delete and rename at several levels, including a whole directory
+        fs::write(root.join("a/deep/new.rs"), "").unwrap();
+        fs::create_dir_all(root.join("d/inner")).unwrap();
+        fs::write(root.join("d/inner/v.rs"), "").unwrap();
+        fs::remove_dir_all(root.join("b")).unwrap();
+        fs::rename(root.join("c/w.rs"), root.join("c/a_first.rs")).unwrap();
+        fs::remove_file(root.join("top.md")).unwrap();
+        fs::write(root.join("a/x.rs"), "content only").unwrap();
+
+        let changed = [
+            "a/deep/new.rs",
+            "d",
+            "d/inner",
+            "d/inner/v.rs",
+            "b/z.rs",
+            "b",
+            "c/w.rs",
+            "c/a_first.rs",
+            "top.md",
+            "a/x.rs",
+        ];
+        let outcome = tree.apply_changes(changed.iter().map(|p| root.join(p)));
+        assert_eq!(outcome, ChangeOutcome::Patched);
+        assert!(!tree.listings.contains_key(&root.join("b")));
+
+        let full = FileTree::new(&root, false, 10).unwrap();
+        assert_eq!(shape(&tree), shape(&full));
+    }
+
+    #[test]
+    fn collapsed_directories_update_their_listing_only() {
+        let (_temp, root) = project();
+        let mut tree = FileTree::new(&root, false, 10).unwrap();
+        tree.set_expanded(&root.join("a"), false);
+        let before = shape(&tree);
+
+        fs::write(root.join("a/added.rs"), "").unwrap();
+        let outcome = tree.apply_changes([root.join("a/added.rs")]);
+        assert_eq!(outcome, ChangeOutcome::Patched);
+        assert_eq!(shape(&tree), before);
+
+        tree.set_expanded(&root.join("a"), true);
+        let full = FileTree::new(&root, false, 10).unwrap();
+        assert_eq!(shape(&tree), shape(&full));
+    }
+
+    #[test]
+    fn content_edits_and_ignore_files() {
+        let (_temp, root) = project();
+        let mut tree = FileTree::new(&root, false, 10).unwrap();
+        fs::write(root.join("c/w.rs"), "edit").unwrap();
+        assert_eq!(
+            tree.apply_changes([root.join("c/w.rs")]),
+            ChangeOutcome::Unchanged
+        );
+
+        fs::write(root.join(".gitignore"), "c/\n").unwrap();
+        assert_eq!(
+            tree.apply_changes([root.join(".gitignore")]),
+            ChangeOutcome::NeedsRescan
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use ignore::overrides::Override;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::{walk_builder, DirEntryInfo, FileTree};
+
+/// Size, modification time and permissions of a listed entry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EntryMeta {
+    pub modified: Option<SystemTime>,
+    /// File length. Directories get the total of the files below them once
+    /// [`DirSizes`] have been read; anything else that is not a regular file has none.
+    pub size: Option<u64>,
+    /// Unix permission bits.
+    pub mode: Option<u32>,
+}
+
+impl EntryMeta {
+    /// Stat `path`, following symlinks. Unreadable entries get empty metadata.
+    pub(super) fn read(path: &Path) -> Self {
+        match fs::metadata(path) {
+            Ok(meta) => Self {
+                modified: meta.modified().ok(),
+                size: meta.is_file().then_some(meta.len()),
+                mode: mode(&meta),
+            },
+            Err(_) => Self::default(),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn mode(meta: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(meta.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn mode(_meta: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+/// Stat the entries of a listing that have not been yet.
+pub(super) fn fill_meta(entries: &mut [DirEntryInfo]) {
+    for entry in entries.iter_mut().filter(|e| e.meta.is_none()) {
+        entry.meta = Some(EntryMeta::read(&entry.path));
+    }
+}
+
+/// Total size of the files below each directory of a tree, counting only
+/// entries the tree would list.
+#[derive(Debug, Clone, Default)]
+pub struct DirSizes {
+    root: PathBuf,
+    totals: HashMap<PathBuf, u64>,
+}
+
+impl DirSizes {
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn get(&self, dir: &Path) -> Option<u64> {
+        self.totals.get(dir).copied()
+    }
+}
+
+/// A walk of the whole tree that adds up directory sizes, prepared by
+/// [`FileTree::begin_size_scan`] to run off the UI thread.
+pub struct SizeScan {
+    root: PathBuf,
+    show_hidden: bool,
+    show_gitignored: bool,
+    overrides: Option<Override>,
+}
+
+impl SizeScan {
+    pub fn run(self) -> DirSizes {
+        let mut totals: HashMap<PathBuf, u64> = HashMap::new();
+        totals.insert(self.root.clone(), 0);
+        let walker = walk_builder(
+            &self.root,
+            self.show_hidden,
+            self.show_gitignored,
+            self.overrides.as_ref(),
+        )
+        .build();
+        for entry in walker.flatten() {
+            let Some(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                totals.entry(entry.path().to_path_buf()).or_insert(0);
+                continue;
+            }
+            // Links are not followed, so their targets are counted where they live
+            if !file_type.is_file() {
+                continue;
+            }
+            let Ok(len) = entry.metadata().map(|m| m.len()) else {
+                continue;
+            };
+            for dir in entry.path().ancestors().skip(1) {
+                *totals.entry(dir.to_path_buf()).or_insert(0) += len;
+                if dir == self.root {
+                    break;
+                }
+            }
+        }
+        DirSizes {
+            root: self.root,
+            totals,
+        }
+    }
+}
+
+impl FileTree {
+    /// Stat entries as they are read from now on, and the ones already read.
+    pub fn set_collect_meta(&mut self, collect: bool) {
+        if collect == self.collect_meta {
+            return;
+        }
+        self.collect_meta = collect;
+        if !collect || self.snapshot.is_some() {
+            return;
+        }
+        for entries in self.listings.values_mut() {
+            fill_meta(entries);
+        }
+        if !self.listings.is_empty() {
+            self.rebuild_visible_nodes();
+        }
+    }
+
+    /// Prepare a walk that totals the size of every directory.
+    pub fn begin_size_scan(&self) -> SizeScan {
+        SizeScan {
+            root: self.root.clone(),
+            show_hidden: self.show_hidden,
+            show_gitignored: self.show_gitignored,
+            overrides: self.overrides.clone(),
+        }
+    }
+
+    pub fn set_dir_sizes(&mut self, sizes: Option<DirSizes>) {
+        self.dir_sizes = sizes;
+        self.decorate_sizes();
+    }
+
+    pub(super) fn decorate_sizes(&mut self) {
+        let Some(sizes) = &self.dir_sizes else {
+            return;
+        };
+        for node in self.nodes.iter_mut().filter(|n| n.is_dir) {
+            if let Some(size) = sizes.get(&node.path) {
+                node.meta.get_or_insert_with(EntryMeta::default).size = Some(size);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(tree: &FileTree, name: &str) -> Option<EntryMeta> {
+        tree.nodes().iter().find(|n| n.name == name)?.meta
+    }
+
+    #[test]
+    fn metadata_is_read_with_the_listing_when_asked() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+
+        let mut tree = FileTree::new(&root, false, 10).unwrap();
+        assert_eq!(meta(&tree, "main.rs"), None);
+        tree.set_collect_meta(true);
+        let main = meta(&tree, "main.rs").unwrap();
+        assert_eq!(main.size, Some(12));
+        assert!(main.modified.is_some());
+        #[cfg(unix)]
+        assert_eq!(main.mode.map(|m| m & 0o400), Some(0o400));
+
+        // Content edits re-read the listing so the columns stay current
+        fs::write(root.join("src/main.rs"), "fn main() { run() }").unwrap();
+        tree.apply_changes([root.join("src/main.rs")]);
+        assert_eq!(meta(&tree, "main.rs").unwrap().size, Some(19));
+    }
+
+    #[test]
+    fn directory_sizes_add_up_listed_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::write(root.join("a/one"), "1").unwrap();
+        fs::write(root.join("a/b/two"), "22").unwrap();
+        fs::write(root.join("top"), "333").unwrap();
+        fs::write(root.join(".hidden"), "4444").unwrap();
+
+        let mut tree = FileTree::new(&root, false, 10).unwrap();
+        let sizes = tree.begin_size_scan().run();
+        assert_eq!(sizes.get(&root), Some(6));
+        assert_eq!(sizes.get(&root.join("a")), Some(3));
+        assert_eq!(sizes.get(&root.join("a/b")), Some(2));
+
+        tree.set_dir_sizes(Some(sizes));
+        assert_eq!(meta(&tree, "a").unwrap().size, Some(3));
+        assert_eq!(tree.nodes()[0].meta.unwrap().size, Some(6));
+        // Rebuilds keep the totals
+        tree.refresh();
+        assert_eq!(meta(&tree, "b").unwrap().size, Some(2));
+    }
+}
@@ -1,32 +1,245 @@
+mod exclude;
 mod file_node;
+mod filter;
+mod git;
+mod incremental;
+mod meta;
+mod scan;
+mod sort;
+mod summary;
+mod symlink;
 
+pub use exclude::{ExcludeRules, PathFilter, TREE_IGNORE_FILE};
 pub use file_node::FileNode;
+pub use filter::TreeFilter;
+pub use git::{BranchInfo, GitStatus, GitStatusMap};
+pub use incremental::ChangeOutcome;
+pub use meta::{DirSizes, EntryMeta, SizeScan};
+pub use scan::{Scan, ScanControl, ScanResult};
+pub use sort::{SortMode, SortOrder};
+pub use summary::{DEFAULT_CHILD_LIMIT, DEFAULT_NODE_BUDGET};
+pub use symlink::Symlink;
 
 use anyhow::Result;
+use ignore::overrides::Override;
 use ignore::WalkBuilder;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 pub struct FileTree {
     root: PathBuf,
     nodes: Vec<FileNode>,
     pub show_hidden: bool,
+    /// List gitignored entries too (the UI greys them out) instead of hiding them.
+    pub show_gitignored: bool,
+    /// Walk into symlinked directories. Links that loop back above themselves are never walked.
+    pub follow_symlinks: bool,
+    /// Directories with more children show this many and a `… N more files` row.
+    pub child_limit: Option<usize>,
+    /// Directories read once this many rows are shown are summarized too.
+    pub node_budget: Option<usize>,
+    /// Merge chains of directories that only contain one directory into one row.
+    pub compact_dirs: bool,
     max_depth: usize,
+    sort: SortOrder,
+    excludes: ExcludeRules,
+    // `excludes` compiled for the current root
+    overrides: Option<Override>,
     offset: usize,
+    // Directories toggled away from `expand_default`, kept across rebuilds
+    expansion: HashMap<PathBuf, bool>,
+    expand_default: bool,
+    // Bumped by expand/collapse-all so in-flight scans don't resurrect old state
+    expansion_epoch: u64,
+    // Children shown in summarized directories after asking for more
+    revealed: HashMap<PathBuf, usize>,
+    selected: usize,
+    // Full node list of a recorded tree; rebuilds filter it instead of reading the disk
+    snapshot: Option<Vec<FileNode>>,
+    // Sorted children of every directory read so far
+    listings: HashMap<PathBuf, Vec<DirEntryInfo>>,
+    // While set, directories are only expanded by default until this many nodes are shown
+    auto_expand_budget: Option<usize>,
+    // Set on the worker-side copy of a tree during a background scan
+    scan_control: Option<Arc<ScanControl>>,
+    // Latest `git status` of the repository containing the root, if any
+    git: Option<GitStatusMap>,
+    // While set, only matches and their ancestors are shown, from a fully expanded tree
+    filter: Option<TreeFilter>,
+    filter_hidden: usize,
+    // Collapsed directories shown open because a match is below them
+    forced_open: HashSet<PathBuf>,
+    // Stat entries as they are read, for metadata columns
+    collect_meta: bool,
+    // Latest totals from a size scan, shown on directory nodes
+    dir_sizes: Option<DirSizes>,
+}
+
+/// Directories are expanded by default until the tree shows this many rows;
+/// past that they start collapsed so big repositories are not read in full.
+pub const AUTO_EXPAND_NODES: usize = 2000;
+
+/// A directory entry as read from disk.
+#[derive(Debug, Clone)]
+struct DirEntryInfo {
+    path: PathBuf,
+    name: String,
+    // False for symlinks that are not walked
+    is_dir: bool,
+    symlink: Option<Symlink>,
+    // Read on demand, by sort modes and metadata columns that need it
+    meta: Option<EntryMeta>,
+}
+
+impl DirEntryInfo {
+    fn node(&self, depth: usize, is_last: bool, connector: Vec<bool>) -> FileNode {
+        let mut node = FileNode::new(
+            self.path.clone(),
+            self.name.clone(),
+            depth,
+            self.is_dir,
+            is_last,
+            connector,
+        );
+        node.symlink = self.symlink.clone();
+        node.meta = self.meta;
+        node
+    }
 }
 
 impl FileTree {
     pub fn new(root: &Path, show_hidden: bool, max_depth: usize) -> Result<Self> {
+        let mut tree = Self::unscanned(root, show_hidden, max_depth);
+        tree.rebuild_visible_nodes();
+        Ok(tree)
+    }
+
+    /// A tree showing only its root until a [`Scan`] fills it in.
+    pub fn unscanned(root: &Path, show_hidden: bool, max_depth: usize) -> Self {
         let mut tree = Self {
             root: root.to_path_buf(),
             nodes: Vec::new(),
             show_hidden,
+            show_gitignored: false,
+            follow_symlinks: true,
+            child_limit: Some(DEFAULT_CHILD_LIMIT),
+            node_budget: Some(DEFAULT_NODE_BUDGET),
+            compact_dirs: false,
             max_depth,
+            sort: SortOrder::default(),
+            excludes: ExcludeRules::default(),
+            overrides: None,
             offset: 0,
+            expansion: HashMap::new(),
+            expand_default: true,
+            expansion_epoch: 0,
+            revealed: HashMap::new(),
+            selected: 0,
+            snapshot: None,
+            listings: HashMap::new(),
+            auto_expand_budget: Some(AUTO_EXPAND_NODES),
+            scan_control: None,
+            git: None,
+            filter: None,
+            filter_hidden: 0,
+            forced_open: HashSet::new(),
+            collect_meta: false,
+            dir_sizes: None,
         };
+        tree.nodes.push(tree.root_node());
+        tree
+    }
 
-        tree.rebuild_visible_nodes()?;
+    /// Build a tree from recorded entries instead of the filesystem.
+    /// `entries` are paths relative to `root` in display order; directories end with `/`.
+    pub fn from_snapshot(root: &Path, entries: &[String]) -> Self {
+        let root_name = root
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| root.to_string_lossy().to_string());
+        let mut nodes = vec![FileNode::new(
+            root.to_path_buf(),
+            root_name,
+            0,
+            true,
+            true,
+            vec![],
+        )];
+        for entry in entries {
+            let is_dir = entry.ends_with('/');
+            let rel = Path::new(entry.trim_end_matches('/'));
+            let depth = rel.components().count();
+            if depth == 0 {
+                continue;
+            }
+            let name = rel
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            nodes.push(FileNode::new(
+                root.join(rel),
+                name,
+                depth,
+                is_dir,
+                false,
+                vec![],
+            ));
+        }
+        relink(&mut nodes);
 
-        Ok(tree)
+        Self {
+            root: root.to_path_buf(),
+            nodes: nodes.clone(),
+            show_hidden: false,
+            show_gitignored: false,
+            follow_symlinks: false,
+            child_limit: None,
+            node_budget: None,
+            compact_dirs: false,
+            max_depth: usize::MAX,
+            sort: SortOrder::default(),
+            excludes: ExcludeRules::default(),
+            overrides: None,
+            offset: 0,
+            expansion: HashMap::new(),
+            expand_default: true,
+            expansion_epoch: 0,
+            revealed: HashMap::new(),
+            selected: 0,
+            snapshot: Some(nodes),
+            listings: HashMap::new(),
+            auto_expand_budget: None,
+            scan_control: None,
+            git: None,
+            filter: None,
+            filter_hidden: 0,
+            forced_open: HashSet::new(),
+            collect_meta: false,
+            dir_sizes: None,
+        }
+    }
+
+    /// Entries relative to the root in display order, as stored by [`FileTree::from_snapshot`].
+    pub fn snapshot(&self) -> Vec<String> {
+        let relative = |path: &Path, is_dir: bool| {
+            let rel = path.strip_prefix(&self.root).ok()?;
+            let mut s = rel.to_string_lossy().to_string();
+            if is_dir {
+                s.push('/');
+            }
+            Some(s)
+        };
+        // Compacted rows are listed as the directories they merge
+        self.nodes
+            .iter()
+            .filter(|n| n.depth > 0 && n.more.is_none())
+            .flat_map(|n| {
+                let merged = n.compacted.iter().map(|dir| (dir.as_path(), true));
+                merged.chain([(n.path.as_path(), n.is_dir)])
+            })
+            .filter_map(|(path, is_dir)| relative(path, is_dir))
+            .collect()
     }
 
     pub fn root_path(&self) -> &Path {
@@ -45,108 +258,627 @@ impl FileTree {
         self.offset = offset;
     }
 
-    fn rebuild_visible_nodes(&mut self) -> Result<()> {
-        self.nodes.clear();
-        self.build_tree(&self.root.clone(), 0, &[])?;
-        Ok(())
+    /// Whether a directory's children are shown. The root is always expanded.
+    pub fn is_expanded(&self, node: &FileNode) -> bool {
+        node.is_dir
+            && (node.depth == 0
+                || self.is_path_expanded(&node.path)
+                || self.forced_open.contains(&node.path))
     }
 
-    fn build_tree(&mut self, path: &Path, depth: usize, connector: &[bool]) -> Result<()> {
-        if depth > self.max_depth {
-            return Ok(());
+    fn is_path_expanded(&self, path: &Path) -> bool {
+        path == self.root
+            || self
+                .expansion
+                .get(path)
+                .copied()
+                .unwrap_or(self.expand_default)
+    }
+
+    pub fn set_expanded(&mut self, path: &Path, expanded: bool) {
+        if self.is_path_expanded(path) == expanded {
+            return;
         }
+        self.expansion.insert(path.to_path_buf(), expanded);
+        self.rebuild_visible_nodes();
+    }
 
-        if depth == 0 {
-            // Root node
-            let is_dir = path.is_dir();
-            let name = path
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_else(|| path.to_string_lossy().to_string());
+    /// Expand every collapsed ancestor of `path` so it shows up in the tree.
+    pub fn reveal(&mut self, path: &Path) {
+        let mut changed = false;
+        for dir in path.ancestors().skip(1) {
+            if dir == self.root || !dir.starts_with(&self.root) {
+                break;
+            }
+            if !self.is_path_expanded(dir) {
+                self.expansion.insert(dir.to_path_buf(), true);
+                changed = true;
+            }
+        }
+        if changed {
+            self.rebuild_visible_nodes();
+        }
+    }
 
-            let node = FileNode::new(path.to_path_buf(), name, 0, is_dir, true, vec![]);
-            self.nodes.push(node);
+    /// Toggle the directory at `index` in `nodes`, or show more of a summarized
+    /// listing at its `… N more files` row. Returns false for files and the root.
+    pub fn toggle(&mut self, index: usize) -> bool {
+        let Some(node) = self.nodes.get(index) else {
+            return false;
+        };
+        if node.more.is_some() {
+            return self.show_more(index);
+        }
+        if !node.is_dir || node.depth == 0 {
+            return false;
+        }
+        let path = node.path.clone();
+        let expanded = self.is_expanded(node);
+        self.set_expanded(&path, !expanded);
+        true
+    }
 
-            if is_dir {
-                self.build_tree(path, depth + 1, &[])?;
-            }
-            return Ok(());
+    pub fn expand_all(&mut self) {
+        self.expand_default = true;
+        self.auto_expand_budget = None;
+        self.expansion.clear();
+        self.expansion_epoch += 1;
+        self.rebuild_visible_nodes();
+    }
+
+    pub fn collapse_all(&mut self) {
+        self.expand_default = false;
+        self.expansion.clear();
+        self.expansion_epoch += 1;
+        self.rebuild_visible_nodes();
+    }
+
+    /// Index of the keyboard cursor in `nodes`.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_node(&self) -> Option<&FileNode> {
+        self.nodes.get(self.selected)
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.nodes.len().saturating_sub(1));
+    }
+
+    /// Move the cursor to the parent directory of the selected node.
+    pub fn select_parent(&mut self) {
+        let Some(depth) = self.selected_node().map(|n| n.depth) else {
+            return;
+        };
+        if let Some(parent) = self.nodes[..self.selected]
+            .iter()
+            .rposition(|n| n.depth < depth)
+        {
+            self.selected = parent;
         }
+    }
 
-        let walker = WalkBuilder::new(path)
-            .hidden(!self.show_hidden)
-            .git_ignore(true)
-            .git_global(true)
-            .git_exclude(true)
-            .max_depth(Some(1))
-            .build();
+    /// Adjust the scroll offset so the cursor is within `height` visible rows.
+    pub fn scroll_to_selected(&mut self, height: usize) {
+        let height = height.max(1);
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + height {
+            self.offset = self.selected + 1 - height;
+        }
+    }
 
-        // Collect children (skip the directory itself)
-        let mut children: Vec<_> = walker
-            .flatten()
-            .filter(|entry| entry.path() != path)
-            .filter(|entry| {
-                if self.show_hidden {
-                    return true;
+    fn rebuild_visible_nodes(&mut self) {
+        let selected_path = self.selected_node().map(|n| n.path.clone());
+        self.nodes.clear();
+        match self.snapshot.take() {
+            Some(all) if self.filter.is_some() => {
+                self.nodes = self.filter_nodes(all.clone());
+                self.snapshot = Some(all);
+            }
+            Some(all) => {
+                self.nodes = self.visible_snapshot_nodes(&all);
+                self.snapshot = Some(all);
+            }
+            None => {
+                self.build_tree();
+                if self.filter.is_some() {
+                    let all = std::mem::take(&mut self.nodes);
+                    self.nodes = self.filter_nodes(all);
                 }
-                let name = entry.file_name().to_string_lossy();
-                !name.starts_with('.')
+            }
+        }
+        self.decorate_git();
+        self.decorate_sizes();
+        self.restore_selection(selected_path);
+    }
+
+    /// Keep the cursor on the same entry, or the closest still-visible ancestor.
+    fn restore_selection(&mut self, selected_path: Option<PathBuf>) {
+        self.selected = selected_path
+            .and_then(|path| {
+                path.ancestors()
+                    .find_map(|p| self.nodes.iter().position(|n| n.path == p))
             })
-            .collect();
+            .unwrap_or(0)
+            .min(self.nodes.len().saturating_sub(1));
+    }
 
-        children.sort_by(|a, b| {
-            let a_is_dir = a.path().is_dir();
-            let b_is_dir = b.path().is_dir();
-            match (a_is_dir, b_is_dir) {
-                (true, false) => std::cmp::Ordering::Less,
-                (false, true) => std::cmp::Ordering::Greater,
-                _ => {
-                    let a_name = a.file_name().to_string_lossy().to_lowercase();
-                    let b_name = b.file_name().to_string_lossy().to_lowercase();
-                    a_name.cmp(&b_name)
+    /// Drop the subtrees of collapsed directories. Whole subtrees go, so the
+    /// remaining nodes keep valid `is_last`/`connector` values.
+    fn visible_snapshot_nodes(&self, all: &[FileNode]) -> Vec<FileNode> {
+        let mut visible = Vec::with_capacity(all.len());
+        let mut hidden_below: Option<usize> = None;
+        for node in all {
+            if let Some(depth) = hidden_below {
+                if node.depth > depth {
+                    continue;
                 }
+                hidden_below = None;
+            }
+            if node.is_dir && !self.is_path_expanded(&node.path) {
+                hidden_below = Some(node.depth);
             }
-        });
+            visible.push(node.clone());
+        }
+        visible
+    }
 
-        let total = children.len();
-        for (i, entry) in children.into_iter().enumerate() {
-            let entry_path = entry.path().to_path_buf();
-            let is_dir = entry_path.is_dir();
-            let name = entry_path
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_else(|| entry_path.to_string_lossy().to_string());
+    fn root_node(&self) -> FileNode {
+        let name = self
+            .root
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.root.to_string_lossy().to_string());
+        FileNode::new(self.root.clone(), name, 0, self.root.is_dir(), true, vec![])
+    }
 
-            let is_last = i == total - 1;
+    fn build_tree(&mut self) {
+        let root = self.root_node();
+        let is_dir = root.is_dir;
+        let mut nodes = vec![root];
+        if is_dir && self.max_depth > 0 {
+            self.push_children(&mut nodes, &self.root.clone(), 1, &[]);
+        }
+        self.nodes = nodes;
+    }
 
-            let node = FileNode::new(
-                entry_path.clone(),
-                name,
+    /// Append the children of `dir` to `out`, recursing into the expanded ones.
+    /// Only directories that end up visible are read from disk.
+    fn push_children(
+        &mut self,
+        out: &mut Vec<FileNode>,
+        dir: &Path,
+        depth: usize,
+        connector: &[bool],
+    ) {
+        if let Some(control) = &self.scan_control {
+            if control.is_cancelled() {
+                return;
+            }
+        }
+        self.ensure_listing(dir);
+        let entries = self.listings.remove(dir).unwrap_or_default();
+
+        let total = entries.len();
+        let shown = self.children_shown(dir, total, self.nodes.len() + out.len());
+        for (i, entry) in entries[..shown].iter().enumerate() {
+            let is_last = i == total - 1;
+            let rows = self.nodes.len() + out.len();
+            let (node, tail) = self.entry_row(entry, depth, is_last, connector.to_vec(), rows);
+            out.push(node);
+            self.push_expanded(out, &tail, depth, connector);
+        }
+        if shown < total {
+            out.push(summary::placeholder(
+                dir,
+                total - shown,
                 depth,
-                is_dir,
-                is_last,
                 connector.to_vec(),
-            );
-            self.nodes.push(node);
+            ));
+        }
 
-            // Recurse into directories with updated connector
-            if is_dir {
-                let mut child_connector = connector.to_vec();
-                child_connector.push(is_last);
-                self.build_tree(&entry_path, depth + 1, &child_connector)?;
+        self.listings.insert(dir.to_path_buf(), entries);
+    }
+
+    /// Read `dir` unless its listing is cached.
+    fn ensure_listing(&mut self, dir: &Path) {
+        if self.listings.contains_key(dir) {
+            return;
+        }
+        let entries = self.read_dir_entries(dir);
+        if let Some(control) = &self.scan_control {
+            control.add_entries(entries.len());
+        }
+        self.listings.insert(dir.to_path_buf(), entries);
+    }
+
+    /// If `entry` (just pushed to `out`) is an expanded directory, append its children.
+    fn push_expanded(
+        &mut self,
+        out: &mut Vec<FileNode>,
+        entry: &DirEntryInfo,
+        depth: usize,
+        connector: &[bool],
+    ) {
+        let shown = self.nodes.len() + out.len();
+        if entry.is_dir && depth < self.max_depth && self.should_expand(&entry.path, shown) {
+            let is_last = out.last().is_some_and(|n| n.is_last);
+            let mut child_connector = connector.to_vec();
+            child_connector.push(is_last);
+            self.push_children(out, &entry.path, depth + 1, &child_connector);
+        }
+    }
+
+    /// Decide whether to show a directory's children while building, given how
+    /// many rows are shown so far. Defaults chosen under the auto-expand budget
+    /// are pinned, so the shape doesn't shift on later refreshes as files come and go.
+    fn should_expand(&mut self, path: &Path, shown: usize) -> bool {
+        // Filtering looks at everything; collapsed state is applied afterwards
+        if self.filter.is_some() {
+            return true;
+        }
+        if let Some(&expanded) = self.expansion.get(path) {
+            return expanded;
+        }
+        match self.auto_expand_budget {
+            Some(budget) if self.expand_default => {
+                let expanded = shown < budget;
+                self.expansion.insert(path.to_path_buf(), expanded);
+                expanded
             }
+            _ => self.expand_default,
         }
+    }
 
-        Ok(())
+    /// Whether `path` is in its parent's cached listing, and if so whether it is a directory.
+    pub fn listed_kind(&self, path: &Path) -> Option<bool> {
+        let entries = self.listings.get(path.parent()?)?;
+        entries.iter().find(|e| e.path == path).map(|e| e.is_dir)
+    }
+
+    /// Whether `dir` has been read, so that entries missing from its listing were really absent.
+    pub fn has_listing(&self, dir: &Path) -> bool {
+        self.listings.contains_key(dir)
     }
 
     pub fn set_root(&mut self, new_root: PathBuf) {
+        self.set_root_unscanned(new_root);
+        self.rebuild_visible_nodes();
+    }
+
+    /// Switch roots without reading anything; only the root node is shown until a scan.
+    pub fn set_root_unscanned(&mut self, new_root: PathBuf) {
         self.root = new_root;
+        self.overrides = self.compile_excludes();
         self.offset = 0;
-        let _ = self.rebuild_visible_nodes();
+        self.selected = 0;
+        self.listings.clear();
+        self.revealed.clear();
+        self.dir_sizes = None;
+        self.nodes = vec![self.root_node()];
+    }
+
+    /// Hide entries matching `--exclude` globs, or not matching `--include` ones.
+    /// Takes effect on the next refresh or scan.
+    pub fn set_excludes(&mut self, excludes: ExcludeRules) -> Result<()> {
+        excludes.overrides(&self.root)?;
+        self.excludes = excludes;
+        self.overrides = self.compile_excludes();
+        Ok(())
+    }
+
+    fn compile_excludes(&self) -> Option<Override> {
+        if self.excludes.is_empty() {
+            return None;
+        }
+        self.excludes.overrides(&self.root).ok()
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Change how deep directories are walked. Takes effect on the next refresh or scan.
+    pub fn set_max_depth(&mut self, depth: usize) {
+        self.max_depth = depth;
+    }
+
+    pub fn sort(&self) -> SortOrder {
+        self.sort
+    }
+
+    /// Reorder every directory read so far. Recorded trees keep their recorded order.
+    pub fn set_sort(&mut self, order: SortOrder) {
+        if order == self.sort {
+            return;
+        }
+        self.sort = order;
+        if self.snapshot.is_some() {
+            return;
+        }
+        for entries in self.listings.values_mut() {
+            sort::sort_entries(entries, order);
+        }
+        // An unscanned tree is filled in by its scan, which sorts as it reads
+        if !self.listings.is_empty() {
+            self.rebuild_visible_nodes();
+        }
     }
 
     pub fn refresh(&mut self) {
-        let _ = self.rebuild_visible_nodes();
+        self.listings.clear();
+        self.rebuild_visible_nodes();
+    }
+}
+
+impl FileTree {
+    /// Read one directory's children in the tree's sort order, leaving out
+    /// ignored and excluded entries.
+    fn read_dir_entries(&self, dir: &Path) -> Vec<DirEntryInfo> {
+        let show_hidden = self.show_hidden;
+        let follow = self.follow_symlinks;
+        let mut builder = walk_builder(
+            dir,
+            show_hidden,
+            self.show_gitignored,
+            self.overrides.as_ref(),
+        );
+        builder.max_depth(Some(1));
+
+        let mut entries: Vec<DirEntryInfo> = builder
+            .build()
+            .flatten()
+            // Depth 0 is the directory itself
+            .filter(|entry| entry.depth() > 0)
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if !show_hidden && name.starts_with('.') {
+                    return None;
+                }
+                // The walker already knows the type; only symlinks need a stat to follow them
+                let (is_dir, symlink) = match entry.file_type() {
+                    Some(ft) if ft.is_symlink() => {
+                        let (link, is_dir) = symlink::inspect(entry.path(), follow);
+                        (is_dir, Some(link))
+                    }
+                    Some(ft) => (ft.is_dir(), None),
+                    None => (false, None),
+                };
+                Some(DirEntryInfo {
+                    path: entry.into_path(),
+                    name,
+                    is_dir,
+                    symlink,
+                    meta: None,
+                })
+            })
+            .collect();
+
+        if self.collect_meta {
+            meta::fill_meta(&mut entries);
+        }
+        sort::sort_entries(&mut entries, self.sort);
+        entries
+    }
+}
+
+/// A walker over `dir` that leaves out what the tree hides: hidden files unless
+/// shown, gitignored ones unless listed, `.cltreeignore` matches and excludes.
+fn walk_builder(
+    dir: &Path,
+    show_hidden: bool,
+    show_gitignored: bool,
+    overrides: Option<&Override>,
+) -> WalkBuilder {
+    let git_rules = !show_gitignored;
+    let mut builder = WalkBuilder::new(dir);
+    builder
+        .hidden(!show_hidden)
+        .git_ignore(git_rules)
+        .git_global(git_rules)
+        .git_exclude(git_rules)
+        .add_custom_ignore_filename(TREE_IGNORE_FILE);
+    if let Some(overrides) = overrides {
+        builder.overrides(overrides.clone());
+    }
+    builder
+}
+
+/// Recompute `is_last` and `connector` below `nodes[0]`, for a subtree listed in
+/// display order (each node's descendants directly follow it). The first node's
+/// own `is_last` and `connector` are taken as given.
+fn relink(nodes: &mut [FileNode]) {
+    let Some((anchor, rest)) = nodes.split_first_mut() else {
+        return;
+    };
+    let base = anchor.depth + 1;
+
+    // Walk backwards: a node is last if no sibling follows before its parent's subtree ends
+    let mut seen_at_depth: Vec<bool> = Vec::new();
+    for node in rest.iter_mut().rev() {
+        let level = node.depth - base;
+        if seen_at_depth.len() <= level {
+            seen_at_depth.resize(level + 1, false);
+        }
+        node.is_last = !seen_at_depth[level];
+        seen_at_depth[level] = true;
+        // Deeper levels belong to a different parent from here upwards
+        seen_at_depth.truncate(level + 1);
+    }
+
+    // The root's own line is never drawn, so it contributes no connector column
+    let mut connector = anchor.connector.clone();
+    if anchor.depth > 0 {
+        connector.push(anchor.is_last);
+    }
+    let anchor_len = connector.len();
+    for node in rest.iter_mut() {
+        connector.truncate((node.depth - 1).max(anchor_len));
+        node.connector = connector.clone();
+        connector.push(node.is_last);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn snapshot_round_trip_matches_filesystem_tree() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("src/ui")).unwrap();
+        fs::create_dir(root.join("tests")).unwrap();
+        fs::write(root.join("src/ui/mod.rs"), "").unwrap();
+        fs::write(root.join("src/main.rs"), "").unwrap();
+        fs::write(root.join("tests/a.rs"), "").unwrap();
+        fs::write(root.join("README.md"), "").unwrap();
+
+        let tree = FileTree::new(&root, false, 10).unwrap();
+        let restored = FileTree::from_snapshot(&root, &tree.snapshot());
+
+        let shape = |t: &FileTree| -> Vec<(PathBuf, usize, bool, Vec<bool>)> {
+            t.nodes()
+                .iter()
+                .map(|n| (n.path.clone(), n.depth, n.is_last, n.connector.clone()))
+                .collect()
+        };
+        assert_eq!(shape(&restored), shape(&tree));
+    }
+
+    fn names(tree: &FileTree) -> Vec<&str> {
+        tree.nodes().iter().map(|n| n.name.as_str()).collect()
+    }
+
+    #[test]
+    fn expansion_state_survives_refresh() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("a/inner")).unwrap();
+        fs::create_dir(root.join("b")).unwrap();
+        fs::write(root.join("a/inner/x.rs"), "").unwrap();
+        fs::write(root.join("b/y.rs"), "").unwrap();
+
+        let mut tree = FileTree::new(&root, false, 10).unwrap();
+        assert_eq!(names(&tree)[1..], ["a", "inner", "x.rs", "b", "y.rs"]);
+
+        // Collapse `a` with the cursor inside it: the cursor moves up to `a`
+        tree.select(3);
+        tree.set_expanded(&root.join("a"), false);
+        assert_eq!(names(&tree)[1..], ["a", "b", "y.rs"]);
+        assert_eq!(tree.selected_node().unwrap().name, "a");
+        assert!(!tree.nodes()[1].is_last && tree.nodes()[2].is_last);
+
+        fs::write(root.join("a/new.rs"), "").unwrap();
+        tree.refresh();
+        assert_eq!(names(&tree)[1..], ["a", "b", "y.rs"]);
+
+        tree.collapse_all();
+        assert_eq!(names(&tree)[1..], ["a", "b"]);
+        assert!(tree.toggle(2));
+        assert_eq!(names(&tree)[1..], ["a", "b", "y.rs"]);
+        tree.expand_all();
+        assert_eq!(
+            names(&tree)[1..],
+            ["a", "inner", "x.rs", "new.rs", "b", "y.rs"]
+        );
+        // The root and files cannot be toggled
+        assert!(!tree.toggle(0));
+        assert!(!tree.toggle(3));
+    }
+
+    #[test]
+    fn snapshot_trees_collapse_without_touching_disk() {
+        let root = PathBuf::from("/nonexistent/demo");
+        let entries: Vec<String> = [
+            "src/",
+            "src/ui/",
+            "src/ui/mod.rs",
+            "src/main.rs",
+            "README.md",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let mut tree = FileTree::from_snapshot(&root, &entries);
+        tree.set_expanded(&root.join("src/ui"), false);
+        assert_eq!(names(&tree)[1..], ["src", "ui", "main.rs", "README.md"]);
+        tree.select(3);
+        tree.select_parent();
+        assert_eq!(tree.selected_node().unwrap().name, "src");
+        tree.set_expanded(&root.join("src/ui"), true);
+        assert_eq!(tree.snapshot(), entries);
+    }
+
+    #[test]
+    fn only_visible_directories_are_read() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        for d in 0..4 {
+            fs::create_dir(root.join(format!("d{d}"))).unwrap();
+            for f in 0..3 {
+                fs::write(root.join(format!("d{d}/f{f}")), "").unwrap();
+            }
+        }
+
+        let mut tree = FileTree::new(&root, false, 10).unwrap();
+        assert_eq!(tree.nodes().len(), 1 + 4 * 4);
+        assert_eq!(tree.listings.len(), 5);
+
+        // With a small budget only the first directory is expanded and read
+        tree.auto_expand_budget = Some(4);
+        tree.expansion.clear();
+        tree.refresh();
+        assert_eq!(
+            names(&tree)[1..],
+            ["d0", "f0", "f1", "f2", "d1", "d2", "d3"]
+        );
+        assert_eq!(tree.listings.len(), 2);
+
+        // Expanding reads just that directory; the others come from the cache
+        assert!(tree.toggle(6));
+        assert_eq!(tree.listings.len(), 3);
+        assert_eq!(tree.nodes().len(), 11);
+
+        // Pinned defaults keep their shape across refreshes
+        fs::write(root.join("d0/f3"), "").unwrap();
+        tree.refresh();
+        assert_eq!(tree.nodes().len(), 12);
+        assert!(!tree.is_expanded(&tree.nodes()[6]));
+
+        tree.reveal(&root.join("d3/f1"));
+        assert!(tree.nodes().iter().any(|n| n.path == root.join("d3/f1")));
+    }
+
+    #[test]
+    fn sort_order_reorders_listings_and_follows_edits() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir(root.join("dir")).unwrap();
+        fs::write(root.join("file10"), "x").unwrap();
+        fs::write(root.join("file2"), "xxx").unwrap();
+        fs::write(root.join("file1"), "xx").unwrap();
+
+        let mut tree = FileTree::new(&root, false, 10).unwrap();
+        assert_eq!(names(&tree)[1..], ["dir", "file1", "file10", "file2"]);
+
+        let mut order = SortOrder {
+            mode: SortMode::Natural,
+            directories_first: false,
+        };
+        tree.set_sort(order);
+        assert_eq!(names(&tree)[1..], ["dir", "file1", "file2", "file10"]);
+
+        order.mode = SortMode::Size;
+        tree.set_sort(order);
+        assert_eq!(names(&tree)[1..], ["file2", "file1", "file10", "dir"]);
+
+        // A content edit moves the file under a size sort
+        fs::write(root.join("file10"), "xxxxxx").unwrap();
+        tree.apply_changes([root.join("file10")]);
+        assert_eq!(names(&tree)[1..], ["file10", "file2", "file1", "dir"]);
     }
 }
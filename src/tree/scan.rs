@@ -0,0 +1,187 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use super::meta::fill_meta;
+use super::sort::sort_entries;
+use super::{DirEntryInfo, FileTree, SortOrder};
+
+/// Shared between a running scan and the UI: cancellation and progress.
+#[derive(Debug, Default)]
+pub struct ScanControl {
+    cancelled: AtomicBool,
+    entries: AtomicUsize,
+}
+
+impl ScanControl {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Directory entries read so far.
+    pub fn entries(&self) -> usize {
+        self.entries.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn add_entries(&self, n: usize) {
+        self.entries.fetch_add(n, Ordering::Relaxed);
+    }
+}
+
+/// A tree build prepared on the UI thread, to be run on a worker.
+pub struct Scan {
+    tree: FileTree,
+    control: Arc<ScanControl>,
+    epoch: u64,
+}
+
+/// Directory listings read by a [`Scan`], ready to merge back into the tree.
+#[derive(Debug)]
+pub struct ScanResult {
+    root: PathBuf,
+    listings: HashMap<PathBuf, Vec<DirEntryInfo>>,
+    expansion: HashMap<PathBuf, bool>,
+    sort: SortOrder,
+    epoch: u64,
+}
+
+impl Scan {
+    pub fn control(&self) -> Arc<ScanControl> {
+        Arc::clone(&self.control)
+    }
+
+    /// Read every directory the tree will show. Returns None if cancelled.
+    pub fn run(mut self) -> Option<ScanResult> {
+        self.tree.rebuild_visible_nodes();
+        if self.control.is_cancelled() {
+            return None;
+        }
+        Some(ScanResult {
+            root: self.tree.root,
+            listings: self.tree.listings,
+            expansion: self.tree.expansion,
+            sort: self.tree.sort,
+            epoch: self.epoch,
+        })
+    }
+}
+
+impl FileTree {
+    /// Prepare a background build that re-reads every visible directory.
+    pub fn begin_scan(&self) -> Scan {
+        let control = Arc::new(ScanControl::default());
+        let tree = FileTree {
+            root: self.root.clone(),
+            nodes: Vec::new(),
+            show_hidden: self.show_hidden,
+            show_gitignored: self.show_gitignored,
+            follow_symlinks: self.follow_symlinks,
+            child_limit: self.child_limit,
+            node_budget: self.node_budget,
+            compact_dirs: self.compact_dirs,
+            max_depth: self.max_depth,
+            sort: self.sort,
+            excludes: self.excludes.clone(),
+            overrides: self.overrides.clone(),
+            offset: 0,
+            expansion: self.expansion.clone(),
+            expand_default: self.expand_default,
+            expansion_epoch: self.expansion_epoch,
+            revealed: self.revealed.clone(),
+            selected: 0,
+            snapshot: None,
+            listings: HashMap::new(),
+            auto_expand_budget: self.auto_expand_budget,
+            scan_control: Some(Arc::clone(&control)),
+            git: None,
+            filter: self.filter.clone(),
+            filter_hidden: 0,
+            forced_open: HashSet::new(),
+            collect_meta: self.collect_meta,
+            dir_sizes: None,
+        };
+        Scan {
+            tree,
+            control,
+            epoch: self.expansion_epoch,
+        }
+    }
+
+    /// Adopt the listings from a finished scan and rebuild the visible nodes.
+    /// Returns false if the scan was for a different root.
+    pub fn finish_scan(&mut self, result: ScanResult) -> bool {
+        if result.root != self.root {
+            return false;
+        }
+        self.listings = result.listings;
+        // Columns may have been turned on while the scan ran
+        if self.collect_meta {
+            for entries in self.listings.values_mut() {
+                fill_meta(entries);
+            }
+        }
+        // The sort order may have changed while the scan ran
+        if result.sort != self.sort {
+            for entries in self.listings.values_mut() {
+                sort_entries(entries, self.sort);
+            }
+        }
+        // Keep toggles made while the scan ran; adopt the defaults it pinned,
+        // unless expand/collapse-all has reset everything since
+        if result.epoch == self.expansion_epoch {
+            for (path, expanded) in result.expansion {
+                self.expansion.entry(path).or_insert(expanded);
+            }
+        }
+        self.rebuild_visible_nodes();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn scan_matches_synchronous_build() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("src/ui")).unwrap();
+        fs::write(root.join("src/ui/mod.rs"), "").unwrap();
+        fs::write(root.join("README.md"), "").unwrap();
+
+        let expected = FileTree::new(&root, false, 10).unwrap();
+        let mut tree = FileTree::unscanned(&root, false, 10);
+        assert_eq!(tree.nodes().len(), 1);
+
+        let scan = tree.begin_scan();
+        let control = scan.control();
+        let result = scan.run().unwrap();
+        assert_eq!(control.entries(), 4);
+        assert!(tree.finish_scan(result));
+        assert_eq!(tree.snapshot(), expected.snapshot());
+    }
+
+    #[test]
+    fn cancelled_and_stale_scans_are_dropped() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir(root.join("a")).unwrap();
+
+        let mut tree = FileTree::unscanned(&root, false, 10);
+        let scan = tree.begin_scan();
+        scan.control().cancel();
+        assert!(scan.run().is_none());
+
+        let result = tree.begin_scan().run().unwrap();
+        tree.set_root_unscanned(root.join("a"));
+        assert!(!tree.finish_scan(result));
+        assert_eq!(tree.nodes().len(), 1);
+    }
+}
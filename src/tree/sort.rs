@@ -0,0 +1,367 @@
+use std::cmp::Ordering;
+use std::path::Path;
+
+use super::{DirEntryInfo, EntryMeta};
+
+/// What the entries of each directory are ordered by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortMode {
+    /// Case-insensitive name
+    #[default]
+    Name,
+    /// Name, with runs of digits compared as numbers (`file2` before `file10`)
+    Natural,
+    /// Newest first
+    Modified,
+    /// Largest first; directories have no size and go after files
+    Size,
+    /// Extension, then name; entries without one come first
+    Extension,
+}
+
+impl SortMode {
+    pub const ALL: [SortMode; 5] = [
+        SortMode::Name,
+        SortMode::Natural,
+        SortMode::Modified,
+        SortMode::Size,
+        SortMode::Extension,
+    ];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "name" => Some(SortMode::Name),
+            "natural" | "version" => Some(SortMode::Natural),
+            "modified" | "mtime" | "time" => Some(SortMode::Modified),
+            "size" => Some(SortMode::Size),
+            "extension" | "ext" => Some(SortMode::Extension),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Name => "name",
+            SortMode::Natural => "natural",
+            SortMode::Modified => "modified",
+            SortMode::Size => "size",
+            SortMode::Extension => "extension",
+        }
+    }
+
+    /// The mode after this one, wrapping around.
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|m| *m == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Whether entries must be stat'ed to be compared.
+    pub fn needs_metadata(self) -> bool {
+        matches!(self, SortMode::Modified | SortMode::Size)
+    }
+}
+
+/// A sort mode plus whether directories are grouped before files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortOrder {
+    pub mode: SortMode,
+    pub directories_first: bool,
+}
+
+impl Default for SortOrder {
+    fn default() -> Self {
+        Self {
+            mode: SortMode::Name,
+            directories_first: true,
+        }
+    }
+}
+
+impl SortOrder {
+    /// Short description for the tree title, e.g. `size` or `name, mixed`.
+    pub fn label(&self) -> String {
+        if self.directories_first {
+            self.mode.label().to_string()
+        } else {
+            format!("{}, mixed", self.mode.label())
+        }
+    }
+
+    fn compare(&self, a: &DirEntryInfo, b: &DirEntryInfo) -> Ordering {
+        let group = if self.directories_first {
+            b.is_dir.cmp(&a.is_dir)
+        } else {
+            Ordering::Equal
+        };
+        group.then_with(|| match self.mode {
+            SortMode::Name => name_cmp(&a.name, &b.name),
+            SortMode::Natural => natural_cmp(&a.name, &b.name),
+            SortMode::Modified => modified_cmp(a, b),
+            SortMode::Size => size_cmp(a, b),
+            SortMode::Extension => extension_cmp(a, b),
+        })
+    }
+}
+
+/// Order a directory listing, stat'ing entries first if the mode needs it.
+pub(super) fn sort_entries(entries: &mut [DirEntryInfo], order: SortOrder) {
+    if order.mode.needs_metadata() {
+        for entry in entries.iter_mut().filter(|e| e.meta.is_none()) {
+            entry.meta = Some(EntryMeta::read(&entry.path));
+        }
+    }
+    entries.sort_by(|a, b| order.compare(a, b));
+}
+
+/// Case-insensitive, falling back to the exact name so the order is total.
+fn name_cmp(a: &str, b: &str) -> Ordering {
+    a.chars()
+        .flat_map(char::to_lowercase)
+        .cmp(b.chars().flat_map(char::to_lowercase))
+        .then_with(|| a.cmp(b))
+}
+
+/// Like [`name_cmp`], but digit runs compare by value: `v2 < v10`.
+/// Equal values with more leading zeros sort after: `1 < 01`.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut ai, mut bi) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        let (x, y) = match (ai.peek(), bi.peek()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(&x), Some(&y)) => (x, y),
+        };
+        let ord = if x.is_ascii_digit() && y.is_ascii_digit() {
+            let take = |it: &mut std::iter::Peekable<std::str::Chars>| {
+                let mut run = String::new();
+                while let Some(c) = it.next_if(char::is_ascii_digit) {
+                    run.push(c);
+                }
+                run
+            };
+            let (xs, ys) = (take(&mut ai), take(&mut bi));
+            let (xv, yv) = (xs.trim_start_matches('0'), ys.trim_start_matches('0'));
+            xv.len()
+                .cmp(&yv.len())
+                .then_with(|| xv.cmp(yv))
+                .then_with(|| xs.len().cmp(&ys.len()))
+        } else {
+            ai.next();
+            bi.next();
+            x.to_lowercase().cmp(y.to_lowercase())
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+}
+
+fn modified_cmp(a: &DirEntryInfo, b: &DirEntryInfo) -> Ordering {
+    let time = |e: &DirEntryInfo| e.meta.as_ref().and_then(|m| m.modified);
+    // Newest first; entries whose time could not be read go last
+    match (time(a), time(b)) {
+        (Some(x), Some(y)) => y.cmp(&x),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+    .then_with(|| natural_cmp(&a.name, &b.name))
+}
+
+fn size_cmp(a: &DirEntryInfo, b: &DirEntryInfo) -> Ordering {
+    let size = |e: &DirEntryInfo| e.meta.as_ref().and_then(|m| m.size);
+    match (size(a), size(b)) {
+        (Some(x), Some(y)) => y.cmp(&x),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+    .then_with(|| natural_cmp(&a.name, &b.name))
+}
+
+fn extension_cmp(a: &DirEntryInfo, b: &DirEntryInfo) -> Ordering {
+    let ext = |e: &DirEntryInfo| {
+        if e.is_dir {
+            return String::new();
+        }
+        Path::new(&e.name)
+            .extension()
+            .map(|x| x.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    };
+    ext(a)
+        .cmp(&ext(b))
+        .then_with(|| natural_cmp(&a.name, &b.name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    fn entry(name: &str, is_dir: bool, age_secs: u64, size: Option<u64>) -> DirEntryInfo {
+        DirEntryInfo {
+            path: PathBuf::from("/p").join(name),
+            name: name.to_string(),
+            is_dir,
+            symlink: None,
+            meta: Some(EntryMeta {
+                modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1000 - age_secs)),
+                size,
+                mode: None,
+            }),
+        }
+    }
+
+    fn sorted(
+        mut entries: Vec<DirEntryInfo>,
+        mode: SortMode,
+        directories_first: bool,
+    ) -> Vec<String> {
+        let order = SortOrder {
+            mode,
+            directories_first,
+        };
+        sort_entries(&mut entries, order);
+        entries.into_iter().map(|e| e.name).collect()
+    }
+
+    #[test]
+    fn name_ignores_case_and_groups_directories() {
+        let entries = vec![
+            entry("b.rs", false, 0, Some(1)),
+            entry("src", true, 0, None),
+            entry("A.md", false, 0, Some(1)),
+            entry("file10", false, 0, Some(1)),
+            entry("file2", false, 0, Some(1)),
+        ];
+        assert_eq!(
+            sorted(entries.clone(), SortMode::Name, true),
+            ["src", "A.md", "b.rs", "file10", "file2"]
+        );
+        assert_eq!(
+            sorted(entries, SortMode::Name, false),
+            ["A.md", "b.rs", "file10", "file2", "src"]
+        );
+    }
+
+    #[test]
+    fn natural_compares_digit_runs_by_value() {
+        assert_eq!(natural_cmp("file2", "file10"), Ordering::Less);
+        assert_eq!(natural_cmp("v1.10.0", "v1.9.3"), Ordering::Greater);
+        assert_eq!(natural_cmp("Img7", "img12"), Ordering::Less);
+        assert_eq!(natural_cmp("a1", "a01"), Ordering::Less);
+        assert_eq!(natural_cmp("a", "a1"), Ordering::Less);
+        assert_eq!(natural_cmp("x", "x"), Ordering::Equal);
+        let entries = vec![
+            entry("file10", false, 0, None),
+            entry("file2", false, 0, None),
+            entry("file1", false, 0, None),
+        ];
+        assert_eq!(
+            sorted(entries, SortMode::Natural, true),
+            ["file1", "file2", "file10"]
+        );
+    }
+
+    #[test]
+    fn modified_puts_newest_first() {
+        let entries = vec![
+            entry("old", false, 500, Some(1)),
+            entry("new", false, 1, Some(1)),
+            entry("dir", true, 900, None),
+            entry("mid", false, 100, Some(1)),
+        ];
+        assert_eq!(
+            sorted(entries.clone(), SortMode::Modified, true),
+            ["dir", "new", "mid", "old"]
+        );
+        assert_eq!(
+            sorted(entries, SortMode::Modified, false),
+            ["new", "mid", "old", "dir"]
+        );
+    }
+
+    #[test]
+    fn size_puts_largest_first_and_directories_after_files() {
+        let entries = vec![
+            entry("small", false, 0, Some(10)),
+            entry("dir", true, 0, None),
+            entry("big", false, 0, Some(10_000)),
+            entry("also-small", false, 0, Some(10)),
+        ];
+        assert_eq!(
+            sorted(entries.clone(), SortMode::Size, false),
+            ["big", "also-small", "small", "dir"]
+        );
+        assert_eq!(
+            sorted(entries, SortMode::Size, true),
+            ["dir", "big", "also-small", "small"]
+        );
+    }
+
+    #[test]
+    fn extension_groups_by_extension_then_name() {
+        let entries = vec![
+            entry("main.rs", false, 0, None),
+            entry("README.md", false, 0, None),
+            entry("Makefile", false, 0, None),
+            entry("lib.RS", false, 0, None),
+            entry("docs.d", true, 0, None),
+            entry("CHANGELOG.md", false, 0, None),
+        ];
+        assert_eq!(
+            sorted(entries, SortMode::Extension, false),
+            [
+                "docs.d",
+                "Makefile",
+                "CHANGELOG.md",
+                "README.md",
+                "lib.RS",
+                "main.rs"
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_metadata_is_read_from_disk() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(temp.path().join("small"), "a").unwrap();
+        std::fs::write(temp.path().join("large"), "abcdef").unwrap();
+        let mut entries: Vec<DirEntryInfo> = ["small", "large"]
+            .iter()
+            .map(|name| DirEntryInfo {
+                path: temp.path().join(name),
+                name: name.to_string(),
+                is_dir: false,
+                symlink: None,
+                meta: None,
+            })
+            .collect();
+        let order = SortOrder {
+            mode: SortMode::Size,
+            directories_first: true,
+        };
+        sort_entries(&mut entries, order);
+        assert_eq!(entries[0].name, "large");
+        assert_eq!(entries[0].meta.as_ref().unwrap().size, Some(6));
+    }
+
+    #[test]
+    fn modes_parse_and_cycle() {
+        assert_eq!(SortMode::parse("Version"), Some(SortMode::Natural));
+        assert_eq!(SortMode::parse("mtime"), Some(SortMode::Modified));
+        assert_eq!(SortMode::parse("random"), None);
+        for mode in SortMode::ALL {
+            assert_eq!(SortMode::parse(mode.label()), Some(mode));
+        }
+        assert_eq!(SortMode::Extension.next(), SortMode::Name);
+        let mixed = SortOrder {
+            mode: SortMode::Size,
+            directories_first: false,
+        };
+        assert_eq!(mixed.label(), "size, mixed");
+    }
+}
@@ -0,0 +1,267 @@
+use std::path::{Path, PathBuf};
+
+use super::{DirEntryInfo, FileNode, FileTree};
+
+/// A directory shows at most this many children until more are asked for.
+pub const DEFAULT_CHILD_LIMIT: usize = 500;
+
+/// Once the tree shows this many rows, directories read after that point only
+/// show a summary row until they are asked for more.
+pub const DEFAULT_NODE_BUDGET: usize = 20_000;
+
+impl FileTree {
+    /// How many of the `total` children of `dir` to show, with `shown` rows built so far.
+    /// Filtering looks at everything, so limits are off while a filter is set.
+    pub(super) fn children_shown(&self, dir: &Path, total: usize, shown: usize) -> usize {
+        if self.filter.is_some() {
+            return total;
+        }
+        let mut limit = self.child_limit.unwrap_or(usize::MAX);
+        if let Some(budget) = self.node_budget {
+            limit = limit.min(budget.saturating_sub(shown));
+        }
+        // Asking for more overrides both limits
+        let revealed = self.revealed.get(dir).copied().unwrap_or(0);
+        limit.max(revealed).min(total)
+    }
+
+    /// The row for `entry`, merged with the chain of single subdirectories below
+    /// it when compaction is on, and the entry whose children come next.
+    pub(super) fn entry_row(
+        &mut self,
+        entry: &DirEntryInfo,
+        depth: usize,
+        is_last: bool,
+        connector: Vec<bool>,
+        shown: usize,
+    ) -> (FileNode, DirEntryInfo) {
+        let mut node = entry.node(depth, is_last, connector);
+        let mut tail = entry.clone();
+        if !self.compact_dirs || self.filter.is_some() {
+            return (node, tail);
+        }
+        while let Some(only) = self.only_subdirectory(&tail, depth, shown) {
+            node.compacted.push(tail.path);
+            node.path = only.path.clone();
+            node.name = format!("{}/{}", node.name, only.name);
+            node.meta = only.meta;
+            tail = only;
+        }
+        (node, tail)
+    }
+
+    /// The single child of an expanded directory, if that child is a directory.
+    /// Links are never merged, so their targets stay visible.
+    fn only_subdirectory(
+        &mut self,
+        dir: &DirEntryInfo,
+        depth: usize,
+        shown: usize,
+    ) -> Option<DirEntryInfo> {
+        if !dir.is_dir || dir.symlink.is_some() || depth >= self.max_depth {
+            return None;
+        }
+        if !self.should_expand(&dir.path, shown) {
+            return None;
+        }
+        self.ensure_listing(&dir.path);
+        match self.listings.get(&dir.path)?.as_slice() {
+            [only] if only.is_dir && only.symlink.is_none() => Some(only.clone()),
+            _ => None,
+        }
+    }
+
+    /// Show another chunk of the directory summarized by the placeholder at
+    /// `index`. The cursor stays on the same row, now the first newly shown entry.
+    pub(super) fn show_more(&mut self, index: usize) -> bool {
+        let Some(node) = self.nodes.get(index).filter(|n| n.more.is_some()) else {
+            return false;
+        };
+        let dir = node.path.clone();
+        let total = self.listings.get(&dir).map_or(0, Vec::len);
+        let hidden = node.more.unwrap_or(0);
+        let chunk = self.child_limit.unwrap_or(DEFAULT_CHILD_LIMIT).max(1);
+        self.revealed.insert(dir, total - hidden.min(total) + chunk);
+        let keep_cursor = self.selected == index;
+        self.rebuild_visible_nodes();
+        if keep_cursor {
+            self.select(index);
+        }
+        true
+    }
+
+    /// Whether patching the children of `dir`, whose listing went from `old` to
+    /// `new` entries, could leave the tree different from a full rebuild.
+    pub(super) fn patch_needs_rebuild(&self, dir: &Path, old: usize, new: usize) -> bool {
+        if self.compact_dirs
+            && (old <= 1
+                || new <= 1
+                || self
+                    .nodes
+                    .iter()
+                    .any(|n| n.compacted.iter().any(|c| c == dir)))
+        {
+            return true;
+        }
+        let most = old.max(new);
+        let limit = self.child_limit.unwrap_or(usize::MAX);
+        let revealed = self.revealed.get(dir).copied().unwrap_or(0);
+        let over_budget = self
+            .node_budget
+            .is_some_and(|budget| self.nodes.len() + new.saturating_sub(old) >= budget);
+        over_budget || most > limit.max(revealed)
+    }
+}
+
+/// The `… 1,234 more files` row closing a summarized listing of `dir`.
+pub(super) fn placeholder(
+    dir: &Path,
+    hidden: usize,
+    depth: usize,
+    connector: Vec<bool>,
+) -> FileNode {
+    let noun = if hidden == 1 { "file" } else { "files" };
+    let name = format!("… {} more {noun}", group_thousands(hidden));
+    let mut node = FileNode::new(PathBuf::from(dir), name, depth, false, true, connector);
+    node.more = Some(hidden);
+    node
+}
+
+/// `19873` as `19,873`.
+fn group_thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::TreeFilter;
+    use std::fs;
+
+    fn names(tree: &FileTree) -> Vec<&str> {
+        tree.nodes()[1..].iter().map(|n| n.name.as_str()).collect()
+    }
+
+    #[test]
+    fn counts_are_grouped() {
+        assert_eq!(group_thousands(7), "7");
+        assert_eq!(group_thousands(1000), "1,000");
+        assert_eq!(group_thousands(19_873), "19,873");
+        assert_eq!(group_thousands(1_234_567), "1,234,567");
+    }
+
+    #[test]
+    fn large_directories_are_summarized_and_expand_on_demand() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir(root.join("dump")).unwrap();
+        for i in 0..7 {
+            fs::write(root.join(format!("dump/f{i}")), "").unwrap();
+        }
+        fs::write(root.join("z.md"), "").unwrap();
+
+        let mut tree = FileTree::unscanned(&root, false, 10);
+        tree.child_limit = Some(3);
+        tree.refresh();
+        assert_eq!(
+            names(&tree),
+            ["dump", "f0", "f1", "f2", "… 4 more files", "z.md"]
+        );
+        let more = &tree.nodes()[5];
+        assert_eq!(
+            (more.more, more.is_last, more.is_dir),
+            (Some(4), true, false)
+        );
+        assert!(!tree.nodes()[4].is_last);
+
+        tree.select(5);
+        assert!(tree.toggle(5));
+        assert_eq!(
+            names(&tree)[..8],
+            ["dump", "f0", "f1", "f2", "f3", "f4", "f5", "… 1 more file"]
+        );
+        assert_eq!(tree.selected_node().unwrap().name, "f3");
+        assert!(tree.toggle(8));
+        assert_eq!(names(&tree).len(), 9);
+        assert!(tree.snapshot().iter().all(|e| !e.contains('…')));
+    }
+
+    #[test]
+    fn node_budget_summarizes_directories_past_it() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        for dir in ["a", "b"] {
+            fs::create_dir(root.join(dir)).unwrap();
+            for f in 0..3 {
+                fs::write(root.join(format!("{dir}/f{f}")), "").unwrap();
+            }
+        }
+
+        let mut tree = FileTree::unscanned(&root, false, 10);
+        tree.node_budget = Some(5);
+        tree.refresh();
+        assert_eq!(names(&tree), ["a", "f0", "f1", "f2", "b", "… 3 more files"]);
+        // Filtering needs every entry
+        tree.set_filter(Some(TreeFilter::parse("f2").unwrap()));
+        assert_eq!(names(&tree), ["a", "f2", "b", "f2"]);
+    }
+
+    #[test]
+    fn single_child_chains_are_compacted() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("src/main/java/com/foo")).unwrap();
+        fs::write(root.join("src/main/java/com/foo/App.java"), "").unwrap();
+        fs::write(root.join("src/main/java/com/Util.java"), "").unwrap();
+
+        let mut tree = FileTree::unscanned(&root, false, 10);
+        tree.compact_dirs = true;
+        tree.refresh();
+        assert_eq!(
+            names(&tree),
+            ["src/main/java/com", "foo", "App.java", "Util.java"]
+        );
+        let row = &tree.nodes()[1];
+        assert_eq!(row.path, root.join("src/main/java/com"));
+        assert_eq!(
+            row.compacted,
+            [
+                root.join("src"),
+                root.join("src/main"),
+                root.join("src/main/java")
+            ]
+        );
+
+        // The row collapses as a unit, and the chain still compacts while collapsed
+        assert!(tree.toggle(1));
+        assert_eq!(names(&tree), ["src/main/java/com"]);
+        assert!(tree.toggle(1));
+
+        // Snapshots list every directory, so replays rebuild the full chain
+        let restored = FileTree::from_snapshot(&root, &tree.snapshot());
+        assert_eq!(restored.nodes().len(), 8);
+
+        // A second child breaks the chain up
+        fs::write(root.join("src/main/notes.md"), "").unwrap();
+        tree.apply_changes([root.join("src/main/notes.md")]);
+        assert_eq!(
+            names(&tree),
+            [
+                "src/main",
+                "java/com",
+                "foo",
+                "App.java",
+                "Util.java",
+                "notes.md"
+            ]
+        );
+    }
+}
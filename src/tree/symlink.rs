@@ -0,0 +1,139 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where a symbolic link in the tree points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symlink {
+    /// The target as stored in the link, which may be relative.
+    pub target: PathBuf,
+    /// The target does not exist.
+    pub broken: bool,
+    /// The target is a directory above the link, so following it would never end.
+    pub cycle: bool,
+}
+
+/// Read the link at `path` and decide whether the tree lists it as a directory:
+/// only links that are followed, resolve to a directory and don't loop are.
+pub(super) fn inspect(path: &Path, follow: bool) -> (Symlink, bool) {
+    let target = fs::read_link(path).unwrap_or_default();
+    let Ok(meta) = fs::metadata(path) else {
+        let link = Symlink {
+            target,
+            broken: true,
+            cycle: false,
+        };
+        return (link, false);
+    };
+    let cycle = meta.is_dir() && points_above(path, &meta);
+    let link = Symlink {
+        target,
+        broken: false,
+        cycle,
+    };
+    (link, follow && meta.is_dir() && !cycle)
+}
+
+/// Whether the directory a link resolves to is one of the link's own ancestors,
+/// compared by device and inode.
+#[cfg(unix)]
+fn points_above(path: &Path, target: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    path.ancestors().skip(1).any(|dir| {
+        fs::metadata(dir).is_ok_and(|m| m.dev() == target.dev() && m.ino() == target.ino())
+    })
+}
+
+/// Whether the directory a link resolves to is one of the link's own ancestors.
+/// Without inodes, resolved paths are compared instead.
+#[cfg(not(unix))]
+fn points_above(path: &Path, _target: &fs::Metadata) -> bool {
+    let Ok(resolved) = fs::canonicalize(path) else {
+        return false;
+    };
+    path.ancestors()
+        .skip(1)
+        .any(|dir| fs::canonicalize(dir).is_ok_and(|d| d == resolved))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::tree::FileTree;
+    use std::os::unix::fs::symlink;
+
+    fn rows(tree: &FileTree) -> Vec<(String, bool, Option<Symlink>)> {
+        tree.nodes()[1..]
+            .iter()
+            .map(|n| (n.name.clone(), n.is_dir, n.symlink.clone()))
+            .collect()
+    }
+
+    fn link(target: &str, broken: bool, cycle: bool) -> Option<Symlink> {
+        Some(Symlink {
+            target: PathBuf::from(target),
+            broken,
+            cycle,
+        })
+    }
+
+    #[test]
+    fn links_are_recorded_with_their_targets() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir(root.join("lib")).unwrap();
+        fs::write(root.join("lib/a.rs"), "").unwrap();
+        symlink("lib", root.join("shared")).unwrap();
+        symlink("lib/a.rs", root.join("z.rs")).unwrap();
+        symlink("missing.txt", root.join("gone")).unwrap();
+
+        let tree = FileTree::new(&root, false, 10).unwrap();
+        assert_eq!(
+            rows(&tree),
+            [
+                ("lib".to_string(), true, None),
+                ("a.rs".to_string(), false, None),
+                ("shared".to_string(), true, link("lib", false, false)),
+                ("a.rs".to_string(), false, None),
+                ("gone".to_string(), false, link("missing.txt", true, false)),
+                ("z.rs".to_string(), false, link("lib/a.rs", false, false)),
+            ]
+        );
+    }
+
+    #[test]
+    fn links_to_ancestors_are_not_walked() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        symlink("..", root.join("a/b/up")).unwrap();
+        symlink(&root, root.join("a/self")).unwrap();
+
+        let tree = FileTree::new(&root, false, 50).unwrap();
+        let names: Vec<&str> = tree.nodes().iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names[1..], ["a", "b", "up", "self"]);
+        let up = &tree.nodes()[3];
+        assert!(!up.is_dir);
+        assert_eq!(up.symlink, link("..", false, true));
+    }
+
+    #[test]
+    fn unfollowed_links_are_listed_as_leaves() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir(root.join("lib")).unwrap();
+        fs::write(root.join("lib/a.rs"), "").unwrap();
+        symlink("lib", root.join("shared")).unwrap();
+
+        let mut tree = FileTree::unscanned(&root, false, 10);
+        tree.follow_symlinks = false;
+        tree.refresh();
+        assert_eq!(
+            rows(&tree),
+            [
+                ("lib".to_string(), true, None),
+                ("a.rs".to_string(), false, None),
+                ("shared".to_string(), false, link("lib", false, false)),
+            ]
+        );
+    }
+}
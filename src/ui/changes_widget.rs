@@ -0,0 +1,121 @@
+use std::path::Path;
+use std::time::Instant;
+
+use ratatui::prelude::*;
+
+use crate::changes::{ChangeKind, SessionChanges};
+
+/// Flat list of the files changed this session, most recent first.
+pub struct ChangesWidget<'a> {
+    changes: &'a SessionChanges,
+    root: &'a Path,
+    offset: usize,
+}
+
+impl<'a> ChangesWidget<'a> {
+    pub fn new(changes: &'a SessionChanges, root: &'a Path, offset: usize) -> Self {
+        Self {
+            changes,
+            root,
+            offset,
+        }
+    }
+}
+
+impl Widget for ChangesWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // Too narrow for a badge, a path and the counts
+        if area.width < 8 || area.height == 0 {
+            return;
+        }
+        if self.changes.is_empty() {
+            let style = Style::default().fg(Color::DarkGray);
+            buf.set_stringn(
+                area.x,
+                area.y,
+                "  No changes yet",
+                area.width as usize,
+                style,
+            );
+            return;
+        }
+
+        let now = Instant::now();
+        let rows = self.changes.by_recency();
+        for (i, (path, change)) in rows
+            .iter()
+            .skip(self.offset)
+            .take(area.height as usize)
+            .enumerate()
+        {
+            let y = area.y + i as u16;
+            let kind = change.kind();
+            let rel = path.strip_prefix(self.root).unwrap_or(path);
+
+            // Count and age on the right, path truncated to what is left
+            let info = format!(
+                " ×{} {} ",
+                change.count,
+                format_age(now.saturating_duration_since(change.last).as_secs())
+            );
+            let info_width = (info.chars().count() as u16).min(area.width);
+            let path_width = area.width.saturating_sub(info_width + 2) as usize;
+
+            buf.set_string(
+                area.x,
+                y,
+                format!("{} ", kind.badge()),
+                Style::default().fg(kind.color()).bold(),
+            );
+            let name_style = Style::default().fg(if kind == ChangeKind::Deleted {
+                Color::DarkGray
+            } else {
+                Color::White
+            });
+            buf.set_stringn(area.x + 2, y, rel.to_string_lossy(), path_width, name_style);
+            buf.set_string(
+                area.x + area.width - info_width,
+                y,
+                &info,
+                Style::default().fg(Color::DarkGray),
+            );
+        }
+    }
+}
+
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m", secs / 60),
+        _ => format!("{}h", secs / 3600),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn lists_paths_relative_to_the_root_with_counts() {
+        let now = Instant::now();
+        let mut changes = SessionChanges::default();
+        changes.record(PathBuf::from("/p/src/a.rs"), true, true, now);
+        changes.record(PathBuf::from("/p/src/a.rs"), true, true, now);
+        changes.record(PathBuf::from("/p/new.md"), false, true, now);
+
+        let area = Rect::new(0, 0, 24, 3);
+        let mut buf = Buffer::empty(area);
+        ChangesWidget::new(&changes, Path::new("/p"), 0).render(area, &mut buf);
+
+        let line = |y: u16| {
+            (0..area.width)
+                .map(|x| buf[(x, y)].symbol().to_string())
+                .collect::<String>()
+        };
+        // Same timestamp: ordered by path
+        assert!(line(0).starts_with("+ new.md"), "{}", line(0));
+        assert!(line(1).starts_with("~ src/a.rs"), "{}", line(1));
+        assert!(line(1).trim_end().ends_with("×2 0s"), "{}", line(1));
+    }
+}
@@ -0,0 +1,236 @@
+use anyhow::{bail, Result};
+use std::time::{Duration, SystemTime};
+
+use crate::tree::EntryMeta;
+
+/// Narrowest room left for names before metadata columns are dropped.
+const MIN_NAME_WIDTH: u16 = 16;
+
+/// A metadata column shown right of the tree's names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    /// File size, or the total below a directory.
+    Size,
+    /// Time since the last modification.
+    Modified,
+    /// `rwx` permission bits.
+    Permissions,
+}
+
+impl Column {
+    /// In the order they are drawn, which is also the order they are kept in
+    /// when the panel is too narrow for all of them.
+    pub const ALL: [Column; 3] = [Column::Size, Column::Modified, Column::Permissions];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "size" => Some(Self::Size),
+            "modified" | "mtime" | "time" => Some(Self::Modified),
+            "permissions" | "perms" | "mode" => Some(Self::Permissions),
+            _ => None,
+        }
+    }
+
+    /// Columns taken, including the space before the value.
+    pub fn width(self) -> u16 {
+        match self {
+            Self::Size => 6,
+            Self::Modified => 9,
+            Self::Permissions => 10,
+        }
+    }
+
+    /// The value for an entry, or None if it is not known.
+    pub fn format(self, meta: &EntryMeta, now: SystemTime) -> Option<String> {
+        match self {
+            Self::Size => meta.size.map(format_size),
+            Self::Modified => meta
+                .modified
+                .map(|t| format_age(now.duration_since(t).unwrap_or_default())),
+            Self::Permissions => meta.mode.map(format_mode),
+        }
+    }
+}
+
+/// Which metadata columns the tree shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TreeColumns {
+    pub size: bool,
+    pub modified: bool,
+    pub permissions: bool,
+}
+
+impl TreeColumns {
+    pub const ALL: TreeColumns = TreeColumns {
+        size: true,
+        modified: true,
+        permissions: true,
+    };
+
+    /// Parse a comma-separated list such as `size,modified`.
+    pub fn parse(list: &str) -> Result<Self> {
+        let mut columns = Self::default();
+        for name in list.split(',').filter(|n| !n.trim().is_empty()) {
+            match Column::parse(name) {
+                Some(column) => columns.set(column, true),
+                None => bail!(
+                    "unknown column '{}' (expected size, modified or permissions)",
+                    name.trim()
+                ),
+            }
+        }
+        Ok(columns)
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.shown().next().is_none()
+    }
+
+    pub fn contains(self, column: Column) -> bool {
+        match column {
+            Column::Size => self.size,
+            Column::Modified => self.modified,
+            Column::Permissions => self.permissions,
+        }
+    }
+
+    pub fn set(&mut self, column: Column, on: bool) {
+        match column {
+            Column::Size => self.size = on,
+            Column::Modified => self.modified = on,
+            Column::Permissions => self.permissions = on,
+        }
+    }
+
+    pub fn shown(self) -> impl Iterator<Item = Column> {
+        Column::ALL.into_iter().filter(move |&c| self.contains(c))
+    }
+
+    /// The columns that fit in a panel `width` wide next to `reserved` columns
+    /// at its right edge, dropping the last ones first.
+    pub fn fit(self, width: u16, reserved: u16) -> Vec<Column> {
+        let mut columns: Vec<Column> = self.shown().collect();
+        while !columns.is_empty() {
+            let used: u16 = columns.iter().map(|c| c.width()).sum();
+            if width >= MIN_NAME_WIDTH + reserved + used {
+                break;
+            }
+            columns.pop();
+        }
+        columns
+    }
+}
+
+/// `512B`, `4.0K`, `12M`: one decimal below 10, binary units.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+    if bytes < 1024 {
+        return format!("{bytes}B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1023.5 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value < 9.95 {
+        format!("{value:.1}{}", UNITS[unit])
+    } else {
+        format!("{value:.0}{}", UNITS[unit])
+    }
+}
+
+/// `now`, `45s ago`, `3m ago` … `2y ago`.
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    let (value, unit) = match secs {
+        0..5 => return "now".to_string(),
+        5..60 => (secs, "s"),
+        60..3600 => (secs / 60, "m"),
+        3600..86_400 => (secs / 3600, "h"),
+        86_400..604_800 => (secs / 86_400, "d"),
+        604_800..2_592_000 => (secs / 604_800, "w"),
+        2_592_000..31_536_000 => (secs / 2_592_000, "mo"),
+        _ => (secs / 31_536_000, "y"),
+    };
+    format!("{value}{unit} ago")
+}
+
+/// `rwxr-xr-x` from Unix mode bits.
+pub fn format_mode(mode: u32) -> String {
+    (0..9)
+        .map(|i| {
+            let bit = 1 << (8 - i);
+            match (mode & bit != 0, i % 3) {
+                (false, _) => '-',
+                (true, 0) => 'r',
+                (true, 1) => 'w',
+                (true, _) => 'x',
+            }
+        })
+        .collect()
+}
+
+/// Whether anyone may execute the entry.
+pub fn is_executable(meta: &EntryMeta) -> bool {
+    meta.mode.is_some_and(|mode| mode & 0o111 != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_are_human_readable() {
+        assert_eq!(format_size(0), "0B");
+        assert_eq!(format_size(1023), "1023B");
+        assert_eq!(format_size(4096), "4.0K");
+        assert_eq!(format_size(12 * 1024 + 300), "12K");
+        assert_eq!(format_size(1024 * 1024 - 1), "1.0M");
+        assert_eq!(format_size(3 << 30), "3.0G");
+        for bytes in [1023, 1024 * 1000, 1 << 40, u64::MAX] {
+            assert!(format_size(bytes).len() < Column::Size.width() as usize);
+        }
+    }
+
+    #[test]
+    fn ages_are_relative() {
+        let secs = Duration::from_secs;
+        assert_eq!(format_age(secs(2)), "now");
+        assert_eq!(format_age(secs(45)), "45s ago");
+        assert_eq!(format_age(secs(200)), "3m ago");
+        assert_eq!(format_age(secs(2 * 86_400)), "2d ago");
+        assert_eq!(format_age(secs(330 * 86_400)), "11mo ago");
+        assert_eq!(format_age(secs(800 * 86_400)), "2y ago");
+        assert!(format_age(secs(330 * 86_400)).len() < Column::Modified.width() as usize);
+    }
+
+    #[test]
+    fn modes_render_like_ls() {
+        assert_eq!(format_mode(0o100755), "rwxr-xr-x");
+        assert_eq!(format_mode(0o640), "rw-r-----");
+        let meta = |mode| EntryMeta {
+            mode: Some(mode),
+            ..EntryMeta::default()
+        };
+        assert!(is_executable(&meta(0o744)));
+        assert!(!is_executable(&meta(0o644)));
+    }
+
+    #[test]
+    fn columns_parse_and_drop_when_narrow() {
+        let columns = TreeColumns::parse("size, mtime").unwrap();
+        assert_eq!(
+            columns.shown().collect::<Vec<_>>(),
+            [Column::Size, Column::Modified]
+        );
+        assert!(TreeColumns::parse("owner").is_err());
+
+        assert_eq!(TreeColumns::ALL.fit(60, 3).len(), 3);
+        assert_eq!(
+            TreeColumns::ALL.fit(34, 3),
+            [Column::Size, Column::Modified]
+        );
+        assert!(TreeColumns::ALL.fit(20, 3).is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+use ratatui::prelude::*;
+
+use super::format_bytes;
+use crate::diff::{DiffLine, DiffRow, DiffView, LineTag, Undiffable};
+use crate::preview::{clean_line, MAX_PREVIEW_BYTES};
+
+const REMOVED_BG: Color = Color::Rgb(70, 25, 25);
+const ADDED_BG: Color = Color::Rgb(25, 60, 30);
+
+/// The rows of a diff from the view's scroll position, unified or side by side.
+pub struct DiffWidget<'a> {
+    view: &'a DiffView,
+}
+
+impl<'a> DiffWidget<'a> {
+    pub fn new(view: &'a DiffView) -> Self {
+        Self { view }
+    }
+}
+
+impl Widget for DiffWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width < 8 || area.height == 0 {
+            return;
+        }
+        let message = match self.view.diff.undiffable {
+            Some(Undiffable::Binary(size)) => Some(format!(
+                "  Binary file ({}), cannot diff",
+                format_bytes(size as usize)
+            )),
+            Some(Undiffable::TooLarge(size)) => Some(format!(
+                "  Too large to diff ({}, limit {})",
+                format_bytes(size as usize),
+                format_bytes(MAX_PREVIEW_BYTES as usize)
+            )),
+            None if self.view.diff.is_empty() => Some(format!(
+                "  No changes against {}",
+                self.view.diff.source.label()
+            )),
+            None => None,
+        };
+        if let Some(text) = message {
+            buf.set_stringn(
+                area.x,
+                area.y,
+                text,
+                area.width as usize,
+                Style::default().fg(Color::DarkGray),
+            );
+            return;
+        }
+
+        let half = area.width / 2;
+        let rows = self.view.rows();
+        for (i, row) in rows
+            .iter()
+            .skip(self.view.scroll)
+            .take(area.height as usize)
+            .enumerate()
+        {
+            let y = area.y + i as u16;
+            match row {
+                DiffRow::Header(hunk) => {
+                    let style = Style::default().fg(Color::Cyan);
+                    buf.set_stringn(area.x, y, hunk.header(), area.width as usize, style);
+                }
+                DiffRow::Line(line) => {
+                    let number = line.old_no.or(line.new_no);
+                    draw_line(buf, area.x, y, area.width, Some(line), number, true);
+                }
+                DiffRow::Pair(old, new) => {
+                    let old_no = old.and_then(|l| l.old_no);
+                    let new_no = new.and_then(|l| l.new_no);
+                    // Context lines appear on both sides as the same line
+                    let old = old.filter(|l| l.tag != LineTag::Added);
+                    let new = new.filter(|l| l.tag != LineTag::Removed);
+                    draw_line(buf, area.x, y, half, old, old_no, false);
+                    draw_line(buf, area.x + half, y, area.width - half, new, new_no, false);
+                }
+            }
+        }
+    }
+}
+
+/// One side of a row: line number, sign (unified only) and text on the tag's background.
+fn draw_line(
+    buf: &mut Buffer,
+    x: u16,
+    y: u16,
+    width: u16,
+    line: Option<&DiffLine>,
+    number: Option<usize>,
+    unified: bool,
+) {
+    let Some(line) = line else {
+        return;
+    };
+    let (sign, bg) = match line.tag {
+        LineTag::Context => (' ', Color::Reset),
+        LineTag::Removed => ('-', REMOVED_BG),
+        LineTag::Added => ('+', ADDED_BG),
+    };
+    if bg != Color::Reset {
+        for cx in x..x + width {
+            if let Some(cell) = buf.cell_mut((cx, y)) {
+                cell.set_bg(bg);
+            }
+        }
+    }
+    let number = number.map_or(String::new(), |n| n.to_string());
+    let prefix = if unified {
+        format!("{number:>5} {sign} ")
+    } else {
+        format!("{number:>5} ")
+    };
+    let dim = Style::default().fg(Color::DarkGray).bg(bg);
+    let (next, _) = buf.set_stringn(x, y, &prefix, width as usize, dim);
+    let used = next - x;
+    if used < width {
+        let fg = match line.tag {
+            LineTag::Context => Color::Gray,
+            LineTag::Removed => Color::Rgb(255, 170, 170),
+            LineTag::Added => Color::Rgb(170, 255, 170),
+        };
+        let style = Style::default().fg(fg).bg(bg);
+        buf.set_stringn(
+            next,
+            y,
+            clean_line(&line.text),
+            (width - used) as usize,
+            style,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::{BaselineSource, FileDiff};
+    use std::path::Path;
+
+    fn text(buf: &Buffer, y: u16) -> String {
+        (0..buf.area.width)
+            .map(|x| buf[(x, y)].symbol().to_string())
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn renders_unified_and_side_by_side() {
+        let diff = FileDiff::compute(
+            Path::new("/p/f"),
+            BaselineSource::Snapshot,
+            "a\nold\n",
+            "a\nnew\n",
+        );
+        let mut view = DiffView::new(diff);
+        let area = Rect::new(0, 0, 40, 4);
+
+        let mut buf = Buffer::empty(area);
+        DiffWidget::new(&view).render(area, &mut buf);
+        assert_eq!(text(&buf, 0), "@@ -1,2 +1,2 @@");
+        assert_eq!(text(&buf, 1), "    1   a");
+        assert_eq!(text(&buf, 2), "    2 - old");
+        assert_eq!(text(&buf, 3), "    2 + new");
+        assert_eq!(buf[(8, 2)].bg, REMOVED_BG);
+
+        view.toggle_layout();
+        let mut buf = Buffer::empty(area);
+        DiffWidget::new(&view).render(area, &mut buf);
+        assert_eq!(text(&buf, 2), "    2 old               2 new");
+    }
+
+    #[test]
+    fn explains_binary_files() {
+        let diff = FileDiff::undiffable(
+            Path::new("/p/f"),
+            BaselineSource::Head,
+            Undiffable::Binary(6),
+        );
+        let view = DiffView::new(diff);
+        let area = Rect::new(0, 0, 40, 2);
+        let mut buf = Buffer::empty(area);
+        DiffWidget::new(&view).render(area, &mut buf);
+        assert_eq!(text(&buf, 0), "  Binary file (6 B), cannot diff");
+    }
+}
@@ -1,18 +1,98 @@
 use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
 
 use ratatui::{prelude::*, widgets::StatefulWidget};
 
-use super::FileTreeWidgetState;
-use crate::tree::FileTree;
+use super::columns::{self, Column, TreeColumns};
+use super::{FileTreeWidgetState, IconTheme};
+use crate::changes::SessionChanges;
+use crate::tree::{FileNode, FileTree, GitStatus, Symlink};
+
+const SELECTED_BG: Color = Color::Rgb(40, 60, 90);
 
 pub struct FileTreeWidget<'a> {
     tree: &'a FileTree,
     cwd: Option<&'a Path>,
+    // Highlight the keyboard cursor (tree focus mode)
+    show_selection: bool,
+    changes: Option<&'a SessionChanges>,
+    icons: IconTheme,
+    columns: TreeColumns,
 }
 
 impl<'a> FileTreeWidget<'a> {
     pub fn new(tree: &'a FileTree, cwd: Option<&'a Path>) -> Self {
-        Self { tree, cwd }
+        Self {
+            tree,
+            cwd,
+            show_selection: false,
+            changes: None,
+            icons: IconTheme::default(),
+            columns: TreeColumns::default(),
+        }
+    }
+
+    pub fn show_selection(mut self, show: bool) -> Self {
+        self.show_selection = show;
+        self
+    }
+
+    /// Badge files changed this session and highlight recent changes.
+    pub fn changes(mut self, changes: &'a SessionChanges) -> Self {
+        self.changes = Some(changes);
+        self
+    }
+
+    pub fn icons(mut self, icons: IconTheme) -> Self {
+        self.icons = icons;
+        self
+    }
+
+    /// Metadata columns to show, as many as fit the width.
+    pub fn columns(mut self, columns: TreeColumns) -> Self {
+        self.columns = columns;
+        self
+    }
+}
+
+impl FileTreeWidget<'_> {
+    /// Restyle the part of a node's name matched by the tree filter.
+    /// `name_end` is the column just after the name.
+    fn highlight_filter_match(
+        &self,
+        buf: &mut Buffer,
+        area: Rect,
+        node: &FileNode,
+        name_end: u16,
+        y: u16,
+    ) {
+        let Some(filter) = self.tree.filter() else {
+            return;
+        };
+        let Some(ranges) = filter.find(&self.tree.relative_name(node), &node.name) else {
+            return;
+        };
+        let width = |s: &str| unicode_width::UnicodeWidthStr::width(s) as u16;
+        let name_x = name_end.saturating_sub(width(&node.name));
+        for range in ranges {
+            let start = name_x + width(&node.name[..range.start]);
+            let end = name_x + width(&node.name[..range.end]);
+            for x in start..end.min(area.right()) {
+                if let Some(cell) = buf.cell_mut((x, y)) {
+                    cell.set_fg(Color::Yellow);
+                    cell.modifier.insert(Modifier::BOLD | Modifier::UNDERLINED);
+                }
+            }
+        }
+    }
+}
+
+/// Column of a node's icon, relative to the left edge of the tree area.
+pub fn icon_column(node: &FileNode) -> u16 {
+    if node.depth == 0 {
+        0
+    } else {
+        (node.connector.len() as u16 + 1) * 4
     }
 }
 
@@ -26,6 +106,11 @@ impl<'a> StatefulWidget for FileTreeWidget<'a> {
 
         let nodes = self.tree.nodes();
         let visible_height = area.height as usize;
+        let now = Instant::now();
+        let wall_now = SystemTime::now();
+        // Columns sit left of the git marker slot, which they always keep free
+        let columns = self.columns.fit(area.width, 3);
+        let columns_width: u16 = columns.iter().map(|c| c.width()).sum();
 
         // Calculate visible range
         let start = state.offset;
@@ -43,38 +128,87 @@ impl<'a> StatefulWidget for FileTreeWidget<'a> {
                 break;
             }
 
-            // Check if this node is the CWD
-            let is_cwd = self.cwd.is_some_and(|cwd| node.is_dir && node.path == cwd);
+            // Check if this node is the CWD, or a compacted row merging it
+            let is_cwd = self.cwd.is_some_and(|cwd| node.shows_dir(cwd));
 
-            // Clear background for CWD item
-            if is_cwd {
+            let is_selected = self.show_selection && idx == self.tree.selected();
+
+            let change = self
+                .changes
+                .filter(|_| !node.is_dir)
+                .and_then(|changes| changes.get(&node.path));
+            let recency = change.map_or(0.0, |c| c.recency(now));
+
+            // Clear background for CWD item, or a fading one for a recent change
+            if is_cwd || is_selected || recency > 0.0 {
+                let bg = if is_selected {
+                    SELECTED_BG
+                } else if is_cwd {
+                    Color::Rgb(80, 70, 30)
+                } else {
+                    fade(change.map_or(Color::Reset, |c| c.kind().color()), recency)
+                };
                 for x in area.x..area.x + area.width {
                     if let Some(cell) = buf.cell_mut((x, y)) {
-                        cell.set_bg(Color::Rgb(80, 70, 30));
+                        cell.set_bg(bg);
                     }
                 }
             }
 
-            let tree_style = Style::default().fg(Color::DarkGray);
+            let mut tree_style = Style::default().fg(Color::DarkGray);
+            if is_selected {
+                tree_style = tree_style.bg(SELECTED_BG);
+            }
+            let icon = self.icons.icon(node, self.tree.is_expanded(node));
             let node_style = if is_cwd {
                 Style::default()
-                    .bg(Color::Rgb(80, 70, 30))
+                    .bg(if is_selected {
+                        SELECTED_BG
+                    } else {
+                        Color::Rgb(80, 70, 30)
+                    })
                     .fg(Color::Rgb(255, 220, 100))
                     .bold()
+            } else if is_selected {
+                Style::default().bg(SELECTED_BG).fg(icon.color).bold()
             } else {
-                let color = node.display_color();
-                let mut s = Style::default().fg(color);
+                let mut s = Style::default().fg(icon.color);
                 if node.is_dir {
                     s = s.bold();
                 }
                 s
             };
 
+            // Links are italic, and red when their target is missing
+            let node_style = match &node.symlink {
+                Some(link) if !is_cwd => node_style
+                    .fg(if link.broken { Color::Red } else { Color::Cyan })
+                    .italic(),
+                _ => node_style,
+            };
+
+            // Listed only because gitignored entries are shown: greyed out
+            let node_style = if self.tree.show_gitignored
+                && !is_cwd
+                && node.git_status == Some(GitStatus::Ignored)
+            {
+                node_style.fg(Color::DarkGray).not_bold()
+            } else {
+                node_style
+            };
+
+            // The `… N more files` row of a summarized directory
+            let node_style = if node.more.is_some() {
+                node_style.fg(Color::DarkGray).not_bold().italic()
+            } else {
+                node_style
+            };
+
             let mut x_offset = area.x;
 
             if node.depth == 0 {
                 // Root node: icon + name, no tree prefix
-                let icon = node.expanded_icon(true);
+                let icon = self.icons.pad(icon.glyph);
                 let display = if is_cwd {
                     format!("{}● {}", icon, node.name)
                 } else {
@@ -99,8 +233,23 @@ impl<'a> StatefulWidget for FileTreeWidget<'a> {
                 buf.set_string(x_offset, y, branch, tree_style);
                 x_offset += 4;
 
+                // Files changed this session show their badge in place of the icon
+                if let Some(change) = change {
+                    let kind = change.kind();
+                    let style = Style::default().fg(kind.color()).bold();
+                    let badge = self.icons.pad(&kind.badge().to_string());
+                    buf.set_string(x_offset, y, &badge, style);
+                    x_offset += self.icons.column_width();
+                }
+
                 // Draw icon + name
-                let icon = node.expanded_icon(true);
+                let icon = if change.is_some() {
+                    String::new()
+                } else if node.more.is_some() {
+                    self.icons.pad("")
+                } else {
+                    self.icons.pad(icon.glyph)
+                };
                 let display = if is_cwd {
                     format!("{}● {}", icon, node.name)
                 } else {
@@ -108,17 +257,59 @@ impl<'a> StatefulWidget for FileTreeWidget<'a> {
                 };
                 buf.set_string(x_offset, y, &display, node_style);
                 x_offset += unicode_width::UnicodeWidthStr::width(display.as_str()) as u16;
+                self.highlight_filter_match(buf, area, node, x_offset, y);
+                if let Some(link) = &node.symlink {
+                    x_offset = draw_link_target(buf, area, link, x_offset, y, is_selected);
+                }
             }
 
+            // Git status marker, right-aligned; the last column is left for the scrollbar
+            let marker = node.git_status.filter(|_| area.width >= 4);
+            let reserved = if !columns.is_empty() {
+                3 + columns_width
+            } else if marker.is_some() {
+                3
+            } else {
+                0
+            };
+
             // Truncate if too long
             let total_width = x_offset.saturating_sub(area.x);
-            if total_width > area.width {
-                if let Some(x) = area.x.checked_add(area.width.saturating_sub(1)) {
+            if total_width > area.width - reserved {
+                if let Some(x) = area.x.checked_add(area.width.saturating_sub(1 + reserved)) {
                     if let Some(cell) = buf.cell_mut((x, y)) {
                         cell.set_symbol("…");
                     }
                 }
             }
+
+            let mut x = area.right() - reserved;
+            for &column in &columns {
+                let width = column.width();
+                let text = node
+                    .meta
+                    .as_ref()
+                    .and_then(|meta| column.format(meta, wall_now))
+                    .unwrap_or_default();
+                let mut style = column_style(column, node, wall_now);
+                if is_selected {
+                    style = style.bg(SELECTED_BG);
+                }
+                buf.set_string(x, y, format!("{text:>w$}", w = width as usize), style);
+                x += width;
+            }
+            if !columns.is_empty() && marker.is_none() {
+                buf.set_string(x, y, "   ", Style::default());
+            }
+
+            if let Some(status) = marker {
+                let mut style = Style::default().fg(status.color()).bold();
+                if is_selected {
+                    style = style.bg(SELECTED_BG);
+                }
+                let x = area.x + area.width - reserved;
+                buf.set_string(x, y, format!(" {} ", status.marker()), style);
+            }
         }
 
         // Show scroll indicator if needed
@@ -145,10 +336,80 @@ impl<'a> StatefulWidget for FileTreeWidget<'a> {
     }
 }
 
+/// Sizes over 10 MB and changes in the last five minutes stand out, as do
+/// executable files.
+fn column_style(column: Column, node: &FileNode, now: SystemTime) -> Style {
+    const LARGE: u64 = 10 * 1024 * 1024;
+    const HUGE: u64 = 100 * 1024 * 1024;
+    const RECENT: Duration = Duration::from_secs(300);
+    let dim = Style::default().fg(Color::DarkGray);
+    let Some(meta) = &node.meta else {
+        return dim;
+    };
+    match column {
+        Column::Size => match meta.size {
+            Some(size) if size >= HUGE => dim.fg(Color::LightRed).bold(),
+            Some(size) if size >= LARGE => dim.fg(Color::Yellow),
+            _ => dim,
+        },
+        Column::Modified => match meta.modified.and_then(|t| now.duration_since(t).ok()) {
+            Some(age) if age < RECENT => dim.fg(Color::Green),
+            _ => dim,
+        },
+        Column::Permissions if !node.is_dir && columns::is_executable(meta) => dim.fg(Color::Green),
+        Column::Permissions => dim,
+    }
+}
+
+/// Draw ` -> target` after a link's name and return the column after it.
+/// Broken targets are struck through, and links that loop back are marked.
+fn draw_link_target(
+    buf: &mut Buffer,
+    area: Rect,
+    link: &Symlink,
+    x: u16,
+    y: u16,
+    is_selected: bool,
+) -> u16 {
+    let mut style = Style::default().fg(Color::DarkGray);
+    if is_selected {
+        style = style.bg(SELECTED_BG);
+    }
+    let target_style = if link.broken {
+        style.fg(Color::Red).crossed_out()
+    } else {
+        style
+    };
+    let target = link.target.to_string_lossy();
+    let mut x = x;
+    for (text, style) in [
+        (" -> ", style),
+        (target.as_ref(), target_style),
+        (if link.cycle { " ↻" } else { "" }, style),
+    ] {
+        let room = area.right().saturating_sub(x) as usize;
+        buf.set_stringn(x, y, text, room, style);
+        // Keep counting past the edge so the row is truncated with `…`
+        x = x.saturating_add(unicode_width::UnicodeWidthStr::width(text) as u16);
+    }
+    x
+}
+
+/// `color` dimmed towards black by `strength` (1.0 keeps a quarter of it), for fading highlights.
+fn fade(color: Color, strength: f32) -> Color {
+    match color {
+        Color::Rgb(r, g, b) => {
+            let scale = |c: u8| (c as f32 * 0.25 * strength) as u8;
+            Color::Rgb(scale(r), scale(g), scale(b))
+        }
+        other => other,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tree::FileTree;
+    use crate::tree::{FileTree, GitStatusMap, TreeFilter};
     use crate::ui::FileTreeWidgetState;
     use ratatui::{buffer::Buffer, widgets::StatefulWidget};
 
@@ -165,4 +426,201 @@ mod tests {
 
         widget.render(area, &mut buf, &mut state);
     }
+
+    #[test]
+    fn git_markers_are_right_aligned() {
+        let temp = tempfile::tempdir().expect("failed to create tempdir");
+        let root = temp
+            .path()
+            .canonicalize()
+            .expect("failed to resolve tempdir");
+        std::fs::write(root.join("changed.rs"), "x").expect("failed to create file");
+        std::fs::write(root.join("clean.rs"), "x").expect("failed to create file");
+        let mut tree = FileTree::new(&root, false, 10).expect("failed to build tree");
+        let status = b"1 .M N... 100644 100644 100644 a b changed.rs\0";
+        tree.set_git_status(Some(GitStatusMap::parse(&root, status)));
+
+        let area = Rect::new(0, 0, 30, 3);
+        let mut buf = Buffer::empty(area);
+        let mut state = FileTreeWidgetState { offset: 0 };
+        FileTreeWidget::new(&tree, None).render(area, &mut buf, &mut state);
+
+        let marker = |y: u16| buf[(28, y)].symbol().to_string();
+        // Root rolls up the change; clean.rs has no marker
+        assert_eq!(marker(0), "M");
+        assert_eq!(marker(1), "M");
+        assert_eq!(marker(2), " ");
+    }
+
+    #[test]
+    fn filter_matches_are_highlighted() {
+        let temp = tempfile::tempdir().expect("failed to create tempdir");
+        let root = temp
+            .path()
+            .canonicalize()
+            .expect("failed to resolve tempdir");
+        std::fs::write(root.join("handler.rs"), "x").expect("failed to create file");
+        std::fs::write(root.join("other.md"), "x").expect("failed to create file");
+        let mut tree = FileTree::new(&root, false, 10).expect("failed to build tree");
+        tree.set_filter(Some(TreeFilter::parse("dle").expect("valid filter")));
+
+        let area = Rect::new(0, 0, 30, 3);
+        let mut buf = Buffer::empty(area);
+        let mut state = FileTreeWidgetState { offset: 0 };
+        FileTreeWidget::new(&tree, None).render(area, &mut buf, &mut state);
+
+        let row: Vec<String> = (0..area.width)
+            .map(|x| buf[(x, 1)].symbol().to_string())
+            .collect();
+        let start = row
+            .iter()
+            .position(|c| c == "h")
+            .expect("handler.rs is shown") as u16;
+        assert_eq!(buf[(start + 3, 1)].fg, Color::Yellow);
+        assert_ne!(buf[(start + 2, 1)].fg, Color::Yellow);
+        // other.md is filtered out
+        assert_eq!(buf[(4, 2)].symbol(), " ");
+    }
+
+    #[test]
+    fn wide_icons_keep_names_aligned() {
+        let temp = tempfile::tempdir().expect("failed to create tempdir");
+        let root = temp
+            .path()
+            .canonicalize()
+            .expect("failed to resolve tempdir");
+        std::fs::create_dir(root.join("src")).expect("failed to create dir");
+        std::fs::write(root.join("main.rs"), "x").expect("failed to create file");
+        let tree = FileTree::new(&root, false, 10).expect("failed to build tree");
+
+        let area = Rect::new(0, 0, 30, 3);
+        let mut buf = Buffer::empty(area);
+        let mut state = FileTreeWidgetState { offset: 0 };
+        FileTreeWidget::new(&tree, None)
+            .icons(IconTheme::Emoji)
+            .render(area, &mut buf, &mut state);
+
+        // Branch, two-column emoji plus padding, separator, then the name
+        assert_eq!(buf[(4, 1)].symbol(), "🧩");
+        assert_eq!(buf[(8, 1)].symbol(), "s");
+        assert_eq!(buf[(4, 2)].symbol(), "🦀");
+        assert_eq!(buf[(8, 2)].symbol(), "m");
+        assert_eq!(buf[(8, 2)].fg, Color::Rgb(255, 150, 50));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_show_their_target() {
+        let temp = tempfile::tempdir().expect("failed to create tempdir");
+        let root = temp
+            .path()
+            .canonicalize()
+            .expect("failed to resolve tempdir");
+        std::os::unix::fs::symlink("nowhere", root.join("gone")).expect("failed to create link");
+        let tree = FileTree::new(&root, false, 10).expect("failed to build tree");
+
+        let area = Rect::new(0, 0, 30, 2);
+        let mut buf = Buffer::empty(area);
+        let mut state = FileTreeWidgetState { offset: 0 };
+        FileTreeWidget::new(&tree, None).render(area, &mut buf, &mut state);
+
+        let row: String = (0..area.width)
+            .map(|x| buf[(x, 1)].symbol().to_string())
+            .collect();
+        assert_eq!(row.trim_end(), "└── ·  gone -> nowhere");
+        assert_eq!(buf[(7, 1)].fg, Color::Red);
+        assert!(buf[(15, 1)].modifier.contains(Modifier::CROSSED_OUT));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn metadata_columns_are_right_aligned_and_dropped_when_narrow() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp = tempfile::tempdir().expect("failed to create tempdir");
+        let root = temp
+            .path()
+            .canonicalize()
+            .expect("failed to resolve tempdir");
+        std::fs::write(root.join("data.bin"), vec![0u8; 4096]).expect("failed to create file");
+        std::fs::set_permissions(root.join("data.bin"), PermissionsExt::from_mode(0o644))
+            .expect("failed to set permissions");
+        let mut tree = FileTree::new(&root, false, 10).expect("failed to build tree");
+        tree.set_collect_meta(true);
+
+        let render = |width: u16| {
+            let area = Rect::new(0, 0, width, 2);
+            let mut buf = Buffer::empty(area);
+            let mut state = FileTreeWidgetState { offset: 0 };
+            FileTreeWidget::new(&tree, None)
+                .columns(TreeColumns::ALL)
+                .render(area, &mut buf, &mut state);
+            (0..width)
+                .map(|x| buf[(x, 1)].symbol().to_string())
+                .collect::<String>()
+        };
+        // Size, age and mode, then the git marker slot
+        let row = render(50);
+        assert!(row.ends_with("  4.0K      now rw-r--r--   "), "{row:?}");
+        // Too narrow for the age and mode: only the size is kept
+        let row = render(30);
+        assert!(row.ends_with("  4.0K   "), "{row:?}");
+        assert!(row.starts_with("└── ·  data.bin"));
+    }
+
+    #[test]
+    fn listed_gitignored_entries_are_greyed_out() {
+        let temp = tempfile::tempdir().expect("failed to create tempdir");
+        let root = temp
+            .path()
+            .canonicalize()
+            .expect("failed to resolve tempdir");
+        std::fs::write(root.join("app.log"), "x").expect("failed to create file");
+        let mut tree = FileTree::new(&root, false, 10).expect("failed to build tree");
+        tree.set_git_status(Some(GitStatusMap::parse(&root, b"! app.log\0")));
+
+        let area = Rect::new(0, 0, 30, 2);
+        let render = |tree: &FileTree| {
+            let mut buf = Buffer::empty(area);
+            let mut state = FileTreeWidgetState { offset: 0 };
+            FileTreeWidget::new(tree, None).render(area, &mut buf, &mut state);
+            buf[(8, 1)].fg
+        };
+        assert_ne!(render(&tree), Color::DarkGray);
+        tree.show_gitignored = true;
+        assert_eq!(render(&tree), Color::DarkGray);
+    }
+
+    #[test]
+    fn summarized_and_compacted_rows() {
+        let temp = tempfile::tempdir().expect("failed to create tempdir");
+        let root = temp
+            .path()
+            .canonicalize()
+            .expect("failed to resolve tempdir");
+        std::fs::create_dir_all(root.join("a/b/c")).expect("failed to create dir");
+        for name in ["c/x", "c/y", "c/z"] {
+            std::fs::write(root.join("a/b").join(name), "").expect("failed to create file");
+        }
+        let mut tree = FileTree::unscanned(&root, false, 10);
+        tree.compact_dirs = true;
+        tree.child_limit = Some(1);
+        tree.refresh();
+
+        let area = Rect::new(0, 0, 30, 4);
+        let mut buf = Buffer::empty(area);
+        let mut state = FileTreeWidgetState { offset: 0 };
+        let cwd = root.join("a/b");
+        FileTreeWidget::new(&tree, Some(&cwd)).render(area, &mut buf, &mut state);
+
+        let row = |y: u16| -> String {
+            (0..area.width)
+                .map(|x| buf[(x, y)].symbol().to_string())
+                .collect()
+        };
+        assert!(row(1).contains("● a/b/c"), "{}", row(1));
+        assert!(row(3).contains("… 2 more files"), "{}", row(3));
+        let dots = row(3).find('…').expect("placeholder is shown") as u16;
+        assert_eq!(buf[(dots, 3)].fg, Color::DarkGray);
+        assert!(buf[(dots, 3)].modifier.contains(Modifier::ITALIC));
+    }
 }
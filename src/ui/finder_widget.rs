@@ -0,0 +1,107 @@
+use ratatui::prelude::*;
+
+use crate::finder::Finder;
+
+const SELECTED_BG: Color = Color::Rgb(40, 60, 90);
+
+/// Result list of the fuzzy finder, with matched characters highlighted.
+pub struct FinderWidget<'a> {
+    finder: &'a Finder,
+}
+
+impl<'a> FinderWidget<'a> {
+    pub fn new(finder: &'a Finder) -> Self {
+        Self { finder }
+    }
+}
+
+impl Widget for FinderWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width < 4 || area.height == 0 {
+            return;
+        }
+        let dim = Style::default().fg(Color::DarkGray);
+        if self.finder.is_loading() {
+            buf.set_string(area.x, area.y, "  Listing files...", dim);
+            return;
+        }
+        if self.finder.list().matches().is_empty() {
+            buf.set_string(area.x, area.y, "  No matching files", dim);
+            return;
+        }
+
+        let rows = self
+            .finder
+            .list()
+            .matches()
+            .iter()
+            .enumerate()
+            .skip(self.finder.list().offset())
+            .take(area.height as usize);
+        for (i, (row, m)) in rows.enumerate() {
+            let y = area.y + i as u16;
+            let selected = row == self.finder.list().selected();
+            let bg = if selected { SELECTED_BG } else { Color::Reset };
+            if selected {
+                for x in area.x..area.x + area.width {
+                    if let Some(cell) = buf.cell_mut((x, y)) {
+                        cell.set_bg(bg);
+                    }
+                }
+            }
+
+            let mark = if self.finder.is_marked(m.index) {
+                "● "
+            } else {
+                "  "
+            };
+            buf.set_string(area.x, y, mark, Style::default().fg(Color::Cyan).bg(bg));
+
+            let mut x = area.x + 2;
+            let right = area.x + area.width;
+            let file = self.finder.list().candidate(m.index);
+            let mut positions = m.positions.iter().peekable();
+            for (ci, c) in file.chars().enumerate() {
+                if x >= right {
+                    break;
+                }
+                let matched = positions.next_if_eq(&&ci).is_some();
+                let style = if matched {
+                    Style::default().fg(Color::Yellow).bg(bg).bold()
+                } else {
+                    Style::default().fg(Color::White).bg(bg)
+                };
+                let (next, _) = buf.set_stringn(x, y, c.to_string(), (right - x) as usize, style);
+                x = next;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn highlights_matched_characters() {
+        let mut finder = Finder::new(PathBuf::from("/p"));
+        finder.set_files(vec!["src/app.rs".into(), "README.md".into()]);
+        for c in "app".chars() {
+            finder.list_mut().push_char(c);
+        }
+
+        let area = Rect::new(0, 0, 20, 2);
+        let mut buf = Buffer::empty(area);
+        FinderWidget::new(&finder).render(area, &mut buf);
+
+        let text: String = (0..area.width)
+            .map(|x| buf[(x, 0)].symbol().to_string())
+            .collect();
+        assert_eq!(text.trim_end(), "  src/app.rs");
+        assert_eq!(buf[(6, 0)].fg, Color::Yellow);
+        assert_eq!(buf[(2, 0)].fg, Color::White);
+        // Only one file matches
+        assert_eq!(buf[(2, 1)].symbol(), " ");
+    }
+}
@@ -0,0 +1,346 @@
+use ratatui::style::Color;
+use unicode_width::UnicodeWidthStr;
+
+use crate::tree::FileNode;
+
+/// Glyph set used for tree nodes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IconTheme {
+    /// `▸`/`▾` for directories and `·` for files.
+    #[default]
+    Plain,
+    /// Per-type glyphs from a patched Nerd Font.
+    Nerd,
+    /// Per-type emoji, two columns wide.
+    Emoji,
+    /// `+`/`-` for directories and `.` for files, for terminals without Unicode.
+    Ascii,
+}
+
+impl IconTheme {
+    pub const ALL: [IconTheme; 4] = [
+        IconTheme::Plain,
+        IconTheme::Nerd,
+        IconTheme::Emoji,
+        IconTheme::Ascii,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "plain" | "default" => Some(Self::Plain),
+            "nerd" | "nerdfont" | "nerd-font" => Some(Self::Nerd),
+            "emoji" => Some(Self::Emoji),
+            "ascii" => Some(Self::Ascii),
+            _ => None,
+        }
+    }
+
+    /// Theme names, for error messages.
+    pub fn names() -> String {
+        Self::ALL.map(Self::label).join(", ")
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Plain => "plain",
+            Self::Nerd => "nerd",
+            Self::Emoji => "emoji",
+            Self::Ascii => "ascii",
+        }
+    }
+
+    /// Columns taken by a node's icon, including the space after it.
+    pub fn column_width(self) -> u16 {
+        match self {
+            Self::Emoji => 3,
+            _ => 2,
+        }
+    }
+
+    /// Icon and colour for `node`.
+    pub fn icon(self, node: &FileNode, expanded: bool) -> NodeIcon {
+        let mapping = lookup(node);
+        let color = mapping.and_then(|m| m.color).unwrap_or(if node.is_dir {
+            DIR_COLOR
+        } else {
+            FILE_COLOR
+        });
+        let glyph = match self {
+            Self::Plain => match (node.is_dir, expanded) {
+                (true, true) => "▾",
+                (true, false) => "▸",
+                (false, _) => "·",
+            },
+            Self::Ascii => match (node.is_dir, expanded) {
+                (true, true) => "-",
+                (true, false) => "+",
+                (false, _) => ".",
+            },
+            Self::Nerd => match mapping {
+                Some(m) => m.nerd,
+                None if node.is_dir && expanded => "\u{f07c}",
+                None if node.is_dir => "\u{f07b}",
+                None => "\u{f15b}",
+            },
+            Self::Emoji => match mapping {
+                Some(m) => m.emoji,
+                None if node.is_dir && expanded => "📂",
+                None if node.is_dir => "📁",
+                None => "📄",
+            },
+        };
+        NodeIcon { glyph, color }
+    }
+
+    /// `glyph` padded to the icon column, for names to line up across icon widths.
+    pub fn pad(self, glyph: &str) -> String {
+        let width = self.column_width() as usize;
+        let pad = width.saturating_sub(glyph.width());
+        format!("{glyph}{}", " ".repeat(pad))
+    }
+}
+
+/// What a tree node is drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeIcon {
+    pub glyph: &'static str,
+    pub color: Color,
+}
+
+const DIR_COLOR: Color = Color::Rgb(209, 164, 73);
+const FILE_COLOR: Color = Color::Rgb(180, 180, 180);
+
+/// Icons for one kind of entry. The plain and ASCII themes only use the colour.
+struct Mapping {
+    nerd: &'static str,
+    emoji: &'static str,
+    color: Option<Color>,
+}
+
+const fn icon(nerd: &'static str, emoji: &'static str, color: Color) -> Mapping {
+    Mapping {
+        nerd,
+        emoji,
+        color: Some(color),
+    }
+}
+
+const fn folder(nerd: &'static str, emoji: &'static str) -> Mapping {
+    Mapping {
+        nerd,
+        emoji,
+        color: None,
+    }
+}
+
+const RUST: Color = Color::Rgb(255, 150, 50);
+const MARKDOWN: Color = Color::Rgb(100, 180, 255);
+const CONFIG: Color = Color::Rgb(180, 180, 180);
+
+/// Folders matched by exact name. They keep the directory colour.
+static FOLDERS: &[(&str, Mapping)] = &[
+    ("src", folder("\u{f121}", "🧩")),
+    ("tests", folder("\u{f0c3}", "🧪")),
+    ("test", folder("\u{f0c3}", "🧪")),
+    ("docs", folder("\u{f02d}", "📚")),
+    ("doc", folder("\u{f02d}", "📚")),
+    (".git", folder("\u{e5fb}", "🌱")),
+    (".github", folder("\u{e5fd}", "🐙")),
+    (".vscode", folder("\u{e5fc}", "🔧")),
+    (".config", folder("\u{e5fc}", "🔧")),
+    ("node_modules", folder("\u{e5fa}", "📦")),
+    ("target", folder("\u{f085}", "🔨")),
+    ("build", folder("\u{f085}", "🔨")),
+    ("dist", folder("\u{f085}", "🔨")),
+];
+
+/// Files matched by exact name, ahead of their extension.
+static FILENAMES: &[(&str, Mapping)] = &[
+    ("Cargo.toml", icon("\u{e7a8}", "🦀", RUST)),
+    ("Cargo.lock", icon("\u{e7a8}", "🦀", Color::DarkGray)),
+    (
+        "Dockerfile",
+        icon("\u{e7b0}", "🐳", Color::Rgb(50, 150, 230)),
+    ),
+    (".dockerignore", icon("\u{e7b0}", "🐳", Color::DarkGray)),
+    (".gitignore", icon("\u{e702}", "🙈", Color::DarkGray)),
+    (".gitattributes", icon("\u{e702}", "🙈", Color::DarkGray)),
+    (".cltreeignore", icon("\u{e702}", "🙈", Color::DarkGray)),
+    (".env", icon("\u{f084}", "🔑", Color::DarkGray)),
+    ("Makefile", icon("\u{e615}", "🔨", CONFIG)),
+    ("README.md", icon("\u{f02d}", "📖", MARKDOWN)),
+    ("LICENSE", icon("\u{f0e3}", "📜", Color::Rgb(220, 190, 80))),
+    ("package.json", icon("\u{e71e}", "📦", Color::LightYellow)),
+];
+
+/// Files matched by lowercased extension.
+static EXTENSIONS: &[(&[&str], Mapping)] = &[
+    (&["rs"], icon("\u{e7a8}", "🦀", RUST)),
+    (
+        &["js", "mjs", "cjs"],
+        icon("\u{e74e}", "🟨", Color::LightYellow),
+    ),
+    (
+        &["ts", "mts", "cts"],
+        icon("\u{e628}", "🟦", Color::Rgb(50, 150, 255)),
+    ),
+    (
+        &["jsx", "tsx"],
+        icon("\u{e7ba}", "🟦", Color::Rgb(100, 200, 255)),
+    ),
+    (
+        &["py", "pyw", "pyi"],
+        icon("\u{e73c}", "🐍", Color::Rgb(80, 180, 80)),
+    ),
+    (
+        &["html", "htm"],
+        icon("\u{e736}", "🌐", Color::Rgb(230, 120, 50)),
+    ),
+    (
+        &["css", "scss", "sass", "less"],
+        icon("\u{e749}", "🎨", Color::Rgb(180, 100, 255)),
+    ),
+    (
+        &["vue", "svelte"],
+        icon("\u{e6a0}", "💚", Color::LightGreen),
+    ),
+    (&["json"], icon("\u{e60b}", "📋", Color::LightYellow)),
+    (&["yaml", "yml", "toml"], icon("\u{e615}", "🔧", CONFIG)),
+    (&["xml"], icon("\u{e619}", "📰", Color::Rgb(200, 150, 50))),
+    (&["sql"], icon("\u{e706}", "💾", Color::Rgb(200, 200, 50))),
+    (&["md", "markdown"], icon("\u{e73e}", "📝", MARKDOWN)),
+    (&["txt"], icon("\u{f0f6}", "📄", CONFIG)),
+    (
+        &["sh", "bash", "zsh", "fish"],
+        icon("\u{e795}", "🐚", Color::LightGreen),
+    ),
+    (&["go"], icon("\u{e627}", "🐹", Color::Cyan)),
+    (&["java"], icon("\u{e738}", "☕", Color::Rgb(255, 100, 100))),
+    (
+        &["kt", "kts"],
+        icon("\u{e634}", "🟪", Color::Rgb(200, 120, 255)),
+    ),
+    (
+        &["c", "h"],
+        icon("\u{e61e}", "🔵", Color::Rgb(100, 150, 255)),
+    ),
+    (
+        &["cpp", "cc", "cxx", "hpp"],
+        icon("\u{e61d}", "🔵", Color::Rgb(100, 150, 255)),
+    ),
+    (&["rb"], icon("\u{e739}", "💎", Color::LightRed)),
+    (&["lock"], icon("\u{f023}", "🔒", Color::DarkGray)),
+    (
+        &["png", "jpg", "jpeg", "gif", "svg", "ico", "webp"],
+        icon("\u{f1c5}", "🌄", Color::LightMagenta),
+    ),
+];
+
+fn lookup(node: &FileNode) -> Option<&'static Mapping> {
+    let name = node.name.as_str();
+    if node.is_dir {
+        return FOLDERS
+            .iter()
+            .find(|(folder, _)| *folder == name)
+            .map(|(_, m)| m);
+    }
+    if let Some((_, m)) = FILENAMES
+        .iter()
+        .find(|(file, _)| file.eq_ignore_ascii_case(name))
+    {
+        return Some(m);
+    }
+    let ext = node.path.extension()?.to_str()?.to_ascii_lowercase();
+    EXTENSIONS
+        .iter()
+        .find(|(exts, _)| exts.contains(&ext.as_str()))
+        .map(|(_, m)| m)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn node(name: &str, is_dir: bool) -> FileNode {
+        FileNode::new(
+            PathBuf::from("/p").join(name),
+            name.to_string(),
+            1,
+            is_dir,
+            false,
+            vec![],
+        )
+    }
+
+    #[test]
+    fn lookup_prefers_filename_over_extension() {
+        let cargo = IconTheme::Emoji.icon(&node("Cargo.lock", false), false);
+        assert_eq!(cargo.glyph, "🦀");
+        assert_eq!(cargo.color, Color::DarkGray);
+        let lock = IconTheme::Emoji.icon(&node("yarn.lock", false), false);
+        assert_eq!(lock.glyph, "🔒");
+        assert_eq!(
+            IconTheme::Nerd
+                .icon(&node("Dockerfile", false), false)
+                .glyph,
+            "\u{e7b0}"
+        );
+        assert_eq!(
+            IconTheme::Emoji.icon(&node("MAIN.RS", false), false).glyph,
+            "🦀"
+        );
+    }
+
+    #[test]
+    fn folders_keep_their_colour_and_fall_back_to_open_state() {
+        let github = IconTheme::Emoji.icon(&node(".github", true), false);
+        assert_eq!(github.glyph, "🐙");
+        assert_eq!(github.color, DIR_COLOR);
+        assert_eq!(IconTheme::Emoji.icon(&node("misc", true), true).glyph, "📂");
+        // A file named like a special folder is just a file
+        assert_eq!(
+            IconTheme::Emoji.icon(&node("src", false), false).glyph,
+            "📄"
+        );
+        assert_eq!(IconTheme::Plain.icon(&node("src", true), true).glyph, "▾");
+        assert_eq!(
+            IconTheme::Ascii.icon(&node("a.rs", false), false).color,
+            RUST
+        );
+    }
+
+    #[test]
+    fn every_glyph_fits_its_column() {
+        let nerd = FOLDERS
+            .iter()
+            .chain(FILENAMES)
+            .map(|(_, m)| m)
+            .chain(EXTENSIONS.iter().map(|(_, m)| m));
+        for m in nerd {
+            assert_eq!(m.nerd.width(), 1, "{:?}", m.nerd);
+            assert_eq!(m.emoji.width(), 2, "{:?}", m.emoji);
+        }
+        for theme in IconTheme::ALL {
+            for (name, is_dir, expanded) in
+                [("x", true, true), ("x", true, false), ("x", false, false)]
+            {
+                let glyph = theme.icon(&node(name, is_dir), expanded).glyph;
+                assert_eq!(
+                    theme.pad(glyph).width(),
+                    theme.column_width() as usize,
+                    "{theme:?} {glyph:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn parse_accepts_labels_and_aliases() {
+        for theme in IconTheme::ALL {
+            assert_eq!(IconTheme::parse(theme.label()), Some(theme));
+        }
+        assert_eq!(IconTheme::parse("Nerd-Font"), Some(IconTheme::Nerd));
+        assert_eq!(IconTheme::parse("fancy"), None);
+    }
+}
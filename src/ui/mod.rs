@@ -1,34 +1,133 @@
+mod changes_widget;
+mod columns;
+mod diff_widget;
 mod file_tree_widget;
+mod finder_widget;
+mod icons;
+mod palette_widget;
+mod panes;
+mod preview_widget;
+mod status_bar;
 mod terminal_widget;
 
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph},
 };
 
 use crate::app::App;
+use crate::preview::PreviewBody;
+use changes_widget::ChangesWidget;
+pub use columns::TreeColumns;
+use diff_widget::DiffWidget;
+pub use file_tree_widget::icon_column;
 use file_tree_widget::FileTreeWidget;
+use finder_widget::FinderWidget;
+pub use icons::IconTheme;
+use palette_widget::PaletteWidget;
+pub use panes::{Panes, TreeSide, Zoom, DEFAULT_STACK_BELOW};
+use preview_widget::PreviewWidget;
+pub use status_bar::Segment;
+use status_bar::StatusInfo;
 use terminal_widget::TerminalWidget;
 
 pub fn draw(frame: &mut Frame, app: &mut App) {
-    let size = frame.area();
-
-    // Main layout: tree on right, terminal on left
-    let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage(100 - app.tree_width_percent),
-            Constraint::Percentage(app.tree_width_percent),
-        ])
-        .split(size);
-
-    // Terminal pane (left/main area)
-    let terminal_area = chunks[0];
+    app.sync_zoom();
+    let mut screen = frame.area();
+    if app.status_bar_shown && !app.status_segments.is_empty() && screen.height > 2 {
+        let [rest, bar] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(screen);
+        draw_status_bar(frame, app, bar);
+        screen = rest;
+    }
+    let panes = Panes::split(
+        screen,
+        app.tree_side,
+        app.tree_width_percent,
+        app.zoom,
+        app.stack_below,
+    );
+    // Kept for mouse hit-testing, including dragging the border between the panes
+    app.panes = Some(panes);
+
+    let terminal_inner = match panes.terminal {
+        Some(area) => Some(draw_terminal(frame, app, area)),
+        None => {
+            app.terminal_area = None;
+            None
+        }
+    };
+    let tree_inner = match panes.tree {
+        Some(area) => Some(draw_tree(frame, app, area, panes.side)),
+        None => {
+            app.tree_area = None;
+            app.preview_area = None;
+            None
+        }
+    };
+
+    // On the terminal's bottom border, or the tree's while the terminal is hidden
+    if let Some(status) = app.status_line() {
+        if let Some(inner) = terminal_inner.or(tree_inner) {
+            draw_border_status(frame, inner, &status);
+        }
+    }
+
+    if app.diff_view.is_some() {
+        draw_diff(frame, app);
+    }
+
+    if app.finder.is_some() {
+        draw_finder(frame, app);
+    }
+
+    if app.palette.is_some() {
+        draw_palette(frame, app);
+    }
+
+    if let Some(text) = app.pending_paste.as_deref() {
+        draw_paste_prompt(frame, text);
+    }
+}
+
+/// One row along the bottom of the screen with the configured segments.
+fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
+    let line = StatusInfo::gather(app).line(&app.status_segments);
+    let bar = Paragraph::new(line).style(Style::default().bg(Color::Rgb(30, 30, 30)));
+    frame.render_widget(bar, area);
+}
+
+/// Draw the terminal pane in `area` and return the area inside its border.
+fn draw_terminal(frame: &mut Frame, app: &mut App, terminal_area: Rect) -> Rect {
+    // Show input that the child has not read yet (e.g. a big paste into a busy process)
+    let pending = app.terminal.pending_input();
+    let terminal_title = if app.terminal.input_recently_rejected() {
+        " Claude Code · input dropped: queue full ".to_string()
+    } else if pending > 0 {
+        format!(" Claude Code · sending {} ", format_bytes(pending))
+    } else {
+        " Claude Code ".to_string()
+    };
     let terminal_block = Block::default()
-        .title(" Claude Code ")
+        .title(terminal_title)
         .title_style(Style::default().fg(Color::Cyan).bold())
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Cyan));
+    let terminal_block = match &app.scroll_search {
+        Some(search) => {
+            let position = match search.current {
+                Some(i) => format!(" {}/{} ", i + 1, search.matches.len()),
+                None if search.text.is_empty() => " ".to_string(),
+                None => " no matches ".to_string(),
+            };
+            terminal_block.title_bottom(Line::from(vec![
+                Span::styled(" search: ", Style::default().fg(Color::Yellow)),
+                Span::raw(search.text.clone()),
+                Span::styled(position, Style::default().fg(Color::DarkGray)),
+            ]))
+        }
+        None => terminal_block,
+    };
 
     let terminal_inner = terminal_block.inner(terminal_area);
     frame.render_widget(terminal_block, terminal_area);
@@ -40,14 +139,23 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
     // Store terminal area for mouse drag routing
     app.terminal_area = Some(terminal_inner);
 
-    let terminal_widget = TerminalWidget::new(&app.terminal, app.selection.as_ref());
+    let terminal_widget = TerminalWidget::new(&app.terminal, app.selection.as_ref())
+        .highlight(app.search_match_row());
     frame.render_widget(terminal_widget, terminal_inner);
 
     // Set hardware blinking cursor position (terminal always focused)
     {
         let vterm = app.terminal.vterm_lock();
         let cursor = vterm.cursor();
-        if cursor.visible {
+        if cursor.visible
+            && app.pending_paste.is_none()
+            && app.finder.is_none()
+            && app.diff_view.is_none()
+            && app.filter_input.is_none()
+            && app.palette.is_none()
+            && app.scroll_search.is_none()
+            && !app.tree_focus
+        {
             let cx =
                 terminal_inner.x + (cursor.x as u16).min(terminal_inner.width.saturating_sub(1));
             let cy =
@@ -60,33 +168,98 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
         }
     }
 
-    // File tree pane (right side)
-    let tree_area = chunks[1];
+    terminal_inner
+}
 
-    let tree_title = format!(
-        " {} ",
-        app.tree
-            .root_path()
-            .file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_else(|| app.tree.root_path().to_string_lossy().to_string())
-    );
+/// Draw the file tree pane, and the preview within it, and return the area inside the tree's border.
+fn draw_tree(frame: &mut Frame, app: &mut App, mut tree_area: Rect, side: TreeSide) -> Rect {
+    if app.preview_open {
+        // Beside a stacked tree, below one at the side
+        let direction = if side.is_stacked() {
+            Direction::Horizontal
+        } else {
+            Direction::Vertical
+        };
+        let [tree, preview] = Layout::new(
+            direction,
+            [Constraint::Percentage(50), Constraint::Percentage(50)],
+        )
+        .areas(tree_area);
+        tree_area = tree;
+        draw_preview(frame, app, preview);
+    } else {
+        app.preview_area = None;
+    }
+
+    let tree_title = if app.changes_view {
+        format!(" Changed this session ({}) ", app.changes.len())
+    } else {
+        format!(
+            " {} · {} ",
+            app.tree
+                .root_path()
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| app.tree.root_path().to_string_lossy().to_string()),
+            app.tree.sort().label()
+        )
+    };
 
     let tree_block = Block::default()
         .title(tree_title)
         .title_style(Style::default().fg(Color::Yellow).bold())
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(Style::default().fg(if app.tree_focus {
+            Color::Yellow
+        } else {
+            Color::DarkGray
+        }));
+
+    let filter_prefix = " filter: ";
+    let tree_block = match (&app.filter_input, app.tree.filter()) {
+        (Some(input), _) => {
+            let color = if input.invalid {
+                Color::Red
+            } else {
+                Color::White
+            };
+            tree_block.title_bottom(Line::from(vec![
+                Span::styled(filter_prefix, Style::default().fg(Color::Yellow)),
+                Span::styled(format!("{} ", input.text), Style::default().fg(color)),
+            ]))
+        }
+        (None, Some(filter)) if !app.changes_view => tree_block.title_bottom(Line::from(vec![
+            Span::styled(filter_prefix, Style::default().fg(Color::Yellow)),
+            Span::raw(filter.expr().to_string()),
+            Span::styled(
+                format!(" · {} hidden ", app.tree.filter_hidden()),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ])),
+        _ => tree_block,
+    };
 
     let tree_inner = tree_block.inner(tree_area);
     frame.render_widget(tree_block, tree_area);
+    if let Some(input) = &app.filter_input {
+        let width = unicode_width::UnicodeWidthStr::width(filter_prefix)
+            + unicode_width::UnicodeWidthStr::width(input.text.as_str());
+        let x = (tree_area.x + 1 + width as u16).min(tree_area.right().saturating_sub(2));
+        frame.set_cursor_position((x, tree_area.bottom().saturating_sub(1)));
+    }
 
     // Store tree area for mouse scroll routing
     app.tree_area = Some(tree_inner);
 
-    if app.tree_loading {
-        let loading =
-            Paragraph::new("  Scanning files...").style(Style::default().fg(Color::DarkGray));
+    if app.changes_view {
+        let changes = ChangesWidget::new(&app.changes, app.tree.root_path(), app.changes_offset);
+        frame.render_widget(changes, tree_inner);
+    } else if app.tree_loading {
+        let text = match app.scan_progress() {
+            Some(entries) if entries > 0 => format!("  Scanning files... {entries} entries"),
+            _ => "  Scanning files...".to_string(),
+        };
+        let loading = Paragraph::new(text).style(Style::default().fg(Color::DarkGray));
         frame.render_widget(loading, tree_inner);
     } else {
         // Auto-scroll to keep CWD visible — only when CWD actually changes
@@ -98,11 +271,9 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
             .is_none_or(|last| last.as_path() != cwd);
 
         if cwd_changed {
-            let cwd_index = app
-                .tree
-                .nodes()
-                .iter()
-                .position(|n| n.is_dir && n.path == cwd);
+            // Big trees start partly collapsed; make sure the CWD marker is visible
+            app.tree.reveal(cwd);
+            let cwd_index = app.tree.nodes().iter().position(|n| n.shows_dir(cwd));
 
             if let Some(idx) = cwd_index {
                 let mut offset = app.tree.offset();
@@ -117,7 +288,11 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
         }
 
         // Render file tree
-        let file_tree_widget = FileTreeWidget::new(&app.tree, Some(app.terminal.cwd()));
+        let file_tree_widget = FileTreeWidget::new(&app.tree, Some(app.terminal.cwd()))
+            .show_selection(app.tree_focus)
+            .changes(&app.changes)
+            .icons(app.icons)
+            .columns(app.shown_columns());
         frame.render_stateful_widget(
             file_tree_widget,
             tree_inner,
@@ -126,6 +301,243 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
             },
         );
     }
+
+    tree_inner
+}
+
+fn draw_preview(frame: &mut Frame, app: &mut App, area: Rect) {
+    let title = app
+        .preview
+        .as_ref()
+        .and_then(|p| p.path().file_name())
+        .map(|name| format!(" {} ", name.to_string_lossy()))
+        .unwrap_or_else(|| " Preview ".to_string());
+    let mut block = Block::default()
+        .title(title)
+        .title_style(Style::default().fg(Color::Yellow).bold())
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray));
+    let inner = block.inner(area);
+    app.preview_area = Some(inner);
+
+    let Some(preview) = app.preview.as_mut() else {
+        frame.render_widget(block, area);
+        let hint = Paragraph::new("  Hover a file, or select one in tree focus")
+            .style(Style::default().fg(Color::DarkGray));
+        frame.render_widget(hint, inner);
+        return;
+    };
+    let scroll = preview.scroll();
+    if let PreviewBody::Text(text) = preview.body_mut() {
+        text.prepare(scroll + inner.height as usize);
+        if text.line_count() > 0 {
+            let position = format!(" {}/{} ", scroll + 1, text.line_count());
+            block = block.title_bottom(Line::from(position).right_aligned());
+        }
+    }
+    frame.render_widget(block, area);
+    frame.render_widget(PreviewWidget::new(preview), inner);
+}
+
+fn draw_diff(frame: &mut Frame, app: &mut App) {
+    let Some(view) = app.diff_view.as_ref() else {
+        return;
+    };
+    let screen = frame.area();
+    let area = centered_rect(screen.width * 9 / 10, screen.height * 9 / 10, screen);
+    let diff = &view.diff;
+    let path = diff
+        .path
+        .strip_prefix(app.tree.root_path())
+        .unwrap_or(&diff.path);
+    let (added, removed) = diff.stats();
+    let title = Line::from(vec![
+        Span::styled(
+            format!(" {} ", path.display()),
+            Style::default().fg(Color::Yellow).bold(),
+        ),
+        Span::styled(
+            format!("vs {} ", diff.source.label()),
+            Style::default().fg(Color::DarkGray),
+        ),
+        Span::styled(format!("+{added} "), Style::default().fg(Color::Green)),
+        Span::styled(format!("-{removed} "), Style::default().fg(Color::Red)),
+    ]);
+    let hunks = if diff.hunks.is_empty() {
+        String::new()
+    } else {
+        format!(" hunk {}/{} ·", view.current_hunk() + 1, diff.hunks.len())
+    };
+    let hints =
+        format!("{hunks} [n/p] hunk  [s] side-by-side  [a] accept as baseline  [Esc] close ");
+    let block = Block::default()
+        .title(title)
+        .title_bottom(Line::from(hints).right_aligned())
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    frame.render_widget(DiffWidget::new(view), inner);
+    app.diff_area = Some(inner);
+}
+
+fn draw_finder(frame: &mut Frame, app: &mut App) {
+    let Some(finder) = app.finder.as_ref() else {
+        return;
+    };
+    let screen = frame.area();
+    let area = centered_rect(
+        (screen.width * 4 / 5).min(100),
+        (screen.height * 3 / 4).min(30),
+        screen,
+    );
+    let counts = if finder.marked_count() > 0 {
+        format!(
+            " {}/{} · {} marked ",
+            finder.list().matches().len(),
+            finder.list().candidate_count(),
+            finder.marked_count()
+        )
+    } else {
+        format!(
+            " {}/{} ",
+            finder.list().matches().len(),
+            finder.list().candidate_count()
+        )
+    };
+    let block = Block::default()
+        .title(" Find file ")
+        .title_style(Style::default().fg(Color::Yellow).bold())
+        .title_bottom(Line::from(" [Tab] mark  [Enter] insert @path  [Esc] close ").right_aligned())
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    if inner.height < 2 {
+        return;
+    }
+
+    let query_area = Rect::new(inner.x, inner.y, inner.width, 1);
+    // Counts first: the query is drawn over them if it grows that long
+    frame.render_widget(
+        Paragraph::new(counts)
+            .alignment(Alignment::Right)
+            .style(Style::default().fg(Color::DarkGray)),
+        query_area,
+    );
+    let query = Line::from(vec![
+        Span::styled("> ", Style::default().fg(Color::Cyan).bold()),
+        Span::raw(finder.list().query()),
+    ]);
+    frame.render_widget(Paragraph::new(query), query_area);
+    let query_width = unicode_width::UnicodeWidthStr::width(finder.list().query()) as u16;
+    frame.set_cursor_position((
+        (inner.x + 2 + query_width).min(inner.x + inner.width - 1),
+        inner.y,
+    ));
+
+    let list = Rect::new(inner.x, inner.y + 1, inner.width, inner.height - 1);
+    frame.render_widget(FinderWidget::new(finder), list);
+    app.finder_area = Some(list);
+}
+
+fn draw_palette(frame: &mut Frame, app: &mut App) {
+    let Some(palette) = app.palette.as_ref() else {
+        return;
+    };
+    let screen = frame.area();
+    let area = centered_rect(
+        (screen.width * 3 / 5).min(70),
+        (screen.height * 3 / 4).min(palette.item_count() as u16 + 3),
+        screen,
+    );
+    let block = Block::default()
+        .title(" Commands ")
+        .title_style(Style::default().fg(Color::Yellow).bold())
+        .title_bottom(Line::from(" [Enter] run  [Esc] close ").right_aligned())
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    if inner.height < 2 {
+        return;
+    }
+
+    let query_area = Rect::new(inner.x, inner.y, inner.width, 1);
+    let query = Line::from(vec![
+        Span::styled("> ", Style::default().fg(Color::Cyan).bold()),
+        Span::raw(palette.list().query()),
+    ]);
+    frame.render_widget(Paragraph::new(query), query_area);
+    let query_width = unicode_width::UnicodeWidthStr::width(palette.list().query()) as u16;
+    frame.set_cursor_position((
+        (inner.x + 2 + query_width).min(inner.x + inner.width - 1),
+        inner.y,
+    ));
+
+    let list = Rect::new(inner.x, inner.y + 1, inner.width, inner.height - 1);
+    frame.render_widget(PaletteWidget::new(palette), list);
+    app.palette_area = Some(list);
+}
+
+/// Draw a one-line status over the bottom border of a pane, given its inner area.
+pub fn draw_border_status(frame: &mut Frame, inner: Rect, text: &str) {
+    let area = Rect::new(inner.x, inner.y + inner.height, inner.width, 1);
+    let status =
+        Paragraph::new(text.to_string()).style(Style::default().fg(Color::Black).bg(Color::Cyan));
+    frame.render_widget(status, area.intersection(frame.area()));
+}
+
+fn draw_paste_prompt(frame: &mut Frame, text: &str) {
+    let lines = text.lines().count();
+    let body = vec![
+        Line::from(format!(
+            "Paste {lines} lines ({}) into Claude?",
+            format_bytes(text.len())
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("[y/Enter]", Style::default().fg(Color::Cyan).bold()),
+            Span::raw(" paste   "),
+            Span::styled("[n/Esc]", Style::default().fg(Color::Cyan).bold()),
+            Span::raw(" cancel"),
+        ]),
+    ];
+    let area = centered_rect(44, body.len() as u16 + 2, frame.area());
+    let popup = Paragraph::new(body).alignment(Alignment::Center).block(
+        Block::default()
+            .title(" Confirm paste ")
+            .title_style(Style::default().fg(Color::Yellow).bold())
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// A rect of at most `width` x `height` centred in `area`.
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+fn format_bytes(n: usize) -> String {
+    if n >= 1024 * 1024 {
+        format!("{:.1} MB", n as f64 / (1024.0 * 1024.0))
+    } else if n >= 1024 {
+        format!("{:.1} KB", n as f64 / 1024.0)
+    } else {
+        format!("{n} B")
+    }
 }
 
 pub struct FileTreeWidgetState {
@@ -0,0 +1,111 @@
+use ratatui::prelude::*;
+use unicode_width::UnicodeWidthStr;
+
+use crate::actions::Palette;
+
+const SELECTED_BG: Color = Color::Rgb(40, 60, 90);
+
+/// Matching actions of the command palette, each with its key binding on the right.
+pub struct PaletteWidget<'a> {
+    palette: &'a Palette,
+}
+
+impl<'a> PaletteWidget<'a> {
+    pub fn new(palette: &'a Palette) -> Self {
+        Self { palette }
+    }
+}
+
+impl Widget for PaletteWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width < 4 || area.height == 0 {
+            return;
+        }
+        if self.palette.list().matches().is_empty() {
+            let dim = Style::default().fg(Color::DarkGray);
+            buf.set_string(area.x, area.y, "  No matching actions", dim);
+            return;
+        }
+
+        let rows = self
+            .palette
+            .list()
+            .matches()
+            .iter()
+            .enumerate()
+            .skip(self.palette.list().offset())
+            .take(area.height as usize);
+        for (i, (row, m)) in rows.enumerate() {
+            let y = area.y + i as u16;
+            let selected = row == self.palette.list().selected();
+            let bg = if selected { SELECTED_BG } else { Color::Reset };
+            if selected {
+                for x in area.x..area.x + area.width {
+                    if let Some(cell) = buf.cell_mut((x, y)) {
+                        cell.set_bg(bg);
+                    }
+                }
+            }
+
+            let item = self.palette.item(m.index);
+            // The binding goes first so a long name is cut short instead of it
+            let binding_width = item.binding.width() as u16;
+            let right = if binding_width + 6 <= area.width {
+                let x = area.x + area.width - binding_width - 1;
+                buf.set_string(
+                    x,
+                    y,
+                    &item.binding,
+                    Style::default().fg(Color::DarkGray).bg(bg),
+                );
+                x.saturating_sub(1)
+            } else {
+                area.x + area.width
+            };
+
+            let mut x = area.x + 2;
+            let mut positions = m.positions.iter().peekable();
+            for (ci, c) in item.name.chars().enumerate() {
+                if x >= right {
+                    break;
+                }
+                let matched = positions.next_if_eq(&&ci).is_some();
+                let style = if matched {
+                    Style::default().fg(Color::Yellow).bg(bg).bold()
+                } else {
+                    Style::default().fg(Color::White).bg(bg)
+                };
+                let (next, _) = buf.set_stringn(x, y, c.to_string(), (right - x) as usize, style);
+                x = next;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::ActionRegistry;
+    use crate::keys::KeyChord;
+
+    #[test]
+    fn shows_bindings_beside_names() {
+        let registry = ActionRegistry::builtin();
+        let mut palette = Palette::new(&registry, KeyChord::default(), None);
+        for c in "dotfiles".chars() {
+            palette.list_mut().push_char(c);
+        }
+
+        let area = Rect::new(0, 0, 40, 2);
+        let mut buf = Buffer::empty(area);
+        PaletteWidget::new(&palette).render(area, &mut buf);
+
+        let text: String = (0..area.width)
+            .map(|x| buf[(x, 0)].symbol().to_string())
+            .collect();
+        assert!(text.starts_with("  Toggle dotfiles"));
+        assert!(text.trim_end().ends_with("Ctrl+] ."));
+        assert_eq!(buf[(9, 0)].fg, Color::Yellow);
+        assert_eq!(buf[(2, 0)].fg, Color::White);
+    }
+}
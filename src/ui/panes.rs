@@ -0,0 +1,226 @@
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+
+/// Screens narrower than this stack the tree above or below the terminal.
+pub const DEFAULT_STACK_BELOW: u16 = 100;
+
+/// Where the file tree sits next to the terminal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TreeSide {
+    Left,
+    #[default]
+    Right,
+    Top,
+    Bottom,
+}
+
+impl TreeSide {
+    pub const ALL: [TreeSide; 4] = [
+        TreeSide::Left,
+        TreeSide::Right,
+        TreeSide::Top,
+        TreeSide::Bottom,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "left" => Some(Self::Left),
+            "right" => Some(Self::Right),
+            "top" => Some(Self::Top),
+            "bottom" => Some(Self::Bottom),
+            _ => None,
+        }
+    }
+
+    /// Side names, for error messages.
+    pub fn names() -> String {
+        Self::ALL.map(Self::label).join(", ")
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Left => "left",
+            Self::Right => "right",
+            Self::Top => "top",
+            Self::Bottom => "bottom",
+        }
+    }
+
+    /// The tree is above or below the terminal rather than beside it.
+    pub fn is_stacked(self) -> bool {
+        matches!(self, Self::Top | Self::Bottom)
+    }
+
+    /// Where a side-by-side tree goes when the screen is too narrow for it.
+    fn stacked(self) -> Self {
+        match self {
+            Self::Left => Self::Top,
+            Self::Right => Self::Bottom,
+            other => other,
+        }
+    }
+}
+
+/// Which panes are on screen; a zoomed pane takes the whole screen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Zoom {
+    #[default]
+    Off,
+    /// Only the terminal; the tree is hidden.
+    Terminal,
+    /// Only the tree.
+    Tree,
+}
+
+impl Zoom {
+    /// Off, then the terminal alone, then the tree alone.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Terminal,
+            Self::Terminal => Self::Tree,
+            Self::Tree => Self::Off,
+        }
+    }
+}
+
+/// Where the terminal and tree panes are drawn, kept by the app for mouse hit-testing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Panes {
+    pub screen: Rect,
+    pub terminal: Option<Rect>,
+    pub tree: Option<Rect>,
+    /// Where the tree ended up, after stacking on a narrow screen.
+    pub side: TreeSide,
+}
+
+impl Panes {
+    /// Split `screen` with the tree taking `percent` of it on `side`. Screens
+    /// narrower than `stack_below` columns stack the panes instead.
+    pub fn split(screen: Rect, side: TreeSide, percent: u16, zoom: Zoom, stack_below: u16) -> Self {
+        let side = if screen.width < stack_below {
+            side.stacked()
+        } else {
+            side
+        };
+        let (terminal, tree) = match zoom {
+            Zoom::Terminal => (Some(screen), None),
+            Zoom::Tree => (None, Some(screen)),
+            Zoom::Off => {
+                let tree = Constraint::Percentage(percent);
+                let terminal = Constraint::Percentage(100 - percent.min(100));
+                let (direction, tree_first) = match side {
+                    TreeSide::Left => (Direction::Horizontal, true),
+                    TreeSide::Right => (Direction::Horizontal, false),
+                    TreeSide::Top => (Direction::Vertical, true),
+                    TreeSide::Bottom => (Direction::Vertical, false),
+                };
+                let constraints = if tree_first {
+                    [tree, terminal]
+                } else {
+                    [terminal, tree]
+                };
+                let chunks = Layout::default()
+                    .direction(direction)
+                    .constraints(constraints)
+                    .split(screen);
+                if tree_first {
+                    (Some(chunks[1]), Some(chunks[0]))
+                } else {
+                    (Some(chunks[0]), Some(chunks[1]))
+                }
+            }
+        };
+        Self {
+            screen,
+            terminal,
+            tree,
+            side,
+        }
+    }
+
+    /// Whether a cell is on one of the two borders where the panes meet.
+    pub fn on_border(&self, column: u16, row: u16) -> bool {
+        let (Some(tree), Some(_)) = (self.tree, self.terminal) else {
+            return false;
+        };
+        let rows = tree.y..tree.bottom();
+        let columns = tree.x..tree.right();
+        match self.side {
+            TreeSide::Right => {
+                (tree.x.saturating_sub(1)..=tree.x).contains(&column) && rows.contains(&row)
+            }
+            TreeSide::Left => {
+                (tree.right().saturating_sub(1)..=tree.right()).contains(&column)
+                    && rows.contains(&row)
+            }
+            TreeSide::Bottom => {
+                (tree.y.saturating_sub(1)..=tree.y).contains(&row) && columns.contains(&column)
+            }
+            TreeSide::Top => {
+                (tree.bottom().saturating_sub(1)..=tree.bottom()).contains(&row)
+                    && columns.contains(&column)
+            }
+        }
+    }
+
+    /// The tree's share of the screen, in percent, with its border dragged to a cell.
+    pub fn tree_percent_at(&self, column: u16, row: u16) -> u16 {
+        let s = self.screen;
+        let (size, total) = match self.side {
+            TreeSide::Right => (s.right().saturating_sub(column), s.width),
+            TreeSide::Left => ((column + 1).saturating_sub(s.x), s.width),
+            TreeSide::Bottom => (s.bottom().saturating_sub(row), s.height),
+            TreeSide::Top => ((row + 1).saturating_sub(s.y), s.height),
+        };
+        if total == 0 {
+            return 0;
+        }
+        ((size as u32 * 100 + total as u32 / 2) / total as u32) as u16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tree_goes_where_asked() {
+        let screen = Rect::new(0, 0, 200, 50);
+        let panes = Panes::split(screen, TreeSide::Left, 30, Zoom::Off, DEFAULT_STACK_BELOW);
+        assert_eq!(panes.tree, Some(Rect::new(0, 0, 60, 50)));
+        assert_eq!(panes.terminal, Some(Rect::new(60, 0, 140, 50)));
+
+        let panes = Panes::split(screen, TreeSide::Bottom, 30, Zoom::Off, DEFAULT_STACK_BELOW);
+        assert_eq!(panes.tree, Some(Rect::new(0, 35, 200, 15)));
+        assert_eq!(panes.terminal, Some(Rect::new(0, 0, 200, 35)));
+    }
+
+    #[test]
+    fn narrow_screens_stack_and_zoom_hides_a_pane() {
+        let screen = Rect::new(0, 0, 80, 40);
+        let panes = Panes::split(screen, TreeSide::Right, 25, Zoom::Off, DEFAULT_STACK_BELOW);
+        assert_eq!(panes.side, TreeSide::Bottom);
+        assert_eq!(panes.tree, Some(Rect::new(0, 30, 80, 10)));
+        let panes = Panes::split(screen, TreeSide::Right, 25, Zoom::Off, 0);
+        assert_eq!(panes.side, TreeSide::Right);
+
+        let panes = Panes::split(screen, TreeSide::Right, 25, Zoom::Terminal, 0);
+        assert_eq!((panes.terminal, panes.tree), (Some(screen), None));
+        assert!(!panes.on_border(59, 0));
+        let panes = Panes::split(screen, TreeSide::Right, 25, Zoom::Tree, 0);
+        assert_eq!((panes.terminal, panes.tree), (None, Some(screen)));
+    }
+
+    #[test]
+    fn border_follows_the_layout() {
+        let screen = Rect::new(0, 0, 200, 50);
+        let right = Panes::split(screen, TreeSide::Right, 30, Zoom::Off, 0);
+        assert!(right.on_border(139, 10) && right.on_border(140, 10));
+        assert!(!right.on_border(141, 10));
+        assert_eq!(right.tree_percent_at(120, 10), 40);
+
+        let top = Panes::split(screen, TreeSide::Top, 30, Zoom::Off, 0);
+        assert!(top.on_border(5, 14) && top.on_border(5, 15));
+        assert!(!top.on_border(5, 16));
+        assert_eq!(top.tree_percent_at(5, 19), 40);
+    }
+}
@@ -0,0 +1,107 @@
+use ratatui::prelude::*;
+
+use super::format_bytes;
+use crate::preview::{Preview, PreviewBody, MAX_PREVIEW_BYTES};
+
+/// File contents with line numbers, starting at the preview's scroll position.
+/// Text must have been prepared up to the last visible line to be highlighted.
+pub struct PreviewWidget<'a> {
+    preview: &'a Preview,
+}
+
+impl<'a> PreviewWidget<'a> {
+    pub fn new(preview: &'a Preview) -> Self {
+        Self { preview }
+    }
+}
+
+impl Widget for PreviewWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+        let dim = Style::default().fg(Color::DarkGray);
+        let message = match self.preview.body() {
+            PreviewBody::Text(_) => None,
+            PreviewBody::Binary(size) => {
+                Some(format!("  Binary file ({})", format_bytes(*size as usize)))
+            }
+            PreviewBody::TooLarge(size) => Some(format!(
+                "  Too large to preview ({}, limit {})",
+                format_bytes(*size as usize),
+                format_bytes(MAX_PREVIEW_BYTES as usize)
+            )),
+            PreviewBody::Unreadable(error) => Some(format!("  Cannot read file: {error}")),
+        };
+        if let Some(message) = message {
+            buf.set_stringn(area.x, area.y, message, area.width as usize, dim);
+            return;
+        }
+        let PreviewBody::Text(text) = self.preview.body() else {
+            return;
+        };
+        if text.line_count() == 0 {
+            buf.set_stringn(area.x, area.y, "  Empty file", area.width as usize, dim);
+            return;
+        }
+
+        let start = self.preview.scroll();
+        let end = start + area.height as usize;
+        let digits = text.line_count().to_string().len() as u16;
+        let gutter = digits + 3;
+        let right = area.x + area.width;
+        for (i, line) in text.lines(start, end).enumerate() {
+            let y = area.y + i as u16;
+            let number = format!("{:>width$} │ ", start + i + 1, width = digits as usize);
+            buf.set_stringn(area.x, y, number, area.width as usize, dim);
+            if gutter >= area.width {
+                continue;
+            }
+            buf.set_line(area.x + gutter, y, &line, right - area.x - gutter);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(preview: &Preview, width: u16, height: u16) -> Vec<String> {
+        let area = Rect::new(0, 0, width, height);
+        let mut buf = Buffer::empty(area);
+        PreviewWidget::new(preview).render(area, &mut buf);
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buf[(x, y)].symbol().to_string())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn numbers_lines_from_the_scroll_position() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("notes.txt");
+        let body: String = (1..=12).map(|n| format!("line {n}\n")).collect();
+        std::fs::write(&path, body).unwrap();
+
+        let mut preview = Preview::load(path);
+        preview.scroll_by(9);
+        assert_eq!(
+            render(&preview, 20, 4),
+            vec!["10 │ line 10", "11 │ line 11", "12 │ line 12", ""]
+        );
+    }
+
+    #[test]
+    fn explains_binary_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("image.png");
+        std::fs::write(&path, [0x89, b'P', b'N', b'G', 0, 0]).unwrap();
+        let preview = Preview::load(path);
+        assert_eq!(render(&preview, 30, 1), vec!["  Binary file (6 B)"]);
+    }
+}
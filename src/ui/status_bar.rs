@@ -0,0 +1,259 @@
+use anyhow::{bail, Result};
+use ratatui::prelude::*;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::app::App;
+use crate::tree::BranchInfo;
+
+/// A part of the status bar along the bottom of the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment {
+    /// What keys currently do: normal, command, tree, changes, diff or search.
+    Mode,
+    /// Claude's working directory, relative to the tree root.
+    Cwd,
+    /// Branch, commits ahead of and behind its upstream, and uncommitted changes.
+    Git,
+    /// Claude's PID and how long it has been running.
+    Process,
+    /// How far the terminal is scrolled back; only shown while scrolled.
+    Scroll,
+}
+
+impl Segment {
+    /// The default bar, left to right.
+    pub const ALL: [Segment; 5] = [
+        Segment::Mode,
+        Segment::Cwd,
+        Segment::Git,
+        Segment::Process,
+        Segment::Scroll,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "mode" => Some(Self::Mode),
+            "cwd" | "dir" => Some(Self::Cwd),
+            "git" | "branch" => Some(Self::Git),
+            "process" | "pid" => Some(Self::Process),
+            "scroll" => Some(Self::Scroll),
+            _ => None,
+        }
+    }
+
+    /// Parse a comma-separated list such as `mode,git`, keeping its order.
+    /// An empty list is valid and hides the bar.
+    pub fn parse_list(list: &str) -> Result<Vec<Self>> {
+        let mut segments = Vec::new();
+        for name in list.split(',').filter(|n| !n.trim().is_empty()) {
+            match Self::parse(name) {
+                Some(segment) if !segments.contains(&segment) => segments.push(segment),
+                Some(_) => {}
+                None => bail!(
+                    "unknown status bar segment '{}' (expected mode, cwd, git, process or scroll)",
+                    name.trim()
+                ),
+            }
+        }
+        Ok(segments)
+    }
+}
+
+/// What the status bar shows, gathered from the app once per frame.
+#[derive(Debug, Clone, Default)]
+pub struct StatusInfo {
+    pub mode: &'static str,
+    /// Claude's directory relative to the tree root; `.` at the root.
+    pub cwd: String,
+    pub branch: Option<BranchInfo>,
+    /// Tracked files with changes, and untracked entries.
+    pub changed: usize,
+    pub untracked: usize,
+    pub pid: Option<u32>,
+    pub run_time: Option<Duration>,
+    /// Lines scrolled back and lines of scrollback, while scrolled.
+    pub scroll: Option<(usize, usize)>,
+}
+
+impl StatusInfo {
+    pub fn gather(app: &App) -> Self {
+        let root = app.tree.root_path();
+        let git = app.tree.git();
+        let scroll = {
+            let vterm = app.terminal.vterm_lock();
+            let offset = vterm.scroll_offset();
+            (offset > 0).then(|| (offset, vterm.scrollback().len()))
+        };
+        Self {
+            mode: app.mode_label(),
+            cwd: relative_cwd(root, app.terminal.cwd()),
+            branch: git.and_then(|git| git.branch().cloned()),
+            changed: git.map_or(0, |git| git.changed()),
+            untracked: git.map_or(0, |git| git.untracked()),
+            pid: app.terminal.child_pid(),
+            run_time: app.terminal.run_time(),
+            scroll,
+        }
+    }
+
+    /// The bar with `segments` in order; segments with nothing to show are left out.
+    pub fn line(&self, segments: &[Segment]) -> Line<'static> {
+        let dim = Style::default().fg(Color::DarkGray);
+        let mut spans = Vec::new();
+        for &segment in segments {
+            let parts = self.segment(segment);
+            if parts.is_empty() {
+                continue;
+            }
+            spans.push(Span::styled(
+                if spans.is_empty() { " " } else { " │ " },
+                dim,
+            ));
+            spans.extend(parts);
+        }
+        Line::from(spans)
+    }
+
+    fn segment(&self, segment: Segment) -> Vec<Span<'static>> {
+        match segment {
+            Segment::Mode => {
+                let color = match self.mode {
+                    "normal" => Color::Cyan,
+                    "search" => Color::Magenta,
+                    _ => Color::Yellow,
+                };
+                vec![Span::styled(
+                    self.mode.to_uppercase(),
+                    Style::default().fg(color).bold(),
+                )]
+            }
+            Segment::Cwd => vec![Span::raw(self.cwd.clone())],
+            Segment::Git => {
+                let Some(branch) = &self.branch else {
+                    return Vec::new();
+                };
+                let mut spans = vec![Span::styled(
+                    branch.head.clone(),
+                    Style::default().fg(Color::Green),
+                )];
+                if branch.ahead > 0 {
+                    spans.push(Span::raw(format!(" ↑{}", branch.ahead)));
+                }
+                if branch.behind > 0 {
+                    spans.push(Span::raw(format!(" ↓{}", branch.behind)));
+                }
+                if self.changed > 0 {
+                    spans.push(Span::styled(
+                        format!(" ~{}", self.changed),
+                        Style::default().fg(Color::Rgb(230, 180, 60)),
+                    ));
+                }
+                if self.untracked > 0 {
+                    spans.push(Span::styled(
+                        format!(" ?{}", self.untracked),
+                        Style::default().fg(Color::Rgb(120, 200, 120)),
+                    ));
+                }
+                spans
+            }
+            Segment::Process => {
+                let Some(pid) = self.pid else {
+                    return Vec::new();
+                };
+                let mut text = format!("pid {pid}");
+                if let Some(run_time) = self.run_time {
+                    text.push_str(&format!(" · {}", format_run_time(run_time)));
+                }
+                vec![Span::raw(text)]
+            }
+            Segment::Scroll => match self.scroll {
+                Some((offset, total)) => vec![Span::styled(
+                    format!("scrolled {offset}/{total}"),
+                    Style::default().fg(Color::Yellow),
+                )],
+                None => Vec::new(),
+            },
+        }
+    }
+}
+
+/// `cwd` relative to `root`: `.` at the root, the full path outside it.
+fn relative_cwd(root: &Path, cwd: &Path) -> String {
+    match cwd.strip_prefix(root) {
+        Ok(rel) if rel.as_os_str().is_empty() => ".".to_string(),
+        Ok(rel) => rel.to_string_lossy().to_string(),
+        Err(_) => cwd.to_string_lossy().to_string(),
+    }
+}
+
+/// `42s`, `12m05s`, `3h07m`.
+pub fn format_run_time(run_time: Duration) -> String {
+    let secs = run_time.as_secs();
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(line: &Line) -> String {
+        line.spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn segments_parse_in_order() {
+        assert_eq!(
+            Segment::parse_list("git, mode,git").unwrap(),
+            [Segment::Git, Segment::Mode]
+        );
+        assert!(Segment::parse_list("").unwrap().is_empty());
+        assert!(Segment::parse_list("mode,clock").is_err());
+    }
+
+    #[test]
+    fn bar_skips_empty_segments() {
+        let info = StatusInfo {
+            mode: "tree",
+            cwd: relative_cwd(Path::new("/proj"), Path::new("/proj/src/ui")),
+            branch: Some(BranchInfo {
+                head: "main".into(),
+                ahead: 2,
+                behind: 0,
+            }),
+            changed: 3,
+            untracked: 0,
+            pid: Some(4242),
+            run_time: Some(Duration::from_secs(725)),
+            scroll: None,
+        };
+        assert_eq!(
+            text(&info.line(&Segment::ALL)),
+            " TREE │ src/ui │ main ↑2 ~3 │ pid 4242 · 12m05s"
+        );
+
+        let info = StatusInfo {
+            mode: "normal",
+            cwd: relative_cwd(Path::new("/proj"), Path::new("/proj")),
+            scroll: Some((40, 900)),
+            ..Default::default()
+        };
+        assert_eq!(
+            text(&info.line(&[Segment::Scroll, Segment::Git, Segment::Cwd])),
+            " scrolled 40/900 │ ."
+        );
+    }
+
+    #[test]
+    fn run_times_are_short() {
+        assert_eq!(format_run_time(Duration::from_secs(42)), "42s");
+        assert_eq!(
+            format_run_time(Duration::from_secs(3600 * 3 + 420)),
+            "3h07m"
+        );
+    }
+}
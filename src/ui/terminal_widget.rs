@@ -3,9 +3,13 @@ use ratatui::{prelude::*, widgets::Widget};
 use crate::app::Selection;
 use crate::terminal::TerminalPane;
 
+const SEARCH_MATCH_BG: Color = Color::Rgb(90, 70, 0);
+
 pub struct TerminalWidget<'a> {
     terminal: &'a TerminalPane,
     selection: Option<&'a Selection>,
+    // Scrollback row of the current search match
+    highlight: Option<usize>,
 }
 
 impl<'a> TerminalWidget<'a> {
@@ -13,9 +17,16 @@ impl<'a> TerminalWidget<'a> {
         Self {
             terminal,
             selection,
+            highlight: None,
         }
     }
 
+    /// Mark a scrollback row, shown while the view is scrolled back.
+    pub fn highlight(mut self, row: Option<usize>) -> Self {
+        self.highlight = row;
+        self
+    }
+
     /// Check if a given (col, row) is within the selection range.
     fn is_selected(&self, col: u16, row: u16) -> bool {
         let sel = match self.selection {
@@ -97,6 +108,7 @@ impl<'a> Widget for TerminalWidget<'a> {
                     grid.get(line_idx - scrollback.len())
                 };
 
+                let highlighted = self.highlight == Some(line_idx);
                 if let Some(row) = row_data {
                     for (col_idx, cell) in row.iter().enumerate().take(cols_to_render) {
                         if cell.ch.is_empty() {
@@ -109,6 +121,8 @@ impl<'a> Widget for TerminalWidget<'a> {
                                 buf_cell.set_symbol(&cell.ch);
                                 let style = if self.is_selected(col_idx as u16, screen_row as u16) {
                                     cell.style.add_modifier(Modifier::REVERSED)
+                                } else if highlighted {
+                                    cell.style.bg(SEARCH_MATCH_BG)
                                 } else {
                                     cell.style
                                 };
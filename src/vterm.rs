@@ -43,10 +43,15 @@ pub struct VirtualTerminal {
     current_style: Style,
     scrollback: VecDeque<Vec<Cell>>,
     scroll_offset: usize,
+    // Lines ever pushed into the scrollback, so rows keep a stable number as old ones drop off
+    lines_scrolled: usize,
+    // Line the cursor was on when input was last submitted
+    prompt_line: Option<usize>,
     saved_cursor: Option<CursorState>,
     // Alternate screen buffer (used by full-screen apps like vim, less, etc.)
     saved_grid: Option<Vec<Vec<Cell>>>,
     saved_scrollback: Option<VecDeque<Vec<Cell>>>,
+    saved_lines_scrolled: Option<usize>,
     saved_main_cursor: Option<CursorState>,
     parser: Option<vte::Parser>,
     // Scroll region (DECSTBM): top..bottom (0-indexed, bottom is exclusive)
@@ -60,6 +65,8 @@ pub struct VirtualTerminal {
     clipboard_requests: Vec<String>,
     // Whether the child process has enabled focus event tracking (DECSET 1004)
     focus_tracking: bool,
+    // Whether the child process has enabled bracketed paste (DECSET 2004)
+    bracketed_paste: bool,
 }
 
 const MAX_SCROLLBACK: usize = 1000;
@@ -74,9 +81,12 @@ impl VirtualTerminal {
             current_style: Style::default(),
             scrollback: VecDeque::new(),
             scroll_offset: 0,
+            lines_scrolled: 0,
+            prompt_line: None,
             saved_cursor: None,
             saved_grid: None,
             saved_scrollback: None,
+            saved_lines_scrolled: None,
             saved_main_cursor: None,
             parser: Some(vte::Parser::new()),
             scroll_top: 0,
@@ -85,6 +95,7 @@ impl VirtualTerminal {
             reported_cwd: None,
             clipboard_requests: Vec::new(),
             focus_tracking: false,
+            bracketed_paste: false,
         }
     }
 
@@ -113,6 +124,11 @@ impl VirtualTerminal {
         self.focus_tracking
     }
 
+    /// Whether the child process has enabled bracketed paste (DECSET 2004)
+    pub fn bracketed_paste_enabled(&self) -> bool {
+        self.bracketed_paste
+    }
+
     fn make_grid(cols: usize, rows: usize) -> Vec<Vec<Cell>> {
         vec![vec![Cell::default(); cols]; rows]
     }
@@ -187,18 +203,71 @@ impl VirtualTerminal {
         if row >= self.rows {
             return String::new();
         }
-        self.grid[row]
+        cells_text(&self.grid[row])
+    }
+
+    /// Session line numbers (as used by `scrollback_row`) of the scrollback rows
+    /// containing `query`, oldest first. Unlike scrollback indices they stay put
+    /// as new output pushes rows into the scrollback.
+    /// Matching ignores case unless the query contains an uppercase letter.
+    pub fn scrollback_matches(&self, query: &str) -> Vec<usize> {
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let case_sensitive = query.chars().any(char::is_uppercase);
+        let query = if case_sensitive {
+            query.to_string()
+        } else {
+            query.to_lowercase()
+        };
+        self.scrollback
             .iter()
-            .map(|c| {
-                if c.ch.is_empty() || c.ch == " " {
-                    " ".to_string()
+            .enumerate()
+            .filter(|(_, row)| {
+                let text = cells_text(row);
+                if case_sensitive {
+                    text.contains(&query)
                 } else {
-                    c.ch.clone()
+                    text.to_lowercase().contains(&query)
                 }
             })
-            .collect::<String>()
-            .trim_end()
-            .to_string()
+            .map(|(i, _)| self.first_scrollback_line() + i)
+            .collect()
+    }
+
+    /// The current scrollback index of a session line, or None once the
+    /// line has dropped out of the scrollback.
+    pub fn scrollback_row(&self, line: usize) -> Option<usize> {
+        let row = line.checked_sub(self.first_scrollback_line())?;
+        (row < self.scrollback.len()).then_some(row)
+    }
+
+    /// Session line number of the oldest row still in the scrollback.
+    fn first_scrollback_line(&self) -> usize {
+        self.lines_scrolled - self.scrollback.len()
+    }
+
+    /// Remember the cursor's line as the one where input was just submitted.
+    pub fn mark_prompt(&mut self) {
+        self.prompt_line = Some(self.lines_scrolled + self.cursor.y);
+    }
+
+    /// Text printed below the line marked by `mark_prompt` and above the cursor,
+    /// without surrounding blank lines. None if nothing was marked or printed.
+    pub fn output_since_prompt(&self) -> Option<String> {
+        let mark = self.prompt_line?;
+        let first = self.first_scrollback_line();
+        let cursor = self.lines_scrolled + self.cursor.y;
+        let rows = self.scrollback.iter().chain(self.grid.iter());
+        let lines: Vec<String> = rows
+            .enumerate()
+            .map(|(i, row)| (first + i, row))
+            .filter(|&(line, _)| line > mark && line < cursor)
+            .map(|(_, row)| cells_text(row))
+            .collect();
+        let start = lines.iter().position(|l| !l.is_empty())?;
+        let end = lines.iter().rposition(|l| !l.is_empty())?;
+        Some(lines[start..=end].join("\n"))
     }
 
     pub fn rows(&self) -> usize {
@@ -214,6 +283,7 @@ impl VirtualTerminal {
         // Only push to scrollback if scrolling from the very top of the screen
         if self.scroll_top == 0 {
             self.scrollback.push_back(removed);
+            self.lines_scrolled += 1;
             if self.scrollback.len() > MAX_SCROLLBACK {
                 self.scrollback.pop_front();
             }
@@ -540,6 +610,7 @@ impl VirtualTerminal {
     fn enter_alternate_screen(&mut self) {
         self.saved_grid = Some(self.grid.clone());
         self.saved_scrollback = Some(self.scrollback.clone());
+        self.saved_lines_scrolled = Some(self.lines_scrolled);
         self.saved_main_cursor = Some(self.cursor.clone());
         self.grid = Self::make_grid(self.cols, self.rows);
         self.scrollback.clear();
@@ -553,12 +624,24 @@ impl VirtualTerminal {
         if let Some(scrollback) = self.saved_scrollback.take() {
             self.scrollback = scrollback;
         }
+        if let Some(lines) = self.saved_lines_scrolled.take() {
+            self.lines_scrolled = lines;
+        }
         if let Some(cursor) = self.saved_main_cursor.take() {
             self.cursor = cursor;
         }
     }
 }
 
+/// A row's text with trailing blanks removed.
+fn cells_text(row: &[Cell]) -> String {
+    row.iter()
+        .map(|c| if c.ch.is_empty() { " " } else { c.ch.as_str() })
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
 fn percent_decode(input: &str) -> String {
     let mut result = Vec::new();
     let bytes = input.as_bytes();
@@ -801,45 +884,46 @@ impl Perform for VirtualTerminal {
                 self.parse_sgr(params);
             }
             // DECSET / DECRST (private modes)
-            'h' | 'l' => {
-                if intermediates == b"?" {
-                    let set = action == 'h';
-                    for &code in &p {
-                        match code {
-                            25 => {
-                                // DECTCEM - cursor visibility
-                                self.cursor.visible = set;
-                            }
-                            1049 => {
-                                // Alternate screen buffer (with save/restore cursor)
-                                if set {
-                                    self.enter_alternate_screen();
-                                } else {
-                                    self.leave_alternate_screen();
-                                }
-                            }
-                            1047 | 47 => {
-                                // Alternate screen (without save/restore cursor)
-                                if set {
-                                    self.enter_alternate_screen();
-                                } else {
-                                    self.leave_alternate_screen();
-                                }
-                            }
-                            // 1004 = Focus event tracking
-                            1004 => {
-                                self.focus_tracking = set;
+            'h' | 'l' if intermediates == b"?" => {
+                let set = action == 'h';
+                for &code in &p {
+                    match code {
+                        25 => {
+                            // DECTCEM - cursor visibility
+                            self.cursor.visible = set;
+                        }
+                        1049 => {
+                            // Alternate screen buffer (with save/restore cursor)
+                            if set {
+                                self.enter_alternate_screen();
+                            } else {
+                                self.leave_alternate_screen();
                             }
-                            // Modes we acknowledge but don't need special handling for:
-                            // 1 = DECCKM (cursor key mode), 7 = DECAWM (auto-wrap),
-                            // 12 = blinking cursor, 1000/1002/1003/1006 = mouse modes,
-                            // 2004 = bracketed paste
-                            1 | 7 | 12 | 1000 | 1002 | 1003 | 1006 | 2004 => {
-                                // Silently accept — these affect input handling,
-                                // not our grid rendering
+                        }
+                        1047 | 47 => {
+                            // Alternate screen (without save/restore cursor)
+                            if set {
+                                self.enter_alternate_screen();
+                            } else {
+                                self.leave_alternate_screen();
                             }
-                            _ => {}
                         }
+                        // 1004 = Focus event tracking
+                        1004 => {
+                            self.focus_tracking = set;
+                        }
+                        // 2004 = Bracketed paste
+                        2004 => {
+                            self.bracketed_paste = set;
+                        }
+                        // Modes we acknowledge but don't need special handling for:
+                        // 1 = DECCKM (cursor key mode), 7 = DECAWM (auto-wrap),
+                        // 12 = blinking cursor, 1000/1002/1003/1006 = mouse modes
+                        1 | 7 | 12 | 1000 | 1002 | 1003 | 1006 => {
+                            // Silently accept — these affect input handling,
+                            // not our grid rendering
+                        }
+                        _ => {}
                     }
                 }
             }
@@ -853,16 +937,14 @@ impl Perform for VirtualTerminal {
                 }
             }
             // DECSTBM - Set Scrolling Region (top;bottom)
-            'r' => {
-                if intermediates.is_empty() {
-                    let top = p.first().copied().unwrap_or(1).max(1) as usize - 1;
-                    let bottom = p.get(1).copied().unwrap_or(self.rows as u16) as usize;
-                    self.scroll_top = top.min(self.rows);
-                    self.scroll_bottom = bottom.min(self.rows).max(self.scroll_top + 1);
-                    // DECSTBM resets cursor to home
-                    self.cursor.x = 0;
-                    self.cursor.y = 0;
-                }
+            'r' if intermediates.is_empty() => {
+                let top = p.first().copied().unwrap_or(1).max(1) as usize - 1;
+                let bottom = p.get(1).copied().unwrap_or(self.rows as u16) as usize;
+                self.scroll_top = top.min(self.rows);
+                self.scroll_bottom = bottom.min(self.rows).max(self.scroll_top + 1);
+                // DECSTBM resets cursor to home
+                self.cursor.x = 0;
+                self.cursor.y = 0;
             }
             // DSR - Device Status Report
             'n' => {
@@ -1184,6 +1266,16 @@ mod tests {
         assert!(!vt.focus_tracking_enabled());
     }
 
+    #[test]
+    fn test_bracketed_paste_mode() {
+        let mut vt = VirtualTerminal::new(80, 24);
+        assert!(!vt.bracketed_paste_enabled());
+        vt.feed(b"\x1b[?2004h");
+        assert!(vt.bracketed_paste_enabled());
+        vt.feed(b"\x1b[?2004l");
+        assert!(!vt.bracketed_paste_enabled());
+    }
+
     #[test]
     fn test_feed_with_zero_sized_terminal_does_not_panic() {
         let mut vt = VirtualTerminal::new(80, 24);
@@ -1193,4 +1285,47 @@ mod tests {
         assert_eq!(vt.cols, 0);
         assert_eq!(vt.rows, 0);
     }
+
+    #[test]
+    fn finds_text_in_scrollback() {
+        let mut vt = VirtualTerminal::new(10, 2);
+        vt.feed(b"error one\r\nok\r\nError two\r\nlast\r\n");
+        assert_eq!(vt.scrollback.len(), 3);
+        assert_eq!(vt.scrollback_matches("error"), [0, 2]);
+        assert_eq!(vt.scrollback_matches("Error"), [2]);
+        assert!(vt.scrollback_matches("").is_empty());
+    }
+
+    #[test]
+    fn scrollback_matches_survive_new_output() {
+        let mut vt = VirtualTerminal::new(10, 1);
+        vt.feed(b"needle\r\n");
+        for _ in 0..MAX_SCROLLBACK - 1 {
+            vt.feed(b"hay\r\n");
+        }
+        let matches = vt.scrollback_matches("needle");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(vt.scrollback_row(matches[0]), Some(0));
+
+        vt.feed(b"hay\r\n");
+        assert_eq!(vt.scrollback_row(matches[0]), None);
+        assert!(vt.scrollback_matches("needle").is_empty());
+    }
+
+    #[test]
+    fn output_since_prompt_spans_scrolled_lines() {
+        let mut vt = VirtualTerminal::new(10, 3);
+        assert_eq!(vt.output_since_prompt(), None);
+        vt.feed(b"$ run");
+        vt.mark_prompt();
+        vt.feed(b"\r\none\r\n\r\ntwo\r\nthree\r\n$ ");
+        assert_eq!(
+            vt.output_since_prompt().as_deref(),
+            Some("one\n\ntwo\nthree")
+        );
+
+        // Nothing printed yet after the next prompt
+        vt.mark_prompt();
+        assert_eq!(vt.output_since_prompt(), None);
+    }
 }
@@ -0,0 +1,88 @@
+use cltree::asciicast::{Cast, EventKind, Header, Recorder};
+use cltree::vterm::VirtualTerminal;
+use ratatui::prelude::*;
+use std::path::{Path, PathBuf};
+
+/// Feed every event of a recording through a fresh virtual terminal.
+fn play(cast: &Cast) -> VirtualTerminal {
+    let mut vt = VirtualTerminal::new(cast.header.width as usize, cast.header.height as usize);
+    for event in &cast.events {
+        match event.kind {
+            EventKind::Output => vt.feed(event.data.as_bytes()),
+            EventKind::Resize => {
+                let (cols, rows) = event.resize().expect("malformed resize event");
+                vt.resize(cols as usize, rows as usize);
+            }
+            EventKind::Input | EventKind::Marker => {}
+        }
+    }
+    vt
+}
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+#[test]
+fn test_fixture_plays_through_vterm() {
+    let cast = Cast::read(&fixture("prompt.cast")).expect("failed to read fixture");
+    assert_eq!((cast.header.width, cast.header.height), (40, 5));
+    assert_eq!(cast.events.len(), 6);
+
+    let vt = play(&cast);
+    assert_eq!(vt.row_text(0), "> hello");
+    assert_eq!(vt.row_text(1), "✓ done");
+    assert_eq!(vt.cols(), 30);
+    assert_eq!(vt.grid()[1][0].style.fg, Some(Color::Green));
+    assert_eq!(vt.reported_cwd(), Some(Path::new("/tmp/project")));
+}
+
+#[test]
+fn test_recorder_output_round_trips() {
+    let tmp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = tmp_dir.path().join("session.cast");
+    let header = Header {
+        width: 20,
+        height: 3,
+        root: Some(PathBuf::from("/work")),
+        tree: vec!["src/".into(), "src/lib.rs".into()],
+        ..Header::default()
+    };
+
+    {
+        let mut recorder = Recorder::create(&path, &header, false).expect("create recorder");
+        recorder.output(b"caf\xc3");
+        // Second half of a UTF-8 sequence split across reads
+        recorder.output(b"\xa9 \x1b[7mok\x1b[0m");
+        recorder.input(b"ignored without --record-input");
+        recorder.resize(25, 3);
+    }
+
+    let cast = Cast::read(&path).expect("failed to read recording");
+    assert_eq!(cast.header.root, header.root);
+    assert_eq!(cast.header.tree, header.tree);
+    assert!(cast.events.iter().all(|e| e.kind != EventKind::Input));
+    assert!(cast.events.windows(2).all(|w| w[0].time <= w[1].time));
+
+    let vt = play(&cast);
+    assert_eq!(vt.row_text(0), "café ok");
+    assert_eq!(vt.cols(), 25);
+}
+
+#[test]
+fn test_recorder_keeps_input_when_enabled() {
+    let tmp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = tmp_dir.path().join("input.cast");
+    {
+        let mut recorder =
+            Recorder::create(&path, &Header::default(), true).expect("create recorder");
+        recorder.input(b"/help\r");
+    }
+
+    let cast = Cast::read(&path).expect("failed to read recording");
+    assert_eq!(cast.events.len(), 1);
+    assert_eq!(cast.events[0].kind, EventKind::Input);
+    assert_eq!(cast.events[0].data, "/help\r");
+}
@@ -0,0 +1,81 @@
+use cltree::tree::FileTree;
+use std::fs;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+/// 100 top-level directories x 10 subdirectories x 100 files.
+fn generate_tree(root: &std::path::Path) -> usize {
+    let mut files = 0;
+    for a in 0..100 {
+        for b in 0..10 {
+            let dir = root.join(format!("pkg{a:03}")).join(format!("mod{b:02}"));
+            fs::create_dir_all(&dir).expect("failed to create directory");
+            for c in 0..100 {
+                fs::write(dir.join(format!("file{c:03}.rs")), "").expect("failed to write file");
+                files += 1;
+            }
+        }
+    }
+    files
+}
+
+/// Upper bounds for the operations lazy loading is meant to keep fast. They
+/// are far above the measured times so a slow CI machine or a debug build
+/// passes, while a return to reading the whole tree up front does not.
+const INITIAL_BUILD_LIMIT: Duration = Duration::from_secs(2);
+const EXPAND_ONE_LIMIT: Duration = Duration::from_millis(500);
+
+#[test]
+#[ignore = "writes 100k files; run with cargo test --release --test tree_perf_test -- --ignored --nocapture"]
+fn tree_build_on_100k_files_stays_lazy() {
+    let temp = TempDir::new().expect("failed to create temp directory");
+    let root = temp.path().canonicalize().unwrap();
+    let started = Instant::now();
+    let files = generate_tree(&root);
+    println!("generated {files} files in {:?}", started.elapsed());
+
+    let started = Instant::now();
+    let mut tree = FileTree::new(&root, false, 10).expect("failed to build tree");
+    let initial = started.elapsed();
+    // Measure a full expansion rather than the summarized one
+    tree.node_budget = None;
+    tree.child_limit = None;
+    let visible = tree.nodes().len();
+
+    let started = Instant::now();
+    tree.refresh();
+    let refresh = started.elapsed();
+
+    // Expanding a directory reads one listing
+    let collapsed = tree
+        .nodes()
+        .iter()
+        .position(|n| n.is_dir && !tree.is_expanded(n))
+        .expect("big trees start partly collapsed");
+    let started = Instant::now();
+    tree.toggle(collapsed);
+    let expand_one = started.elapsed();
+
+    let started = Instant::now();
+    tree.expand_all();
+    let expand_all = started.elapsed();
+
+    println!("initial build: {initial:?} ({visible} rows)");
+    println!("refresh: {refresh:?}");
+    println!("expand one directory: {expand_one:?}");
+    println!("expand all: {expand_all:?} ({} rows)", tree.nodes().len());
+
+    assert!(
+        visible < files / 10,
+        "initial build should not show everything"
+    );
+    assert!(
+        initial < INITIAL_BUILD_LIMIT,
+        "initial build took {initial:?}, limit {INITIAL_BUILD_LIMIT:?}"
+    );
+    assert!(
+        expand_one < EXPAND_ONE_LIMIT,
+        "expanding one directory took {expand_one:?}, limit {EXPAND_ONE_LIMIT:?}"
+    );
+    assert_eq!(tree.nodes().len(), 1 + 100 + 100 * 10 + files);
+}